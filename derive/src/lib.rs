@@ -0,0 +1,31 @@
+#![deny(missing_docs)]
+// the crate is named after ABC-ECS
+#![allow(non_snake_case)]
+//! The derive macros of ABC-ECS, they are re-exported by the ABC_ECS crate, so use them from there
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Implements Resource with as_any and as_any_mut returning self and an empty update, see ABC_ECS::Resource
+#[proc_macro_derive(Resource)]
+pub fn derive_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::ABC_ECS::Resource for #name #type_generics #where_clause {
+            fn update(&mut self) {}
+
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+                self
+            }
+        }
+    }
+    .into()
+}
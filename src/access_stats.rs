@@ -0,0 +1,139 @@
+use crate::*;
+#[cfg(feature = "access-stats")]
+use std::sync::Mutex;
+
+/// Counts how many times each component type is read and written
+/// Accesses can come from the parallel phase, so the counts are behind a Mutex
+#[cfg(feature = "access-stats")]
+pub(crate) struct AccessStats {
+    // where counts[TypeId::of::<T>()] = (type name, reads, writes)
+    counts: Mutex<FxHashMap<TypeId, (&'static str, u64, u64)>>,
+}
+
+#[cfg(feature = "access-stats")]
+impl AccessStats {
+    pub(crate) fn new() -> Self {
+        AccessStats {
+            counts: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    fn record<T: Component>(&self, write: bool) {
+        let mut counts = self.counts.lock().unwrap();
+        let (_, reads, writes) =
+            counts
+                .entry(TypeId::of::<T>())
+                .or_insert((std::any::type_name::<T>(), 0, 0));
+        if write {
+            *writes += 1;
+        } else {
+            *reads += 1;
+        }
+    }
+
+    pub(crate) fn reset(&self) {
+        self.counts.lock().unwrap().clear();
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets how many times each component type was read and written since the last World::run started,
+    /// as (TypeId::of::<T>(), type name, reads, writes) sorted by type name
+    /// Frequently accessed components are good candidates for a more cache friendly layout
+    #[cfg(feature = "access-stats")]
+    pub fn access_stats(&self) -> Vec<(TypeId, &'static str, u64, u64)> {
+        let mut access_stats = self
+            .access_stats
+            .counts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(type_id, (name, reads, writes))| (*type_id, *name, *reads, *writes))
+            .collect::<Vec<(TypeId, &'static str, u64, u64)>>();
+        access_stats.sort_by_key(|(_, name, _, _)| *name);
+        access_stats
+    }
+
+    /// Resets all the access counts to zero, World::run does this at the start of every frame
+    #[cfg(feature = "access-stats")]
+    pub fn reset_access_stats(&self) {
+        self.access_stats.reset();
+    }
+
+    // these compile to nothing without the access-stats feature
+    #[inline(always)]
+    pub(crate) fn record_read<T: Component>(&self) {
+        #[cfg(feature = "access-stats")]
+        self.access_stats.record::<T>(false);
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_write<T: Component>(&self) {
+        #[cfg(feature = "access-stats")]
+        self.access_stats.record::<T>(true);
+    }
+}
+
+#[cfg(all(test, feature = "access-stats"))]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity {
+        x: f32,
+    }
+
+    struct MovementSystem {}
+
+    impl System for MovementSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            for entity in engine.get_entities() {
+                let (position, velocity) =
+                    engine.get_components_mut::<(Position, Velocity)>(entity);
+                position.x += velocity.x;
+            }
+        }
+    }
+
+    #[test]
+    fn test_access_stats() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let entity =
+            entities_and_components.add_entity_with((Position { x: 0.0 }, Velocity { x: 1.0 }));
+        entities_and_components.add_entity_with((Position { x: 0.0 }, Velocity { x: 2.0 }));
+
+        entities_and_components.try_get_component::<Position>(entity);
+        entities_and_components.get_components::<(Position, Velocity)>(entity);
+        entities_and_components.try_get_component_mut::<Velocity>(entity);
+
+        let position_name = std::any::type_name::<Position>();
+        let velocity_name = std::any::type_name::<Velocity>();
+        assert_eq!(
+            entities_and_components.access_stats(),
+            vec![
+                (TypeId::of::<Position>(), position_name, 2, 0),
+                (TypeId::of::<Velocity>(), velocity_name, 1, 1),
+            ]
+        );
+
+        // the counts are reset every frame
+        world.add_system(MovementSystem {});
+        world.run();
+        let (position,) = world
+            .entities_and_components
+            .get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 1.0);
+        assert_eq!(
+            world.entities_and_components.access_stats(),
+            vec![
+                (TypeId::of::<Position>(), position_name, 1, 2),
+                (TypeId::of::<Velocity>(), velocity_name, 0, 2),
+            ]
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use crate::*;
+use std::hash::{Hash, Hasher};
+
+pub(crate) type ArchetypeEmptyHook = Box<dyn Fn(&[TypeId]) + Send + Sync>;
+
+// an entity, its sorted component types before the change, and after it
+pub(crate) type ArchetypeTransition = (Entity, Vec<TypeId>, Vec<TypeId>);
+
+/// Identifies an archetype, a unique set of component types
+/// Two entities with the same component types always have the same ArchetypeId
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ArchetypeId(u64);
+
+impl ArchetypeId {
+    fn of(component_types: &[TypeId]) -> Self {
+        let mut hasher = rustc_hash::FxHasher::default();
+        component_types.hash(&mut hasher);
+        ArchetypeId(hasher.finish())
+    }
+}
+
+// every archetype that has been seen, and which one every entity is in
+#[derive(Default)]
+pub(crate) struct ArchetypeTable {
+    // in the order they were first seen, an archetype is kept after its last entity is gone
+    archetypes: Vec<Archetype>,
+    // the index of every archetype in archetypes, by its sorted component types
+    indices: FxHashMap<Vec<TypeId>, usize>,
+    // the index in archetypes of the archetype of every entity
+    entity_archetypes: SecondaryMap<DefaultKey, usize>,
+}
+
+struct Archetype {
+    id: ArchetypeId,
+    // sorted, as TypeId::of::<T>()
+    component_types: Vec<TypeId>,
+    entity_count: usize,
+}
+
+impl ArchetypeTable {
+    // moves the entity to the archetype with these component types, adding it if it is new
+    fn place(&mut self, entity_id: DefaultKey, component_types: Vec<TypeId>) {
+        let index = match self.indices.get(&component_types) {
+            Some(index) => *index,
+            None => {
+                let index = self.archetypes.len();
+                self.archetypes.push(Archetype {
+                    id: ArchetypeId::of(&component_types),
+                    component_types: component_types.clone(),
+                    entity_count: 0,
+                });
+                self.indices.insert(component_types, index);
+                index
+            }
+        };
+        if let Some(old_index) = self.entity_archetypes.insert(entity_id, index) {
+            self.archetypes[old_index].entity_count -= 1;
+        }
+        self.archetypes[index].entity_count += 1;
+    }
+
+    pub(crate) fn entity_removed(&mut self, entity_id: DefaultKey) {
+        if let Some(index) = self.entity_archetypes.remove(entity_id) {
+            self.archetypes[index].entity_count -= 1;
+        }
+    }
+
+    // moves every entity's archetype to the entity it was moved to by compact
+    pub(crate) fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.entity_archetypes, entity_ids);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// returns an iterator over every distinct set of component types in the world, in the order they first appeared,
+    /// with the archetype's id, its sorted component types (TypeId::of::<T>()), and the number of entities that have exactly those components
+    /// The archetypes are kept up to date as entities change, so this doesn't go through the entities
+    pub fn archetypes(&self) -> impl Iterator<Item = (ArchetypeId, &[TypeId], usize)> {
+        self.archetype_table
+            .archetypes
+            .iter()
+            .filter(|archetype| archetype.entity_count > 0)
+            .map(|archetype| {
+                (
+                    archetype.id,
+                    archetype.component_types.as_slice(),
+                    archetype.entity_count,
+                )
+            })
+    }
+
+    /// Adds a callback that is called with the sorted component types (TypeId::of::<T>()) of an archetype
+    /// whenever an entity is removed and no other entity has exactly those components anymore
+    /// Removing a component from an entity does not count, only removing the entity, including with transfer_with
+    /// This is meant for storage backends and pools that want to reclaim the memory of an unused archetype
+    pub fn on_archetype_empty(&mut self, f: impl Fn(&[TypeId]) + Send + Sync + 'static) {
+        self.archetype_empty_hooks.push(Box::new(f));
+    }
+
+    /// Starts recording every time an entity gains or loses a component, to be read with drain_archetype_transitions
+    /// Recording is off by default, since it allocates the component types of the entity on every add and remove
+    pub fn track_archetype_transitions(&mut self) {
+        self.archetype_transitions.get_or_insert_with(Vec::new);
+    }
+
+    /// Takes the archetype changes recorded since the last drain, oldest first
+    /// Each one is the entity with its sorted component types (TypeId::of::<T>()) before and after the change,
+    /// spawning an entity with several components or moving it in with transfer_with records a transition for each component,
+    /// swap_all_components records one transition for each of the two entities if their components differed
+    /// Overwriting a component and removing the entity are not transitions
+    /// returns nothing unless track_archetype_transitions has been called
+    pub fn drain_archetype_transitions(&mut self) -> Vec<(Entity, Vec<TypeId>, Vec<TypeId>)> {
+        self.archetype_transitions
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    // records that the component was just added to or removed from the entity
+    pub(crate) fn archetype_transition(
+        &mut self,
+        entity: Entity,
+        component_type: TypeId,
+        added: bool,
+    ) {
+        self.archetype_generation += 1;
+        self.refresh_archetype(entity);
+        if self.archetype_transitions.is_none() {
+            return;
+        }
+        let Some(components) = self.components.get(entity.entity_id) else {
+            return;
+        };
+        // components with a custom storage are not in the map
+        let mut new_types = self.component_types_of(components);
+        new_types.retain(|type_id| *type_id != component_type);
+        let mut old_types = new_types.clone();
+        if added {
+            new_types.push(component_type);
+            new_types.sort();
+        } else {
+            old_types.push(component_type);
+            old_types.sort();
+        }
+        if let Some(transitions) = &mut self.archetype_transitions {
+            transitions.push((entity, old_types, new_types));
+        }
+    }
+
+    // the sorted component types of the entity, only if archetype transitions are being recorded
+    pub(crate) fn archetype_types(&self, entity: Entity) -> Option<Vec<TypeId>> {
+        self.archetype_transitions.as_ref()?;
+        let components = self.components.get(entity.entity_id)?;
+        Some(self.component_types_of(components))
+    }
+
+    // records that the entity's components were replaced all at once, old_types is from archetype_types before
+    pub(crate) fn archetype_replaced(&mut self, entity: Entity, old_types: Vec<TypeId>) {
+        let Some(new_types) = self.archetype_types(entity) else {
+            return;
+        };
+        if new_types == old_types {
+            return;
+        }
+        if let Some(transitions) = &mut self.archetype_transitions {
+            transitions.push((entity, old_types, new_types));
+        }
+    }
+
+    // records an entity that was moved in with all of its components at once, like it got them one at a time
+    pub(crate) fn archetype_entered(&mut self, entity: Entity) {
+        self.archetype_generation += 1;
+        self.refresh_archetype(entity);
+        if self.archetype_transitions.is_none() {
+            return;
+        }
+        let Some(components) = self.components.get(entity.entity_id) else {
+            return;
+        };
+        let new_types = self.component_types_of(components);
+        if let Some(transitions) = &mut self.archetype_transitions {
+            for index in 0..new_types.len() {
+                transitions.push((
+                    entity,
+                    new_types[..index].to_vec(),
+                    new_types[..=index].to_vec(),
+                ));
+            }
+        }
+    }
+
+    // puts the entity in the archetype of the components it has now, called after every structural change to it
+    pub(crate) fn refresh_archetype(&mut self, entity: Entity) {
+        let Some(components) = self.components.get(entity.entity_id) else {
+            return;
+        };
+        let component_types = self.component_types_of(components);
+        self.archetype_table
+            .place(entity.entity_id, component_types);
+    }
+
+    /// calls the archetype empty hooks if the removed entity with these components was the last of its archetype
+    /// the entity has to be removed from the index already
+    pub(crate) fn archetype_left(&self, components: &Map<dyn Any>) {
+        if self.archetype_empty_hooks.is_empty() {
+            return;
+        }
+
+        let storage_type_ids = components.as_raw().keys().collect::<Vec<&TypeId>>();
+        let same_archetype = |other: &Map<dyn Any>| {
+            other.as_raw().len() == storage_type_ids.len()
+                && storage_type_ids
+                    .iter()
+                    .all(|type_id| other.as_raw().contains_key(*type_id))
+        };
+
+        // only the entities with the rarest of the components can have exactly the same components
+        let rarest = storage_type_ids
+            .iter()
+            .map(|type_id| self.entities_with_components.get(*type_id))
+            .min_by_key(|entities| entities.map_or(0, |entities| entities.len()));
+        let still_used = match rarest {
+            Some(None) => false,
+            Some(Some(entities)) => entities
+                .keys()
+                .filter_map(|entity_id| self.components.get(entity_id))
+                .any(same_archetype),
+            // an entity without components, any other empty entity has the same archetype
+            None => self.components.values().any(same_archetype),
+        };
+
+        if !still_used {
+            let component_types = self.component_types_of(components);
+            for hook in &self.archetype_empty_hooks {
+                hook(&component_types);
+            }
+        }
+    }
+
+    // the sorted types of the components in the map, as TypeId::of::<T>()
+    fn component_types_of(&self, components: &Map<dyn Any>) -> Vec<TypeId> {
+        let mut component_types = components
+            .as_raw()
+            .keys()
+            .map(|storage_type_id| {
+                // anything not added through add_component_to is reported as it is stored
+                *self
+                    .component_type_ids
+                    .get(storage_type_id)
+                    .unwrap_or(storage_type_id)
+            })
+            .collect::<Vec<TypeId>>();
+        component_types.sort();
+        component_types
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+
+    struct Velocity;
+
+    #[test]
+    fn test_archetypes() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let moving_entities = (0..3)
+            .map(|_| entities_and_components.add_entity_with((Position, Velocity)))
+            .collect::<Vec<Entity>>();
+        for _ in 0..2 {
+            entities_and_components.add_entity_with((Position,));
+        }
+
+        let archetypes = entities_and_components
+            .archetypes()
+            .collect::<Vec<(ArchetypeId, &[TypeId], usize)>>();
+        assert_eq!(archetypes.len(), 2);
+
+        let mut moving_types = vec![TypeId::of::<Position>(), TypeId::of::<Velocity>()];
+        moving_types.sort();
+        let moving = archetypes
+            .iter()
+            .find(|(_, component_types, _)| *component_types == moving_types.as_slice())
+            .unwrap();
+        assert_eq!(moving.2, 3);
+
+        let still = archetypes
+            .iter()
+            .find(|(_, component_types, _)| *component_types == [TypeId::of::<Position>()])
+            .unwrap();
+        assert_eq!(still.2, 2);
+        assert_ne!(moving.0, still.0);
+        let (moving_id, still_id) = (moving.0, still.0);
+
+        // the counts follow the entities as they change
+        entities_and_components.remove_entity(moving_entities[0]);
+        entities_and_components.remove_component_from::<Velocity>(moving_entities[1]);
+        entities_and_components.compact();
+        entities_and_components.add_entity();
+        assert_eq!(
+            entities_and_components
+                .archetypes()
+                .map(|(archetype_id, _, entity_count)| (archetype_id, entity_count))
+                .collect::<Vec<(ArchetypeId, usize)>>(),
+            // spawning passes through the archetypes with fewer components first
+            vec![(ArchetypeId::of(&[]), 1), (still_id, 3), (moving_id, 1)]
+        );
+    }
+
+    #[test]
+    fn test_archetype_transitions() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Position,));
+        // nothing is recorded before tracking starts
+        entities_and_components.track_archetype_transitions();
+        assert!(entities_and_components
+            .drain_archetype_transitions()
+            .is_empty());
+
+        entities_and_components.add_component_to(entity, Velocity);
+        entities_and_components.add_component_to(entity, Velocity);
+        entities_and_components.remove_component_from::<Velocity>(entity);
+        entities_and_components.remove_component_from::<Velocity>(entity);
+
+        let old_types = vec![TypeId::of::<Position>()];
+        let mut new_types = vec![TypeId::of::<Position>(), TypeId::of::<Velocity>()];
+        new_types.sort();
+        assert_eq!(
+            entities_and_components.drain_archetype_transitions(),
+            vec![
+                (entity, old_types.clone(), new_types.clone()),
+                (entity, new_types, old_types),
+            ]
+        );
+        assert!(entities_and_components
+            .drain_archetype_transitions()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_on_archetype_empty() {
+        use std::sync::{Arc, Mutex};
+
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let emptied = Arc::new(Mutex::new(Vec::new()));
+        let hook_emptied = emptied.clone();
+        entities_and_components.on_archetype_empty(move |component_types| {
+            hook_emptied.lock().unwrap().push(component_types.to_vec());
+        });
+
+        let first = entities_and_components.add_entity_with((Position, Velocity));
+        let second = entities_and_components.add_entity_with((Position, Velocity));
+        let unique = entities_and_components.add_entity_with((Position,));
+
+        // another entity still has Position and Velocity
+        entities_and_components.remove_entity(first);
+        assert!(emptied.lock().unwrap().is_empty());
+
+        entities_and_components.remove_entity(unique);
+        assert_eq!(
+            *emptied.lock().unwrap(),
+            vec![vec![TypeId::of::<Position>()]]
+        );
+
+        entities_and_components.remove_entity(second);
+        let mut expected = vec![TypeId::of::<Position>(), TypeId::of::<Velocity>()];
+        expected.sort();
+        assert_eq!(emptied.lock().unwrap()[1], expected);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::*;
+
+/// A loosely typed value stored with set_attr
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttrValue {
+    /// A floating point number
+    Float(f64),
+    /// An integer
+    Int(i64),
+    /// A boolean
+    Bool(bool),
+    /// A string
+    String(String),
+}
+
+impl From<f64> for AttrValue {
+    fn from(value: f64) -> Self {
+        AttrValue::Float(value)
+    }
+}
+
+impl From<i64> for AttrValue {
+    fn from(value: i64) -> Self {
+        AttrValue::Int(value)
+    }
+}
+
+impl From<bool> for AttrValue {
+    fn from(value: bool) -> Self {
+        AttrValue::Bool(value)
+    }
+}
+
+impl From<String> for AttrValue {
+    fn from(value: String) -> Self {
+        AttrValue::String(value)
+    }
+}
+
+impl From<&str> for AttrValue {
+    fn from(value: &str) -> Self {
+        AttrValue::String(value.to_string())
+    }
+}
+
+/// The component holding the attributes of an entity, it is added and removed by set_attr and remove_attr
+/// Entities with attributes can be found by querying for it
+#[derive(Clone)]
+pub struct Attributes(FxHashMap<String, AttrValue>);
+
+impl EntitiesAndComponents {
+    /// Sets an attribute on an entity, replacing the value if the key is already set
+    /// Attributes are for loosely typed data like designer tweakable values, a component is faster for anything accessed every frame
+    /// panics if the entity does not exist
+    pub fn set_attr(&mut self, entity: Entity, key: &str, value: AttrValue) {
+        if let Some(attributes) = self.try_get_component_mut::<Attributes>(entity) {
+            attributes.0.insert(key.to_string(), value);
+            return;
+        }
+
+        let mut attributes = FxHashMap::default();
+        attributes.insert(key.to_string(), value);
+        self.add_component_to(entity, Attributes(attributes));
+    }
+
+    /// Gets an attribute of an entity, None if it isn't set or the entity does not exist
+    pub fn get_attr(&self, entity: Entity, key: &str) -> Option<&AttrValue> {
+        self.inspect_component::<Attributes>(entity)
+            .present()?
+            .0
+            .get(key)
+    }
+
+    /// Removes an attribute from an entity and returns it
+    /// The Attributes component is removed along with the last attribute
+    pub fn remove_attr(&mut self, entity: Entity, key: &str) -> Option<AttrValue> {
+        if !self.does_entity_exist(entity) {
+            return None;
+        }
+        let attributes = self.try_get_component_mut::<Attributes>(entity)?;
+        let value = attributes.0.remove(key);
+        if attributes.0.is_empty() {
+            self.remove_component_from::<Attributes>(entity);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity();
+        assert_eq!(entities_and_components.get_attr(entity, "speed"), None);
+
+        entities_and_components.set_attr(entity, "speed", 2.5.into());
+        entities_and_components.set_attr(entity, "lives", AttrValue::Int(3));
+        entities_and_components.set_attr(entity, "boss", true.into());
+        entities_and_components.set_attr(entity, "name", "Gorgon".into());
+        entities_and_components.set_attr(entity, "lives", AttrValue::Int(2));
+
+        assert_eq!(
+            entities_and_components.get_attr(entity, "speed"),
+            Some(&AttrValue::Float(2.5))
+        );
+        assert_eq!(
+            entities_and_components.get_attr(entity, "lives"),
+            Some(&AttrValue::Int(2))
+        );
+        assert_eq!(
+            entities_and_components.get_attr(entity, "boss"),
+            Some(&AttrValue::Bool(true))
+        );
+        assert_eq!(
+            entities_and_components.get_attr(entity, "name"),
+            Some(&AttrValue::String("Gorgon".to_string()))
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Attributes>(),
+            1
+        );
+
+        for key in ["speed", "lives", "boss", "name"] {
+            assert!(entities_and_components.remove_attr(entity, key).is_some());
+        }
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Attributes>(),
+            0
+        );
+
+        entities_and_components.remove_entity(entity);
+        assert_eq!(entities_and_components.get_attr(entity, "speed"), None);
+    }
+}
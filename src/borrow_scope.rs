@@ -0,0 +1,169 @@
+use crate::*;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+/// Gives out references to components inside EntitiesAndComponents::borrow_scope
+/// Unlike the tuple getters, every component is borrowed separately,
+/// so a mutable borrow of one component type can be taken while another one is still in use
+/// Borrows are checked at runtime and last until the scope ends,
+/// borrowing the same component on the same entity mutably twice, or mutably and immutably, panics
+pub struct ScopedAccess<'a> {
+    entities_and_components: *mut EntitiesAndComponents,
+    // where borrows[(entity_id, TypeId::of::<Box<T>>())] = whether the borrow is mutable
+    borrows: RefCell<FxHashMap<(DefaultKey, TypeId), bool>>,
+    lifetime: PhantomData<&'a mut EntitiesAndComponents>,
+}
+
+impl<'a> ScopedAccess<'a> {
+    /// Gets a reference to a component on an entity
+    /// panics if the entity does not exist, the component does not exist on the entity,
+    /// or the component is already borrowed mutably in this scope
+    pub fn get<T: Component>(&self, entity: Entity) -> &T {
+        self.borrow::<T>(entity, false);
+        // SAFETY: the component is not borrowed mutably, and only components are ever borrowed from the pointer
+        // while the scope is alive, the world itself is borrowed mutably by borrow_scope for the whole scope
+        let entities_and_components = unsafe { &*self.entities_and_components };
+        entities_and_components.record_read::<T>();
+        let components = entities_and_components
+            .components
+            .get(entity.entity_id)
+            .unwrap_or_else(|| {
+                panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+            });
+        match get_stored::<T>(&entities_and_components.storages, components, entity) {
+            Some(component) => component,
+            None => panic_missing_component::<T>(entity),
+        }
+    }
+
+    /// Gets a mutable reference to a component on an entity
+    /// panics if the entity does not exist, the component does not exist on the entity,
+    /// or the component is already borrowed in this scope
+    // handing out &mut from &self is the point, the runtime borrow check makes sure it is unique
+    #[allow(clippy::mut_from_ref)]
+    pub fn get_mut<T: Component>(&self, entity: Entity) -> &mut T {
+        self.borrow::<T>(entity, true);
+        // SAFETY: the component is not borrowed anywhere else in this scope, every component is a separate allocation
+        // so references to different components never overlap, and the world is not touched again until the scope ends
+        let entities_and_components = unsafe { &mut *self.entities_and_components };
+        entities_and_components.assert_default_storage::<T>();
+        match entities_and_components
+            .components
+            .get_mut(entity.entity_id)
+            .unwrap_or_else(|| {
+                panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+            })
+            .get_mut::<Box<T>>()
+        {
+            Some(component) => component,
+            None => panic_missing_component::<T>(entity),
+        }
+    }
+
+    fn borrow<T: Component>(&self, entity: Entity, mutable: bool) {
+        let mut borrows = self.borrows.borrow_mut();
+        match borrows.entry((entity.entity_id, TypeId::of::<Box<T>>())) {
+            std::collections::hash_map::Entry::Occupied(entry) => {
+                if mutable || *entry.get() {
+                    panic!(
+                        "Component of type {type:?} on entity {entity:?} is already borrowed in this scope",
+                        type = std::any::type_name::<T>()
+                    );
+                }
+            }
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(mutable);
+            }
+        }
+    }
+}
+
+fn panic_missing_component<T>(entity: Entity) -> ! {
+    panic!(
+        "Component of type {type:?} does not exist on entity {entity:?}",
+        type = std::any::type_name::<T>()
+    );
+}
+
+impl EntitiesAndComponents {
+    /// Runs f with a ScopedAccess, which can borrow components one at a time instead of as a tuple
+    /// The borrows are checked at runtime and all of them are released when f returns
+    /// Components borrowed mutably show up in changed_since like any other mutable access
+    pub fn borrow_scope(&mut self, f: impl FnOnce(&mut ScopedAccess)) {
+        if self.frozen_mutation() {
+            return;
+        }
+        let mut access = ScopedAccess {
+            entities_and_components: self as *mut EntitiesAndComponents,
+            borrows: RefCell::new(FxHashMap::default()),
+            lifetime: PhantomData,
+        };
+        f(&mut access);
+
+        // the changes are only recorded now, since the tracker can't be touched while components are borrowed
+        for ((entity_id, type_id), mutable) in access.borrows.into_inner() {
+            if mutable {
+                self.change_tracker.component_changed(entity_id, type_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity {
+        x: f32,
+    }
+
+    #[test]
+    fn test_borrow_scope() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity =
+            entities_and_components.add_entity_with((Position { x: 0.0 }, Velocity { x: 2.0 }));
+        let other = entities_and_components.add_entity_with((Position { x: 1.0 },));
+        let tick = entities_and_components.current_tick();
+
+        entities_and_components.borrow_scope(|access| {
+            let position = access.get_mut::<Position>(entity);
+            let velocity = access.get_mut::<Velocity>(entity);
+            velocity.x *= 2.0;
+            position.x += velocity.x;
+            // shared borrows of the same component are fine
+            let first = access.get::<Position>(other);
+            let second = access.get::<Position>(other);
+            position.x += first.x + second.x;
+        });
+
+        let (position, velocity) =
+            entities_and_components.get_components::<(Position, Velocity)>(entity);
+        assert_eq!(position.x, 6.0);
+        assert_eq!(velocity.x, 4.0);
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Velocity>(tick)
+                .collect::<Vec<Entity>>(),
+            vec![entity]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_borrow_scope_aliasing_panics() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Position { x: 0.0 },));
+
+        entities_and_components.borrow_scope(|access| {
+            let position = access.get_mut::<Position>(entity);
+            let aliased = access.get_mut::<Position>(entity);
+            position.x += aliased.x;
+        });
+    }
+}
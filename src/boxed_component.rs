@@ -0,0 +1,92 @@
+use crate::*;
+
+pub(crate) type BoxedInserter = fn(&mut EntitiesAndComponents, Entity, Box<dyn Any>);
+
+impl EntitiesAndComponents {
+    /// Adds a component that has been boxed as Box<dyn Any> to an entity, type_id is TypeId::of::<T>() of the boxed component
+    /// This lets layers that don't know the concrete types, like scripting or deserialization, add components
+    /// The component can then be read back like any other component
+    /// If the component already exists on the entity, it will be overwritten
+    /// panics if the entity does not exist, if the component is not of the type type_id,
+    /// or if the type has never been added or registered with register_boxed_component, because otherwise its storage is unknown
+    pub fn add_boxed_component(
+        &mut self,
+        entity: Entity,
+        type_id: TypeId,
+        component: Box<dyn Any>,
+    ) {
+        let Some(inserter) = self.boxed_inserters.get(&type_id).copied() else {
+            panic!(
+                "Component type {type_id:?} has not been registered, add it once or call register_boxed_component"
+            );
+        };
+        inserter(self, entity, component);
+    }
+
+    /// Lets add_boxed_component add components of type T before any T was added with add_component_to
+    pub fn register_boxed_component<T: Component>(&mut self) {
+        self.boxed_inserters
+            .entry(TypeId::of::<T>())
+            .or_insert(add_boxed_component_of::<T>);
+    }
+}
+
+pub(crate) fn add_boxed_component_of<T: Component>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    component: Box<dyn Any>,
+) {
+    let Ok(component) = component.downcast::<T>() else {
+        panic!(
+            "Boxed component is not a {}, the type id does not match the component",
+            std::any::type_name::<T>()
+        );
+    };
+    entities_and_components.add_component_to(entity, *component);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[test]
+    fn test_add_boxed_component() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_boxed_component::<Position>();
+
+        let entity = entities_and_components.add_entity();
+        let component: Box<dyn Any> = Box::new(Position { x: 1.0, y: 2.0 });
+        entities_and_components.add_boxed_component(entity, TypeId::of::<Position>(), component);
+
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 1.0, y: 2.0 });
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Position>()
+                .collect::<Vec<&Entity>>(),
+            vec![&entity]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_add_boxed_component_wrong_type() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_boxed_component::<Position>();
+
+        let entity = entities_and_components.add_entity();
+        entities_and_components.add_boxed_component(
+            entity,
+            TypeId::of::<Position>(),
+            Box::new(1u32),
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use crate::*;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How many add and remove events are kept for every component type, older ones are forgotten
+pub const MAX_STRUCTURAL_EVENTS: usize = 4096;
+
+/// When a component was last changed and how many times it has been changed
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ComponentTicks {
+    pub(crate) changed: u64,
+    pub(crate) generation: u64,
+    // orders the components of an entity by when they were added, unlike changed it is counted while suppressed
+    pub(crate) added: u64,
+}
+
+/// Keeps track of when components are added and changed
+/// Every change gets a new tick that is higher than all the ticks before it,
+/// the tick counter is atomic so components can be marked as changed from the parallel phase
+pub(crate) struct ChangeTracker {
+    tick: AtomicU64,
+    suppressed: bool,
+    // how many components have ever been added, see ComponentTicks::added
+    additions: u64,
+    // where ticks[entity_id][component_id]
+    ticks: SecondaryMap<DefaultKey, FxHashMap<TypeId, ComponentTicks>>,
+    // the generation a component starts at if it is added again after being removed from the entity,
+    // so a generation is never handed out twice for the same entity and component type
+    next_generations: SecondaryMap<DefaultKey, FxHashMap<TypeId, u64>>,
+    // the tick every component was added or removed at, oldest first, by the TypeId the component is stored under
+    added: FxHashMap<TypeId, VecDeque<(u64, Entity)>>,
+    removed: FxHashMap<TypeId, VecDeque<(u64, Entity)>>,
+}
+
+fn log_event(
+    events: &mut FxHashMap<TypeId, VecDeque<(u64, Entity)>>,
+    type_id: TypeId,
+    tick: u64,
+    entity_id: DefaultKey,
+) {
+    let events = events.entry(type_id).or_default();
+    if events.len() == MAX_STRUCTURAL_EVENTS {
+        events.pop_front();
+    }
+    events.push_back((tick, Entity { entity_id }));
+}
+
+// the entities of the events after the tick, each entity only once
+fn events_since(
+    events: Option<&VecDeque<(u64, Entity)>>,
+    since: u64,
+) -> impl Iterator<Item = Entity> + '_ {
+    let mut seen = FxHashSet::default();
+    events
+        .into_iter()
+        .flatten()
+        .skip_while(move |(tick, _)| *tick <= since)
+        .map(|(_, entity)| *entity)
+        .filter(move |entity| seen.insert(*entity))
+}
+
+impl ChangeTracker {
+    pub(crate) fn new() -> Self {
+        ChangeTracker {
+            tick: AtomicU64::new(0),
+            suppressed: false,
+            additions: 0,
+            ticks: SecondaryMap::new(),
+            next_generations: SecondaryMap::new(),
+            added: FxHashMap::default(),
+            removed: FxHashMap::default(),
+        }
+    }
+
+    fn next_tick(&self) -> u64 {
+        self.tick.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub(crate) fn current_tick(&self) -> u64 {
+        self.tick.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_suppressed(&self) -> bool {
+        self.suppressed
+    }
+
+    pub(crate) fn entity_added(&mut self, entity_id: DefaultKey) {
+        self.ticks.insert(entity_id, FxHashMap::default());
+    }
+
+    pub(crate) fn entity_removed(&mut self, entity_id: DefaultKey) {
+        self.next_generations.remove(entity_id);
+        // despawning an entity removes all of its components
+        if let Some(ticks) = self.ticks.remove(entity_id) {
+            for type_id in ticks.keys() {
+                self.log_removed(entity_id, *type_id);
+            }
+        }
+    }
+
+    pub(crate) fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.ticks, entity_ids);
+        remap_keys(&mut self.next_generations, entity_ids);
+        for events in self.added.values_mut().chain(self.removed.values_mut()) {
+            for (_, entity) in events.iter_mut() {
+                *entity = remap_entity(*entity, entity_ids);
+            }
+        }
+    }
+
+    // exchanges the ticks of two entities' components, except the linked ones that stay with the entity
+    pub(crate) fn swap_entities(
+        &mut self,
+        a: DefaultKey,
+        b: DefaultKey,
+        linked_components: &FxHashSet<TypeId>,
+    ) {
+        let mut ticks_a = self.ticks.remove(a).unwrap_or_default();
+        let mut ticks_b = self.ticks.remove(b).unwrap_or_default();
+        std::mem::swap(&mut ticks_a, &mut ticks_b);
+        swap_entries_back(&mut ticks_a, &mut ticks_b, linked_components);
+        self.ticks.insert(a, ticks_a);
+        self.ticks.insert(b, ticks_b);
+    }
+
+    fn log_removed(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        if !self.suppressed {
+            let tick = self.next_tick();
+            log_event(&mut self.removed, type_id, tick, entity_id);
+        }
+    }
+
+    pub(crate) fn component_added(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        // components added while suppressed start out unchanged
+        let tick = if self.suppressed { 0 } else { self.next_tick() };
+        if let Some(ticks) = self.ticks.get_mut(entity_id) {
+            match ticks.entry(type_id) {
+                // overwriting a component is a change, so the generation keeps counting up
+                std::collections::hash_map::Entry::Occupied(mut entry) => {
+                    let component_ticks = entry.get_mut();
+                    component_ticks.changed = tick;
+                    component_ticks.generation += 1;
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    self.additions += 1;
+                    let generation = self
+                        .next_generations
+                        .get_mut(entity_id)
+                        .and_then(|next_generations| next_generations.remove(&type_id))
+                        .unwrap_or(0);
+                    entry.insert(ComponentTicks {
+                        changed: tick,
+                        generation,
+                        added: self.additions,
+                    });
+                    if !self.suppressed {
+                        log_event(&mut self.added, type_id, tick, entity_id);
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn component_removed(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        let removed = self
+            .ticks
+            .get_mut(entity_id)
+            .and_then(|ticks| ticks.remove(&type_id));
+        if let Some(removed) = removed {
+            if let Some(next_generations) = self.next_generations.entry(entity_id) {
+                next_generations
+                    .or_default()
+                    .insert(type_id, removed.generation + 1);
+            }
+            self.log_removed(entity_id, type_id);
+        }
+    }
+
+    /// only marks components that exist on the entity, so it is fine to call before checking
+    pub(crate) fn component_changed(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        if let Some(component_ticks) = self
+            .ticks
+            .get_mut(entity_id)
+            .and_then(|ticks| ticks.get_mut(&type_id))
+        {
+            // the generation is bumped even while suppressed, otherwise data derived from the component could go stale
+            component_ticks.generation += 1;
+            if !self.suppressed {
+                // can't call next_tick here, self.ticks is already borrowed mutably
+                component_ticks.changed = self.tick.fetch_add(1, Ordering::Relaxed) + 1;
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, entity_id: DefaultKey, type_id: TypeId) -> Option<ComponentTicks> {
+        self.ticks.get(entity_id)?.get(&type_id).copied()
+    }
+
+    // the TypeIds the components of the entity are stored under, in the order they were added
+    pub(crate) fn component_types(&self, entity_id: DefaultKey) -> Vec<TypeId> {
+        let Some(ticks) = self.ticks.get(entity_id) else {
+            return Vec::new();
+        };
+        let mut component_types = ticks
+            .iter()
+            .map(|(type_id, ticks)| (ticks.added, *type_id))
+            .collect::<Vec<(u64, TypeId)>>();
+        component_types.sort_unstable();
+        component_types
+            .into_iter()
+            .map(|(_, type_id)| type_id)
+            .collect()
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets the current change tick
+    /// Every time a component is added or accessed mutably it is stamped with a new, higher tick
+    /// so storing this value and later passing it to changed_since gives everything that changed in between
+    pub fn current_tick(&self) -> u64 {
+        self.change_tracker.current_tick()
+    }
+
+    /// returns an iterator over all entities with a certain component that was added or accessed mutably after the tick
+    pub fn changed_since<T: Component>(&self, tick: u64) -> impl Iterator<Item = Entity> + '_ {
+        let type_id = TypeId::of::<Box<T>>();
+        self.get_entities_with_component::<T>()
+            .filter(move |entity| {
+                self.change_tracker
+                    .get(entity.entity_id, type_id)
+                    .is_some_and(|ticks| ticks.changed > tick)
+            })
+            .copied()
+    }
+
+    /// returns an iterator over the entities that a certain component was added to after the tick, and that still have it
+    /// Overwriting a component is not an add, use changed_since to also get those
+    /// Only the last MAX_STRUCTURAL_EVENTS adds of every component type are remembered
+    pub fn added<T: Component>(&self, tick: u64) -> impl Iterator<Item = Entity> + '_ {
+        let type_id = TypeId::of::<Box<T>>();
+        events_since(self.change_tracker.added.get(&type_id), tick)
+            .filter(move |entity| self.change_tracker.get(entity.entity_id, type_id).is_some())
+    }
+
+    /// returns an iterator over the entities that a certain component was removed from after the tick,
+    /// including entities that were removed along with it
+    /// The entities may have the component again, or not exist anymore
+    /// Only the last MAX_STRUCTURAL_EVENTS removals of every component type are remembered
+    pub fn removed<T: Component>(&self, tick: u64) -> impl Iterator<Item = Entity> + '_ {
+        events_since(
+            self.change_tracker.removed.get(&TypeId::of::<Box<T>>()),
+            tick,
+        )
+    }
+
+    /// Gets the generation of a component on an entity, which goes up by one every time it is accessed mutably or overwritten
+    /// Data derived from a component can be cached along with its generation and only recomputed when the generation changes
+    /// Removing the component and adding it again does not start the generation over
+    /// returns None if the component does not exist on the entity
+    pub fn component_generation<T: Component>(&self, entity: Entity) -> Option<u64> {
+        self.change_tracker
+            .get(entity.entity_id, TypeId::of::<Box<T>>())
+            .map(|ticks| ticks.generation)
+    }
+
+    /// Runs f without recording any changes, components added or changed inside f will not show up in changed_since
+    /// This is useful for bulk loading a level, so the loaded entities start out unchanged
+    pub fn suppress_change_tracking(&mut self, f: impl FnOnce(&mut Self)) {
+        let was_suppressed = self.change_tracker.suppressed;
+        self.change_tracker.suppressed = true;
+        f(self);
+        self.change_tracker.suppressed = was_suppressed;
+    }
+
+    pub(crate) fn mark_changed(&mut self, entity: Entity, type_id: TypeId) {
+        // the access has already been handed out, so a frozen world is only reported
+        self.frozen_mutation();
+        if !self.histories.is_empty() {
+            self.record_history(entity, type_id);
+        }
+        self.change_tracker
+            .component_changed(entity.entity_id, type_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity {
+        x: f32,
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let moving =
+            entities_and_components.add_entity_with((Position { x: 0.0 }, Velocity { x: 1.0 }));
+        let still = entities_and_components.add_entity_with((Position { x: 0.0 },));
+
+        let tick = entities_and_components.current_tick();
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(tick)
+                .count(),
+            0
+        );
+
+        let (position, velocity) =
+            entities_and_components.get_components_mut::<(Position, Velocity)>(moving);
+        position.x += velocity.x;
+
+        let changed = entities_and_components
+            .changed_since::<Position>(tick)
+            .collect::<Vec<Entity>>();
+        assert_eq!(changed, vec![moving]);
+
+        // immutable access is not a change
+        let (position,) = entities_and_components.get_components::<(Position,)>(still);
+        assert_eq!(position.x, 0.0);
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(tick)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        struct RigidBody {
+            initialized: bool,
+        }
+
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let first = entities_and_components.add_entity_with((RigidBody { initialized: false },));
+        let second = entities_and_components.add_entity();
+
+        // frame 1 initializes the rigid bodies added before it
+        let frame_1 = entities_and_components.current_tick();
+        assert_eq!(
+            entities_and_components
+                .added::<RigidBody>(0)
+                .collect::<Vec<Entity>>(),
+            vec![first]
+        );
+        entities_and_components.add_component_to(second, RigidBody { initialized: false });
+        // overwriting is not an add
+        entities_and_components.add_component_to(first, RigidBody { initialized: true });
+
+        // frame 2 only sees what happened after frame 1 started
+        let frame_2 = entities_and_components.current_tick();
+        let added = entities_and_components
+            .added::<RigidBody>(frame_1)
+            .collect::<Vec<Entity>>();
+        assert_eq!(added, vec![second]);
+        for entity in added {
+            let (rigid_body,) = entities_and_components.get_components_mut::<(RigidBody,)>(entity);
+            rigid_body.initialized = true;
+        }
+        assert_eq!(entities_and_components.removed::<RigidBody>(0).count(), 0);
+        entities_and_components.remove_component_from::<RigidBody>(first);
+        entities_and_components.remove_component_from::<RigidBody>(first);
+        entities_and_components.remove_entity(second);
+
+        assert_eq!(
+            entities_and_components
+                .removed::<RigidBody>(frame_2)
+                .collect::<Vec<Entity>>(),
+            vec![first, second]
+        );
+        // second was added after frame 1 but doesn't have the component anymore
+        assert_eq!(
+            entities_and_components.added::<RigidBody>(frame_1).count(),
+            0
+        );
+        let frame_3 = entities_and_components.current_tick();
+        assert_eq!(
+            entities_and_components
+                .removed::<RigidBody>(frame_3)
+                .count(),
+            0
+        );
+        assert_eq!(entities_and_components.added::<Position>(0).count(), 0);
+    }
+
+    #[test]
+    fn test_component_generation() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            None
+        );
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0 });
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            Some(0)
+        );
+
+        // reads don't change the generation
+        entities_and_components.get_components::<(Position,)>(entity);
+        entities_and_components.try_get_component::<Position>(entity);
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            Some(0)
+        );
+
+        entities_and_components.get_components_mut::<(Position,)>(entity);
+        entities_and_components.try_get_component_mut::<Position>(entity);
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            Some(2)
+        );
+
+        entities_and_components.add_component_to(entity, Position { x: 1.0 });
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            Some(3)
+        );
+
+        // a component added again continues after the generation of the removed one
+        entities_and_components.remove_component_from::<Position>(entity);
+        entities_and_components.add_component_to(entity, Position { x: 2.0 });
+        assert_eq!(
+            entities_and_components.component_generation::<Position>(entity),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_suppress_change_tracking() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let load_tick = entities_and_components.current_tick();
+        entities_and_components.suppress_change_tracking(|entities_and_components| {
+            for i in 0..10 {
+                let entity = entities_and_components.add_entity_with((Position { x: 0.0 },));
+                let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+                position.x = i as f32;
+            }
+        });
+
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(load_tick)
+                .count(),
+            0
+        );
+
+        // tracking is back on after the suppressor
+        let entity = entities_and_components.get_nth_entity(3).unwrap();
+        entities_and_components.try_get_component_mut::<Position>(entity);
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(load_tick)
+                .collect::<Vec<Entity>>(),
+            vec![entity]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::*;
+
+// clones the component stored under one TypeId from an entity in one world to an entity in another,
+// entities maps the cloned entities to their new handles
+pub(crate) type ComponentCloner = fn(
+    &EntitiesAndComponents,
+    Entity,
+    &mut EntitiesAndComponents,
+    Entity,
+    &HashMap<Entity, Entity>,
+);
+
+impl EntitiesAndComponents {
+    /// Registers T as cloneable, so entities with it can be copied with clone_subset
+    pub fn register_clone<T: Component + Clone>(&mut self) {
+        self.cloners
+            .insert(TypeId::of::<Box<T>>(), clone_component_of::<T>);
+    }
+
+    /// Clones the entities and their components into a new world, for example to show a preview of part of a level
+    /// Returns the new world and the handle of every cloned entity in it by its handle in self
+    /// Every component on the entities has to be registered with register_clone, otherwise nothing is cloned
+    /// The components the world adds itself, like attributes, are cloned without being registered
+    /// and the error lists the components that aren't registered
+    /// Parents, children and relationships are kept between the cloned entities, and dropped to entities outside of them
+    /// Entity values in your own components are cloned as they are, so they still point at entities in self
+    /// Resources, systems and stable ids are not cloned
+    /// panics if an entity does not exist
+    pub fn clone_subset(
+        &self,
+        entities: &[Entity],
+    ) -> Result<(EntitiesAndComponents, HashMap<Entity, Entity>), NotCloneableError> {
+        for entity in entities {
+            if !self.does_entity_exist(*entity) {
+                panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+            }
+        }
+
+        // check every component first, so nothing is cloned if one of them can't be
+        let mut cloners = Vec::new();
+        let mut not_cloneable = Vec::new();
+        for (type_id, with_component) in &self.entities_with_components {
+            if !entities
+                .iter()
+                .any(|entity| with_component.contains_key(entity.entity_id))
+            {
+                continue;
+            }
+            match self
+                .cloners
+                .get(type_id)
+                .copied()
+                .or_else(|| internal_cloner(*type_id))
+            {
+                Some(cloner) => cloners.push((with_component, cloner)),
+                None => not_cloneable.push(
+                    self.component_type_names
+                        .get(type_id)
+                        .copied()
+                        .unwrap_or("an unnamed component"),
+                ),
+            }
+        }
+        if !not_cloneable.is_empty() {
+            not_cloneable.sort();
+            return Err(NotCloneableError {
+                components: not_cloneable,
+            });
+        }
+
+        let mut dest = EntitiesAndComponents::new();
+        // the clones keep their relations and links, so the hooks that look after them are needed in dest too
+        dest.cloners = self.cloners.clone();
+        dest.relation_cleanups = self.relation_cleanups.clone();
+        dest.entity_remaps = self.entity_remaps.clone();
+        dest.linked_components = self.linked_components.clone();
+        dest.component_type_names = self.component_type_names.clone();
+        let mut new_entities = HashMap::new();
+        for entity in entities {
+            new_entities
+                .entry(*entity)
+                .or_insert_with(|| dest.add_entity());
+        }
+        for (with_component, cloner) in cloners {
+            for (entity, new_entity) in &new_entities {
+                if with_component.contains_key(entity.entity_id) {
+                    cloner(self, *entity, &mut dest, *new_entity, &new_entities);
+                }
+            }
+        }
+        Ok((dest, new_entities))
+    }
+}
+
+fn clone_component_of<T: Component + Clone>(
+    source: &EntitiesAndComponents,
+    entity: Entity,
+    dest: &mut EntitiesAndComponents,
+    new_entity: Entity,
+    _: &HashMap<Entity, Entity>,
+) {
+    if let Some(component) = get_stored::<T>(
+        &source.storages,
+        &source.components[entity.entity_id],
+        entity,
+    ) {
+        dest.add_component_to(new_entity, component.clone());
+    }
+}
+
+// the components the world adds itself, which are cloned without being registered
+fn internal_cloner(type_id: TypeId) -> Option<ComponentCloner> {
+    if type_id == TypeId::of::<Box<Disabled>>() {
+        Some(|_, _, dest, new_entity, _| dest.add_component_to(new_entity, Disabled))
+    } else if type_id == TypeId::of::<Box<Attributes>>() {
+        Some(clone_component_of::<Attributes>)
+    } else if type_id == TypeId::of::<Box<Parent>>() {
+        Some(clone_parent)
+    } else if type_id == TypeId::of::<Box<Children>>() {
+        Some(clone_children)
+    } else {
+        None
+    }
+}
+
+fn clone_parent(
+    source: &EntitiesAndComponents,
+    entity: Entity,
+    dest: &mut EntitiesAndComponents,
+    new_entity: Entity,
+    entities: &HashMap<Entity, Entity>,
+) {
+    let parent = source
+        .get_parent(entity)
+        .and_then(|parent| entities.get(&parent));
+    if let Some(parent) = parent {
+        dest.add_component_to(new_entity, Parent(*parent));
+    }
+}
+
+fn clone_children(
+    source: &EntitiesAndComponents,
+    entity: Entity,
+    dest: &mut EntitiesAndComponents,
+    new_entity: Entity,
+    entities: &HashMap<Entity, Entity>,
+) {
+    let children = source
+        .get_children(entity)
+        .into_iter()
+        .filter_map(|child| entities.get(&child).copied())
+        .collect::<Vec<Entity>>();
+    if !children.is_empty() {
+        dest.add_component_to(new_entity, Children { children });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Name(&'static str);
+
+    struct Follows;
+
+    // not Clone
+    struct Connection;
+
+    #[test]
+    fn test_clone_subset() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_clone::<Position>();
+        entities_and_components.register_clone::<Name>();
+        let entities = ["a", "b", "c", "d", "e"]
+            .map(|name| entities_and_components.add_entity_with((Position { x: 1.0 }, Name(name))));
+        entities_and_components.set_parent(entities[3], entities[1]);
+        entities_and_components.set_parent(entities[2], entities[1]);
+        entities_and_components.relate::<Follows>(entities[1], entities[3]);
+        entities_and_components.relate::<Follows>(entities[1], entities[4]);
+
+        let (preview, new_entities) = entities_and_components
+            .clone_subset(&[entities[1], entities[3]])
+            .unwrap();
+
+        assert_eq!(preview.get_entity_count(), 2);
+        assert_eq!(new_entities.len(), 2);
+        for entity in [entities[1], entities[3]] {
+            let (position, name) =
+                preview.get_components::<(Position, Name)>(new_entities[&entity]);
+            let (old_position, old_name) =
+                entities_and_components.get_components::<(Position, Name)>(entity);
+            assert_eq!(position, old_position);
+            assert_eq!(name, old_name);
+        }
+        // the links to entities that weren't cloned are dropped
+        let (b, d) = (new_entities[&entities[1]], new_entities[&entities[3]]);
+        assert_eq!(preview.get_children(b), vec![d]);
+        assert_eq!(preview.get_parent(d), Some(b));
+        assert_eq!(preview.relations_from::<Follows>(b), &[d]);
+        assert_eq!(preview.relations_to::<Follows>(d), &[b]);
+        // attributes are cloned without being registered
+        entities_and_components.set_attr(entities[0], "speed", AttrValue::Int(3));
+        let (mut clone, clones) = entities_and_components
+            .clone_subset(&[entities[0], entities[1], entities[3]])
+            .unwrap();
+        assert_eq!(
+            clone.get_attr(clones[&entities[0]], "speed"),
+            Some(&AttrValue::Int(3))
+        );
+        // removing a clone cleans up its relations in the cloned world
+        clone.remove_entity(clones[&entities[3]]);
+        assert!(clone
+            .relations_from::<Follows>(clones[&entities[1]])
+            .is_empty());
+        // the original is unchanged
+        assert_eq!(entities_and_components.get_entity_count(), 5);
+        assert_eq!(entities_and_components.get_children(entities[1]).len(), 2);
+
+        entities_and_components.add_component_to(entities[3], Connection);
+        let error = entities_and_components
+            .clone_subset(&[entities[1], entities[3]])
+            .err()
+            .unwrap();
+        assert_eq!(error.components, vec![std::any::type_name::<Connection>()]);
+    }
+}
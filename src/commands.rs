@@ -0,0 +1,343 @@
+use crate::*;
+
+type Command = Box<dyn FnOnce(&mut EntitiesAndComponents) + Send + Sync>;
+
+/// A queue of structural changes (spawns, despawns, adding and removing components)
+/// that are not applied right away, but later when the world is flushed
+/// This is useful when the world can't be changed right now, for example while iterating over it
+/// Commands are applied in the order they were queued
+/// Systems running in parallel each queue into their own Commands, see EntitiesAndComponentsThreadSafe::commands,
+/// and those are applied one system at a time in the order the systems were added, so the result doesn't depend on timing
+/// Commands can conflict when they are applied, the later one sees what the earlier ones did:
+/// removing an entity that was already removed does nothing, and neither does adding or removing a component on it,
+/// adding a component the entity already has replaces it, so the last one applied is kept
+pub struct Commands {
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    /// Creates a new empty command queue
+    pub fn new() -> Self {
+        Commands {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Queues an entity to be spawned with the given components
+    /// If the world already has the maximum number of entities when the command is applied, it will do nothing
+    pub fn spawn<T: OwnedComponents<Input = T> + Send + Sync + 'static>(&mut self, components: T) {
+        self.add(move |entities_and_components| {
+            let _ = entities_and_components.try_add_entity_with(components);
+        });
+    }
+
+    /// Queues an entity to be removed
+    /// If the entity no longer exists when the command is applied, it will do nothing
+    pub fn remove_entity(&mut self, entity: Entity) {
+        self.add(move |entities_and_components| {
+            if entities_and_components.does_entity_exist(entity) {
+                entities_and_components.remove_entity(entity);
+            }
+        });
+    }
+
+    /// Queues a component to be added to an entity
+    /// If the entity no longer exists when the command is applied, it will do nothing
+    pub fn add_component_to<T: Component + Send + Sync>(&mut self, entity: Entity, component: T) {
+        self.add(move |entities_and_components| {
+            if entities_and_components.does_entity_exist(entity) {
+                entities_and_components.add_component_to(entity, component);
+            }
+        });
+    }
+
+    /// Queues a component to be removed from an entity
+    /// If the entity no longer exists when the command is applied, it will do nothing
+    pub fn remove_component_from<T: Component + Send + Sync>(&mut self, entity: Entity) {
+        self.add(move |entities_and_components| {
+            if entities_and_components.does_entity_exist(entity) {
+                entities_and_components.remove_component_from::<T>(entity);
+            }
+        });
+    }
+
+    /// Queues an arbitrary change to the world
+    pub fn add(
+        &mut self,
+        command: impl FnOnce(&mut EntitiesAndComponents) + Send + Sync + 'static,
+    ) {
+        self.commands.push(Box::new(command));
+    }
+
+    /// Moves all the commands from other to the end of this queue, leaving other empty
+    pub fn append(&mut self, other: &mut Commands) {
+        self.commands.append(&mut other.commands);
+    }
+
+    /// Gets the number of queued commands
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Checks if there are no queued commands
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Applies all the queued commands to the world in the order they were queued
+    pub fn apply(self, entities_and_components: &mut EntitiesAndComponents) {
+        for command in self.commands {
+            command(entities_and_components);
+        }
+    }
+}
+
+impl<'a> EntitiesAndComponentsThreadSafe<'a> {
+    /// Gets the command queue of the system using this view of the world
+    /// Every system running in parallel has its own queue, they are added to the world's queue once the phase is over,
+    /// in the order the systems were added, and applied at the next flush
+    pub fn commands(&self) -> std::sync::MutexGuard<'_, Commands> {
+        self.commands.lock().unwrap()
+    }
+}
+
+// runs f on every system at once, each with its own view of the world so the commands they queue are kept apart,
+// returns the commands queued by every system in the order of the systems, whichever finished first
+pub(crate) fn run_with_own_commands<S: Send>(
+    entities_and_components: &mut EntitiesAndComponents,
+    systems: &mut [S],
+    f: impl Fn(&mut S, &EntitiesAndComponentsThreadSafe) + Send + Sync,
+) -> Vec<Commands> {
+    // the systems can only read the caches
+    entities_and_components.refresh_cached_queries();
+    #[cfg(not(feature = "single-threaded"))]
+    {
+        let entities_and_components_ptr = EntitiesAndComponentPtr {
+            entities_and_components: entities_and_components as *mut _,
+        };
+        systems
+            .par_iter_mut()
+            .map(|system| {
+                let mut entities_and_components_ptr = entities_and_components_ptr.clone();
+                let thread_safe = EntitiesAndComponentsThreadSafe::new(unsafe {
+                    entities_and_components_ptr.as_mut()
+                });
+                f(system, &thread_safe);
+                thread_safe.commands.into_inner().unwrap()
+            })
+            .collect::<Vec<Commands>>()
+    }
+    #[cfg(feature = "single-threaded")]
+    systems
+        .iter_mut()
+        .map(|system| {
+            let thread_safe = EntitiesAndComponentsThreadSafe::new(entities_and_components);
+            f(system, &thread_safe);
+            thread_safe.commands.into_inner().unwrap()
+        })
+        .collect::<Vec<Commands>>()
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets the world's command queue
+    /// The queued commands are applied when flush is called, which World::run does automatically
+    pub fn commands(&mut self) -> &mut Commands {
+        &mut self.commands
+    }
+
+    /// Calls f for every entity with the component T, except disabled ones, with the component and a command queue
+    /// This is the safe way to change the world while iterating over it, spawns, despawns
+    /// and other structural changes are queued in f and applied once every entity has been visited
+    /// The entities are collected before f is first called, so entities spawned by the commands are not visited
+    pub fn run_query_with_commands<T: Component>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut T, &mut Commands),
+    ) {
+        let entities = self
+            .get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+        let mut commands = Commands::new();
+        for entity in entities {
+            if let (Some(component),) = self.try_get_components_mut::<(T,)>(entity) {
+                f(entity, component, &mut commands);
+            }
+        }
+        commands.apply(self);
+    }
+
+    /// Applies everything that has been deferred, so the world is fully up to date afterwards
+    /// Commands queued while flushing are applied as well
+    /// World::run calls this automatically, but it can be called manually between systems
+    pub fn flush(&mut self) {
+        if !self.commands.is_empty() && self.frozen_mutation() {
+            return;
+        }
+        while !self.commands.is_empty() {
+            let commands = std::mem::take(&mut self.commands);
+            commands.apply(self);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    #[test]
+    fn test_flush_applies_deferred_spawn() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        entities_and_components
+            .commands()
+            .spawn((Position { x: 3.0 },));
+
+        assert_eq!(entities_and_components.get_entity_count(), 0);
+
+        entities_and_components.flush();
+
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+        let entity = entities_and_components
+            .get_entity_with_component::<Position>(0)
+            .unwrap();
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 3.0);
+        assert!(entities_and_components.commands().is_empty());
+    }
+
+    #[test]
+    fn test_run_query_with_commands() {
+        struct Health(u32);
+
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entities =
+            [0, 10, 0, 20].map(|health| entities_and_components.add_entity_with((Health(health),)));
+
+        let mut visited = 0;
+        entities_and_components.run_query_with_commands(|entity, health: &mut Health, commands| {
+            visited += 1;
+            if health.0 == 0 {
+                // every dead entity is replaced by a fresh one
+                commands.remove_entity(entity);
+                commands.spawn((Health(100),));
+            } else {
+                health.0 -= 5;
+            }
+        });
+
+        // the spawned entities were not visited
+        assert_eq!(visited, 4);
+        assert!(!entities_and_components.does_entity_exist(entities[0]));
+        assert!(!entities_and_components.does_entity_exist(entities[2]));
+        let health = entities_and_components
+            .get_entities_with_component::<Health>()
+            .map(|entity| {
+                let (health,) = entities_and_components.get_components::<(Health,)>(*entity);
+                health.0
+            })
+            .collect::<Vec<u32>>();
+        assert_eq!(health.len(), 4);
+        assert_eq!(health.iter().filter(|health| **health == 100).count(), 2);
+        assert!(health.contains(&5) && health.contains(&15));
+    }
+
+    #[test]
+    fn test_parallel_commands_apply_in_system_order() {
+        #[derive(Debug, PartialEq)]
+        struct Owner(&'static str);
+
+        // queues a despawn of the target and claims the other entity, both systems do this at once
+        struct ClaimSystem {
+            name: &'static str,
+            delay: u64,
+        }
+
+        impl System for ClaimSystem {
+            fn is_read_only(&self) -> bool {
+                true
+            }
+
+            fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+                // the first system finishes last, so applying in finishing order would give a different result
+                std::thread::sleep(std::time::Duration::from_millis(self.delay));
+                let entities = engine.get_entities();
+                let mut commands = engine.commands();
+                commands.remove_entity(entities[0]);
+                commands.add_component_to(entities[1], Owner(self.name));
+                commands.spawn((Owner(self.name),));
+            }
+        }
+
+        let mut world = World::new();
+        let target = world.entities_and_components.add_entity();
+        let claimed = world.entities_and_components.add_entity();
+        world.add_system(ClaimSystem {
+            name: "first",
+            delay: 20,
+        });
+        world.add_system(ClaimSystem {
+            name: "second",
+            delay: 0,
+        });
+
+        world.run();
+
+        // the second despawn found the entity already gone and did nothing
+        assert!(!world.entities_and_components.does_entity_exist(target));
+        // the second system's component was applied last
+        let (owner,) = world
+            .entities_and_components
+            .get_components::<(Owner,)>(claimed);
+        assert_eq!(*owner, Owner("second"));
+        // the first system's entity was spawned first
+        let spawned = world
+            .entities_and_components
+            .get_entities()
+            .into_iter()
+            .filter(|entity| *entity != claimed)
+            .map(|entity| {
+                let (owner,) = world
+                    .entities_and_components
+                    .get_components::<(Owner,)>(entity);
+                owner.0
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!(spawned, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_run_flushes_deferred_despawn() {
+        struct DespawnSystem {}
+
+        impl System for DespawnSystem {
+            fn run(&mut self, engine: &mut EntitiesAndComponents) {
+                for entity in engine.get_entities() {
+                    engine.commands().remove_entity(entity);
+                }
+                // nothing is removed until the world is flushed
+                assert_eq!(engine.get_entity_count(), 2);
+            }
+        }
+
+        let mut world = World::new();
+        world.entities_and_components.add_entity();
+        world.entities_and_components.add_entity();
+        world.add_system(DespawnSystem {});
+
+        world.run();
+
+        assert_eq!(world.entities_and_components.get_entity_count(), 0);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::*;
+use slotmap::Key;
+
+// where entity_ids[old entity_id] = the entity it was moved to by compact
+pub(crate) type EntityIds = FxHashMap<DefaultKey, Entity>;
+
+// rewrites the Entity handles stored inside the components of one type, after compact has moved every entity
+pub(crate) type EntityRemap = fn(&mut EntitiesAndComponents, &EntityIds);
+
+// moves the values of a map keyed by entity to the new keys, values of entities that don't exist anymore are dropped
+pub(crate) fn remap_keys<V>(map: &mut SecondaryMap<DefaultKey, V>, entity_ids: &EntityIds) {
+    *map = std::mem::take(map)
+        .into_iter()
+        .filter_map(|(entity_id, value)| {
+            entity_ids
+                .get(&entity_id)
+                .map(|entity| (entity.entity_id, value))
+        })
+        .collect();
+}
+
+// gets the entity an entity was moved to, entities that were already gone stay as they are
+pub(crate) fn remap_entity(entity: Entity, entity_ids: &EntityIds) -> Entity {
+    entity_ids.get(&entity.entity_id).copied().unwrap_or(entity)
+}
+
+// frees every slot of an empty map, so the next inserts go into the lowest slots
+// the slots keep their versions, so an old handle can never point to whatever is inserted into its slot
+fn free_every_slot<V>(map: &mut SlotMap<DefaultKey, V>, placeholder: impl Fn() -> V) {
+    // fill the free slots until a new slot is made, which only has version 1
+    let mut placeholders = Vec::new();
+    loop {
+        let key = map.insert(placeholder());
+        placeholders.push(key);
+        if key.data().as_ffi() >> 32 == 1 {
+            break;
+        }
+    }
+    // the slot freed last is used first, so the highest slot is freed first
+    placeholders.sort_by_key(|key| std::cmp::Reverse(key.data().as_ffi() as u32));
+    for key in placeholders {
+        map.remove(key);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Moves every entity to a new Entity handle, so the entities are stored next to each other again
+    /// After removing a lot of entities the remaining ones are spread out, which makes iterating over them slower
+    /// The entities keep their order, and the new handle of every entity is returned by its old handle
+    /// Every Entity handle held outside the world becomes invalid and has to be replaced with its new handle,
+    /// this includes Entity values in your own components
+    /// An old handle never points to another entity after this, it just doesn't exist anymore
+    /// The slots of removed entities are reused instead of freed for this, so compact doesn't give memory back
+    /// Parents, children, relationships, stable ids, external ids and EntityRefs are updated automatically
+    /// Deferred commands are flushed first, and the undo journal is cleared since it refers to the old handles
+    pub fn compact(&mut self) -> HashMap<Entity, Entity> {
+        self.flush();
+        if self.frozen_mutation() {
+            return HashMap::new();
+        }
+
+        let old_entities = self
+            .iter_entities_include_disabled()
+            .collect::<Vec<Entity>>();
+        let components = old_entities
+            .iter()
+            .map(|old_entity| {
+                self.entities.remove(old_entity.entity_id);
+                self.components.remove(old_entity.entity_id).unwrap()
+            })
+            .collect::<Vec<Map<dyn Any>>>();
+        free_every_slot(&mut self.entities, || Entity {
+            entity_id: DefaultKey::null(),
+        });
+        free_every_slot(&mut self.components, Map::new);
+        let mut entity_ids = EntityIds::default();
+        for (old_entity, components) in old_entities.iter().zip(components) {
+            let entity_id = self.components.insert(components);
+            self.entities.insert(Entity { entity_id });
+            entity_ids.insert(old_entity.entity_id, Entity { entity_id });
+        }
+        self.archetype_generation += 1;
+
+        for entities in self.entities_with_components.values_mut() {
+            remap_keys(entities, &entity_ids);
+            for (entity_id, entity) in entities.iter_mut() {
+                entity.entity_id = entity_id;
+            }
+        }
+        self.change_tracker.remap(&entity_ids);
+        self.component_masks.remap(&entity_ids);
+        self.archetype_table.remap(&entity_ids);
+        self.stable_ids.remap(&entity_ids);
+        if let Some(external_ids) = &mut self.external_ids {
+            external_ids.remap(&entity_ids);
+        }
+        remap_keys(&mut self.ref_counts, &entity_ids);
+        self.remap_temporary_tags(&entity_ids);
+        self.remap_pending_inits(&entity_ids);
+        for storage in self.storages.values_mut() {
+            storage.remap(&entity_ids);
+        }
+        for history in self.histories.values_mut() {
+            history.remap(&entity_ids);
+        }
+        for mailboxes in self.mailboxes.values_mut() {
+            mailboxes.remap(&entity_ids);
+        }
+        self.journal.clear();
+
+        self.remap_hierarchy(&entity_ids);
+        self.remap_entity_refs(&entity_ids);
+        let remaps = self
+            .entity_remaps
+            .values()
+            .copied()
+            .collect::<Vec<EntityRemap>>();
+        for remap in remaps {
+            remap(self, &entity_ids);
+        }
+
+        old_entities
+            .into_iter()
+            .map(|old_entity| (old_entity, entity_ids[&old_entity.entity_id]))
+            .collect()
+    }
+
+    fn remap_hierarchy(&mut self, entity_ids: &EntityIds) {
+        for components in self.components.values_mut() {
+            if let Some(parent) = components.get_mut::<Box<Parent>>() {
+                parent.0 = remap_entity(parent.0, entity_ids);
+            }
+            if let Some(children) = components.get_mut::<Box<Children>>() {
+                for child in children.children.iter_mut() {
+                    *child = remap_entity(*child, entity_ids);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    struct Follows;
+
+    #[test]
+    fn test_compact() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entities = (0..100)
+            .map(|i| entities_and_components.add_entity_with((Position { x: i as f32 },)))
+            .collect::<Vec<Entity>>();
+        for (i, entity) in entities.iter().enumerate() {
+            if i % 3 != 0 {
+                entities_and_components.remove_entity(*entity);
+            }
+        }
+        let kept = entities.into_iter().step_by(3).collect::<Vec<Entity>>();
+        entities_and_components.set_parent(kept[1], kept[0]);
+        entities_and_components.relate::<Follows>(kept[2], kept[3]);
+        entities_and_components.set_stable_id(kept[4], 7);
+
+        let moved = entities_and_components.compact();
+        assert_eq!(moved.len(), kept.len());
+        // the slots are reused, but the old handles don't point to the moved entities
+        for (old_entity, entity) in &moved {
+            assert!(
+                old_entity == entity || !entities_and_components.does_entity_exist(*old_entity)
+            );
+        }
+        assert_eq!(entities_and_components.get_entity_count(), kept.len());
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            kept.len()
+        );
+
+        // the entities are iterated in the same order with the same components
+        let compacted = entities_and_components.get_entities();
+        for (i, old_entity) in kept.iter().enumerate() {
+            let entity = moved[old_entity];
+            assert_eq!(compacted[i], entity);
+            let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+            assert_eq!(*position, Position { x: (i * 3) as f32 });
+        }
+
+        assert_eq!(
+            entities_and_components.get_parent(moved[&kept[1]]),
+            Some(moved[&kept[0]])
+        );
+        assert_eq!(
+            entities_and_components.get_children(moved[&kept[0]]),
+            vec![moved[&kept[1]]]
+        );
+        assert_eq!(
+            entities_and_components.relations_to::<Follows>(moved[&kept[3]]),
+            &[moved[&kept[2]]]
+        );
+        assert_eq!(
+            entities_and_components.get_entity_by_stable_id(7),
+            Some(moved[&kept[4]])
+        );
+    }
+}
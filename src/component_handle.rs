@@ -0,0 +1,139 @@
+use crate::*;
+use std::marker::PhantomData;
+
+/// Points at the component T of an entity without borrowing it, so it can be kept across frames,
+/// for example by a system that remembers which components it is interested in
+/// The component is looked up again every time the handle is resolved
+pub struct ComponentHandle<T> {
+    entity: Entity,
+    component: PhantomData<fn() -> T>,
+}
+
+impl<T: Component> ComponentHandle<T> {
+    /// Creates a handle to the component T of an entity, the entity doesn't need to have it yet
+    pub fn new(entity: Entity) -> Self {
+        ComponentHandle {
+            entity,
+            component: PhantomData,
+        }
+    }
+
+    /// Gets the entity the handle points into
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Gets the component, None if the entity has been removed or doesn't have the component
+    pub fn resolve<'a>(&self, entities_and_components: &'a EntitiesAndComponents) -> Option<&'a T> {
+        if !entities_and_components.does_entity_exist(self.entity) {
+            return None;
+        }
+        let (component,) = entities_and_components.try_get_components::<(T,)>(self.entity);
+        component
+    }
+
+    /// Gets the component mutably, None if the entity has been removed or doesn't have the component
+    pub fn resolve_mut<'a>(
+        &self,
+        entities_and_components: &'a mut EntitiesAndComponents,
+    ) -> Option<&'a mut T> {
+        if !entities_and_components.does_entity_exist(self.entity) {
+            return None;
+        }
+        let (component,) = entities_and_components.try_get_components_mut::<(T,)>(self.entity);
+        component
+    }
+}
+
+impl<T> Clone for ComponentHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ComponentHandle<T> {}
+
+impl<T> PartialEq for ComponentHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entity == other.entity
+    }
+}
+
+impl<T> Eq for ComponentHandle<T> {}
+
+impl<T> std::fmt::Debug for ComponentHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentHandle")
+            .field("entity", &self.entity)
+            .field("component", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Health(u32);
+
+    // the health shown on the boss bar
+    struct Shown(Option<u32>);
+
+    impl Resource for Shown {}
+
+    // remembers the boss's health between frames instead of looking the boss up again
+    struct BossBar {
+        boss: Option<ComponentHandle<Health>>,
+    }
+
+    impl System for BossBar {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            if self.boss.is_none() {
+                let boss = *engine
+                    .get_entities_with_component::<Health>()
+                    .next()
+                    .unwrap();
+                self.boss = Some(ComponentHandle::new(boss));
+            }
+            let shown = self
+                .boss
+                .and_then(|boss| boss.resolve(engine))
+                .map(|health| health.0);
+            engine.get_resource_mut::<Shown>().unwrap().0 = shown;
+        }
+    }
+
+    #[test]
+    fn test_component_handle() {
+        let mut world = World::new();
+        let boss = world
+            .entities_and_components
+            .add_entity_with((Health(100),));
+        world.entities_and_components.add_resource(Shown(None));
+        world.add_system(BossBar { boss: None });
+        world.run();
+
+        let handle = ComponentHandle::<Health>::new(boss);
+        handle
+            .resolve_mut(&mut world.entities_and_components)
+            .unwrap()
+            .0 -= 30;
+        world.run();
+        let shown = world
+            .entities_and_components
+            .get_resource::<Shown>()
+            .unwrap();
+        assert_eq!(shown.0, Some(70));
+
+        world.entities_and_components.remove_entity(boss);
+        world.run();
+        let shown = world
+            .entities_and_components
+            .get_resource::<Shown>()
+            .unwrap();
+        assert_eq!(shown.0, None);
+        assert!(handle
+            .resolve_mut(&mut world.entities_and_components)
+            .is_none());
+    }
+}
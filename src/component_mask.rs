@@ -0,0 +1,240 @@
+use crate::*;
+
+/// The most component types that can be part of a ComponentMask
+pub const MAX_MASK_COMPONENTS: usize = 128;
+
+/// A set of component types, one bit per type, built with EntitiesAndComponents::component_mask
+/// Checking an entity against a mask is a single bitwise and, instead of a lookup per component type
+/// Only your own component types get a bit, Parent, Children and relationships are never part of a mask
+/// Masks are only meaningful for the world that created them
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ComponentMask {
+    bits: u128,
+}
+
+impl ComponentMask {
+    /// Checks if every type in other is also in this mask
+    pub fn contains(&self, other: ComponentMask) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+// the bit of every component type, and the mask of the components on every entity
+pub(crate) struct ComponentMasks {
+    // by the TypeId the component is stored under, Box<T>
+    bits: FxHashMap<TypeId, u128>,
+    entities: SecondaryMap<DefaultKey, u128>,
+}
+
+impl ComponentMasks {
+    pub(crate) fn new() -> Self {
+        ComponentMasks {
+            bits: FxHashMap::default(),
+            entities: SecondaryMap::new(),
+        }
+    }
+
+    // gives the type the next free bit the first time it is seen, None once every bit is used
+    // the linked components are internal, so they don't use up bits
+    fn bit(&mut self, type_id: TypeId, linked_components: &FxHashSet<TypeId>) -> Option<u128> {
+        if let Some(bit) = self.bits.get(&type_id) {
+            return Some(*bit);
+        }
+        if self.bits.len() == MAX_MASK_COMPONENTS || linked_components.contains(&type_id) {
+            return None;
+        }
+        let bit = 1 << self.bits.len();
+        self.bits.insert(type_id, bit);
+        Some(bit)
+    }
+
+    // the bits of every type, or None if one of them doesn't have a bit
+    fn existing_bits(&self, type_ids: &[TypeId]) -> Option<u128> {
+        type_ids
+            .iter()
+            .map(|type_id| self.bits.get(type_id))
+            .try_fold(0, |bits, bit| Some(bits | bit?))
+    }
+
+    pub(crate) fn component_added(
+        &mut self,
+        entity_id: DefaultKey,
+        type_id: TypeId,
+        linked_components: &FxHashSet<TypeId>,
+    ) {
+        let Some(bit) = self.bit(type_id, linked_components) else {
+            return;
+        };
+        if let Some(mask) = self.entities.entry(entity_id) {
+            *mask.or_insert(0) |= bit;
+        }
+    }
+
+    pub(crate) fn component_removed(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        if let (Some(bit), Some(mask)) = (self.bits.get(&type_id), self.entities.get_mut(entity_id))
+        {
+            *mask &= !bit;
+        }
+    }
+
+    pub(crate) fn entity_removed(&mut self, entity_id: DefaultKey) {
+        self.entities.remove(entity_id);
+    }
+
+    // exchanges the masks of two entities, except the bits of the linked components that stay with the entity
+    pub(crate) fn swap_entities(
+        &mut self,
+        a: DefaultKey,
+        b: DefaultKey,
+        linked_components: &FxHashSet<TypeId>,
+    ) {
+        let linked = linked_components
+            .iter()
+            .filter_map(|type_id| self.bits.get(type_id))
+            .fold(0, |linked, bit| linked | bit);
+        let mask_a = self.entities.get(a).copied().unwrap_or(0);
+        let mask_b = self.entities.get(b).copied().unwrap_or(0);
+        self.entities
+            .insert(a, (mask_b & !linked) | (mask_a & linked));
+        self.entities
+            .insert(b, (mask_a & !linked) | (mask_b & linked));
+    }
+
+    pub(crate) fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.entities, entity_ids);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Builds a mask of the component types in the tuple, to be checked with matches_mask
+    /// The mask should be built once and reused, building it is slower than checking it
+    /// panics if the world has seen more than MAX_MASK_COMPONENTS component types,
+    /// or if a type is Parent, Children or a relationship
+    pub fn component_mask<T: ComponentTypes>(&mut self) -> ComponentMask {
+        let mut bits = 0;
+        for type_id in T::storage_type_ids() {
+            if self.linked_components.contains(&type_id) {
+                panic!("Parent, Children and relationships can't be part of a component mask");
+            }
+            bits |= self
+                .component_masks
+                .bit(type_id, &self.linked_components)
+                .unwrap_or_else(|| {
+                    panic!("Component masks can only hold {MAX_MASK_COMPONENTS} component types");
+                });
+        }
+        ComponentMask { bits }
+    }
+
+    // checks if an entity has every type, with a single bitwise and if every type has a mask bit
+    // type_ids are the TypeIds the components are stored under
+    pub(crate) fn has_all_matcher<'a>(
+        &'a self,
+        type_ids: &'a [TypeId],
+    ) -> impl Fn(&Entity) -> bool + 'a {
+        let bits = self.component_masks.existing_bits(type_ids);
+        move |entity| match bits {
+            Some(bits) => self
+                .component_masks
+                .entities
+                .get(entity.entity_id)
+                .is_some_and(|mask| mask & bits == bits),
+            None => type_ids
+                .iter()
+                .all(|type_id| is_in(self.entities_with_components.get(type_id), entity)),
+        }
+    }
+
+    /// Gets the mask of the component types on an entity
+    /// Only the first MAX_MASK_COMPONENTS component types the world has seen are included
+    pub fn entity_mask(&self, entity: Entity) -> ComponentMask {
+        ComponentMask {
+            bits: self
+                .component_masks
+                .entities
+                .get(entity.entity_id)
+                .copied()
+                .unwrap_or(0),
+        }
+    }
+
+    /// Checks if an entity has every component type in the mask
+    /// An entity that does not exist has no components
+    pub fn matches_mask(&self, entity: Entity, mask: ComponentMask) -> bool {
+        self.entity_mask(entity).contains(mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+    struct Velocity;
+    struct Health;
+    struct Sprite;
+
+    #[test]
+    fn test_matches_mask() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let moving = entities_and_components.add_entity_with((Position, Velocity));
+        let still = entities_and_components.add_entity_with((Position,));
+
+        let mask = entities_and_components.component_mask::<(Position, Velocity)>();
+        assert!(entities_and_components.matches_mask(moving, mask));
+        assert!(!entities_and_components.matches_mask(still, mask));
+
+        entities_and_components.add_component_to(still, Velocity);
+        assert!(entities_and_components.matches_mask(still, mask));
+        entities_and_components.remove_component_from::<Position>(moving);
+        assert!(!entities_and_components.matches_mask(moving, mask));
+
+        entities_and_components.remove_entity(still);
+        assert!(!entities_and_components.matches_mask(still, mask));
+
+        // the hierarchy is internal, so it doesn't take up a bit
+        let parent = entities_and_components.add_entity();
+        entities_and_components.set_parent(moving, parent);
+        assert_eq!(entities_and_components.component_masks.bits.len(), 2);
+    }
+
+    #[test]
+    fn bench_mask_matching() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entities = (0..100000)
+            .map(|i| {
+                if i % 2 == 0 {
+                    entities_and_components.add_entity_with((Position, Velocity, Health, Sprite))
+                } else {
+                    entities_and_components.add_entity_with((Position, Velocity, Health))
+                }
+            })
+            .collect::<Vec<Entity>>();
+
+        let sets = <(Position, Velocity, Health, Sprite)>::storage_type_ids()
+            .into_iter()
+            .map(|type_id| &entities_and_components.entities_with_components[&type_id])
+            .collect::<Vec<_>>();
+        let start = std::time::Instant::now();
+        let set_matches = entities
+            .iter()
+            .filter(|entity| sets.iter().all(|set| set.contains_key(entity.entity_id)))
+            .count();
+        let set_time = start.elapsed();
+
+        let mask = entities_and_components.component_mask::<(Position, Velocity, Health, Sprite)>();
+        let start = std::time::Instant::now();
+        let mask_matches = entities
+            .iter()
+            .filter(|entity| entities_and_components.matches_mask(**entity, mask))
+            .count();
+        let mask_time = start.elapsed();
+
+        assert_eq!(set_matches, 50000);
+        assert_eq!(mask_matches, 50000);
+        println!("Per type set lookups: {:?}", set_time);
+        println!("Mask matching: {:?}", mask_time);
+    }
+}
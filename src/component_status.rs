@@ -0,0 +1,77 @@
+use crate::*;
+
+/// Whether an entity has a component, returned by EntitiesAndComponents::inspect_component
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComponentStatus<T> {
+    /// The entity exists and has the component
+    Present(T),
+    /// The entity exists but does not have the component
+    AbsentOnLiveEntity,
+    /// The entity has been removed, or the handle was never valid
+    EntityDead,
+}
+
+impl<T> ComponentStatus<T> {
+    /// Gets the component if it is present
+    pub fn present(self) -> Option<T> {
+        match self {
+            ComponentStatus::Present(component) => Some(component),
+            _ => None,
+        }
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets a component on an entity, telling apart an entity without the component from an entity that doesn't exist
+    /// Unlike try_get_components this never panics, so it can be used with handles received over the network
+    pub fn inspect_component<T: Component>(&self, entity: Entity) -> ComponentStatus<&T> {
+        let Some(components) = self.components.get(entity.entity_id) else {
+            return ComponentStatus::EntityDead;
+        };
+        self.record_read::<T>();
+        match get_stored::<T>(&self.storages, components, entity) {
+            Some(component) => ComponentStatus::Present(component),
+            None => ComponentStatus::AbsentOnLiveEntity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn test_inspect_component() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Health(10),));
+
+        assert_eq!(
+            entities_and_components.inspect_component::<Health>(entity),
+            ComponentStatus::Present(&Health(10))
+        );
+
+        entities_and_components.remove_component_from::<Health>(entity);
+        assert_eq!(
+            entities_and_components.inspect_component::<Health>(entity),
+            ComponentStatus::AbsentOnLiveEntity
+        );
+
+        entities_and_components.remove_entity(entity);
+        assert_eq!(
+            entities_and_components.inspect_component::<Health>(entity),
+            ComponentStatus::EntityDead
+        );
+        // the slot is reused, but the old handle is still dead
+        entities_and_components.add_entity_with((Health(20),));
+        assert_eq!(
+            entities_and_components
+                .inspect_component::<Health>(entity)
+                .present(),
+            None
+        );
+    }
+}
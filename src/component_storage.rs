@@ -0,0 +1,376 @@
+use crate::*;
+
+/// Storage for every component of type T, used instead of the default per-entity storage
+/// after it is set with EntitiesAndComponents::set_storage
+/// Adding, removing, getting and despawning go through the storage,
+/// but get_all_components, try_get_component, get_many_mut, for_each_pair, ScopedAccess::get_mut, archetypes,
+/// transfer_with and undo_last only work with the default storage
+/// While any custom storage is set, World::run calls single_entity_step on one entity after another
+pub trait ComponentStorage<T>: Send + Sync {
+    /// Stores the component for the entity, returns the component it replaced
+    fn insert(&mut self, entity: Entity, component: T) -> Option<T>;
+
+    /// Removes the entity's component and returns it
+    fn remove(&mut self, entity: Entity) -> Option<T>;
+
+    /// Gets a reference to the entity's component
+    fn get(&self, entity: Entity) -> Option<&T>;
+
+    /// Gets a mutable reference to the entity's component
+    fn get_mut(&mut self, entity: Entity) -> Option<&mut T>;
+}
+
+// a ComponentStorage<T> with T erased, so storages of every type can be kept in one map
+pub(crate) trait ErasedStorage: Send + Sync {
+    // the TypeId the component is indexed under, Box<T>
+    fn index_type_id(&self) -> TypeId;
+
+    // returns true if the entity had a component
+    fn remove_entity(&mut self, entity: Entity) -> bool;
+
+    // moves every component to the entity it was moved to by compact
+    fn remap(&mut self, entity_ids: &EntityIds);
+
+    // exchanges the components of two entities, see swap_all_components
+    fn swap(&mut self, a: Entity, b: Entity);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedStorage<T>(Box<dyn ComponentStorage<T>>);
+
+impl<T: Component> ErasedStorage for TypedStorage<T> {
+    fn index_type_id(&self) -> TypeId {
+        TypeId::of::<Box<T>>()
+    }
+
+    fn remove_entity(&mut self, entity: Entity) -> bool {
+        self.0.remove(entity).is_some()
+    }
+
+    fn remap(&mut self, entity_ids: &EntityIds) {
+        // everything is taken out first, an old handle can be the same as the new handle of another entity
+        let components = entity_ids
+            .iter()
+            .filter_map(|(entity_id, new_entity)| {
+                let old_entity = Entity {
+                    entity_id: *entity_id,
+                };
+                self.0
+                    .remove(old_entity)
+                    .map(|component| (*new_entity, component))
+            })
+            .collect::<Vec<(Entity, T)>>();
+        for (entity, component) in components {
+            self.0.insert(entity, component);
+        }
+    }
+
+    fn swap(&mut self, a: Entity, b: Entity) {
+        let component_a = self.0.remove(a);
+        let component_b = self.0.remove(b);
+        if let Some(component_b) = component_b {
+            self.0.insert(a, component_b);
+        }
+        if let Some(component_a) = component_a {
+            self.0.insert(b, component_a);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// the custom storages, by TypeId::of::<T>()
+pub(crate) type Storages = FxHashMap<TypeId, Box<dyn ErasedStorage>>;
+
+fn storage_of<T: Component>(storages: &Storages) -> Option<&dyn ComponentStorage<T>> {
+    let storage = storages.get(&TypeId::of::<T>())?;
+    let storage = storage.as_any().downcast_ref::<TypedStorage<T>>().unwrap();
+    Some(&*storage.0)
+}
+
+fn storage_of_mut<T: Component>(storages: &mut Storages) -> Option<&mut dyn ComponentStorage<T>> {
+    let storage = storages.get_mut(&TypeId::of::<T>())?;
+    let storage = storage
+        .as_any_mut()
+        .downcast_mut::<TypedStorage<T>>()
+        .unwrap();
+    Some(&mut *storage.0)
+}
+
+// gets the entity's T from wherever T is stored, components are the entity's components in the default storage
+// these take the fields instead of self, so they can be used while other fields are borrowed
+pub(crate) fn get_stored<'a, T: Component>(
+    storages: &'a Storages,
+    components: &'a Map<dyn Any>,
+    entity: Entity,
+) -> Option<&'a T> {
+    match storage_of::<T>(storages) {
+        Some(storage) => storage.get(entity),
+        None => components.get::<Box<T>>().map(|component| &**component),
+    }
+}
+
+pub(crate) fn get_stored_mut<'a, T: Component>(
+    storages: &'a mut Storages,
+    components: &'a mut Map<dyn Any>,
+    entity: Entity,
+) -> Option<&'a mut T> {
+    match storage_of_mut::<T>(storages) {
+        Some(storage) => storage.get_mut(entity),
+        None => components
+            .get_mut::<Box<T>>()
+            .map(|component| &mut **component),
+    }
+}
+
+pub(crate) fn insert_stored<T: Component>(
+    storages: &mut Storages,
+    components: &mut Map<dyn Any>,
+    entity: Entity,
+    component: T,
+) -> Option<T> {
+    match storage_of_mut::<T>(storages) {
+        Some(storage) => storage.insert(entity, component),
+        None => components
+            .insert(Box::new(component))
+            .map(|component| *component),
+    }
+}
+
+pub(crate) fn remove_stored<T: Component>(
+    storages: &mut Storages,
+    components: &mut Map<dyn Any>,
+    entity: Entity,
+) -> Option<T> {
+    match storage_of_mut::<T>(storages) {
+        Some(storage) => storage.remove(entity),
+        None => components.remove::<Box<T>>().map(|component| *component),
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Stores every component of type T in storage instead of the default storage
+    /// The components of type T already in the world are moved into the new storage
+    pub fn set_storage<T: Component>(&mut self, storage: Box<dyn ComponentStorage<T>>) {
+        if self.frozen_mutation() {
+            return;
+        }
+        let entities = self
+            .entities_with_components
+            .get(&TypeId::of::<Box<T>>())
+            .map(|entities| entities.values().copied().collect::<Vec<Entity>>())
+            .unwrap_or_default();
+
+        let mut moved = Vec::with_capacity(entities.len());
+        for entity in entities {
+            let components = &mut self.components[entity.entity_id];
+            if let Some(component) = remove_stored::<T>(&mut self.storages, components, entity) {
+                moved.push((entity, component));
+                // components in a custom storage are not part of the archetype
+                self.refresh_archetype(entity);
+            }
+        }
+
+        self.storages
+            .insert(TypeId::of::<T>(), Box::new(TypedStorage(storage)));
+        let storage = storage_of_mut::<T>(&mut self.storages).unwrap();
+        for (entity, component) in moved {
+            storage.insert(entity, component);
+        }
+    }
+
+    /// Checks if components of type T are kept in a storage set with set_storage
+    pub fn has_custom_storage<T: Component>(&self) -> bool {
+        self.storages.contains_key(&TypeId::of::<T>())
+    }
+
+    // removes a despawned entity from every custom storage and from their indexes
+    pub(crate) fn remove_from_storages(&mut self, entity: Entity) {
+        for storage in self.storages.values_mut() {
+            if storage.remove_entity(entity) {
+                if let Some(entities) = self
+                    .entities_with_components
+                    .get_mut(&storage.index_type_id())
+                {
+                    entities.remove(entity.entity_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity;
+
+    struct HashMapStorage<T> {
+        map: HashMap<Entity, T>,
+        // lets the test see how many components are in the storage after it is moved into the world
+        len: Arc<AtomicUsize>,
+    }
+
+    impl<T: Send + Sync> ComponentStorage<T> for HashMapStorage<T> {
+        fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+            let previous = self.map.insert(entity, component);
+            self.len.store(self.map.len(), Ordering::SeqCst);
+            previous
+        }
+
+        fn remove(&mut self, entity: Entity) -> Option<T> {
+            let removed = self.map.remove(&entity);
+            self.len.store(self.map.len(), Ordering::SeqCst);
+            removed
+        }
+
+        fn get(&self, entity: Entity) -> Option<&T> {
+            self.map.get(&entity)
+        }
+
+        fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+            self.map.get_mut(&entity)
+        }
+    }
+
+    #[test]
+    fn test_hash_map_storage_round_trip() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let existing = entities_and_components.add_entity_with((Position { x: 1.0 }, Velocity));
+        let len = Arc::new(AtomicUsize::new(0));
+        entities_and_components.set_storage::<Position>(Box::new(HashMapStorage {
+            map: HashMap::new(),
+            len: len.clone(),
+        }));
+        assert!(entities_and_components.has_custom_storage::<Position>());
+        // the existing component was moved into the storage
+        assert_eq!(len.load(Ordering::SeqCst), 1);
+        let (position,) = entities_and_components.get_components::<(Position,)>(existing);
+        assert_eq!(*position, Position { x: 1.0 });
+
+        let entity = entities_and_components.add_entity_with((Position { x: 2.0 },));
+        assert_eq!(len.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            2
+        );
+
+        let (position, velocity) =
+            entities_and_components.try_get_components_mut::<(Position, Velocity)>(entity);
+        assert!(velocity.is_none());
+        position.unwrap().x = 3.0;
+        let (position,) = entities_and_components.try_get_components::<(Position,)>(entity);
+        assert_eq!(position, Some(&Position { x: 3.0 }));
+
+        entities_and_components.remove_component_from::<Position>(existing);
+        assert_eq!(len.load(Ordering::SeqCst), 1);
+        let (position,) = entities_and_components.try_get_components::<(Position,)>(existing);
+        assert!(position.is_none());
+
+        entities_and_components.remove_entity(entity);
+        assert_eq!(len.load(Ordering::SeqCst), 0);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_accessors_use_custom_storage() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.set_storage::<Position>(Box::new(HashMapStorage {
+            map: HashMap::new(),
+            len: Arc::new(AtomicUsize::new(0)),
+        }));
+        let entities = (0..3)
+            .map(|i| entities_and_components.add_entity_with((Position { x: i as f32 },)))
+            .collect::<Vec<Entity>>();
+
+        entities_and_components.for_each_component_mut::<Position>(|_, position, _| {
+            position.x += 1.0;
+        });
+        entities_and_components.par_for_each_mut::<Position>(|_, position| position.x *= 2.0);
+        assert_eq!(
+            entities_and_components
+                .query_where(|_, position: &Position| position.x > 3.0)
+                .map(|(entity, _)| entity)
+                .collect::<Vec<Entity>>(),
+            vec![entities[1], entities[2]]
+        );
+        assert_eq!(
+            entities_and_components
+                .max_by_component(|position: &Position| position.x)
+                .map(|(entity, _)| entity),
+            Some(entities[2])
+        );
+    }
+
+    // moves every position and notes which threads it ran on
+    struct MoveSystem {
+        threads: Arc<Mutex<HashSet<std::thread::ThreadId>>>,
+    }
+
+    impl System for MoveSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            self.threads
+                .lock()
+                .unwrap()
+                .insert(std::thread::current().id());
+            let (position,) = single_entity.get_components_mut::<(Position,)>();
+            position.x += 1.0;
+        }
+
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_single_entity_step_with_custom_storage() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.set_storage::<Position>(Box::new(HashMapStorage {
+            map: HashMap::new(),
+            len: Arc::new(AtomicUsize::new(0)),
+        }));
+        let entities = (0..1000)
+            .map(|i| entities_and_components.add_entity_with((Position { x: i as f32 },)))
+            .collect::<Vec<Entity>>();
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        world.add_system(MoveSystem {
+            threads: threads.clone(),
+        });
+
+        world.run();
+        world.run();
+
+        for (i, entity) in entities.iter().enumerate() {
+            let (position,) = world
+                .entities_and_components
+                .get_components::<(Position,)>(*entity);
+            assert_eq!(position.x, i as f32 + 2.0);
+        }
+        // the storage was never shared between threads
+        assert_eq!(threads.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::*;
+
+// gets the component T of an entity as &dyn Any, wherever it is stored
+pub(crate) type ComponentAccessor =
+    for<'a> fn(&'a EntitiesAndComponents, Entity) -> Option<&'a dyn Any>;
+
+pub(crate) fn get_component_as_any<T: Component>(
+    entities_and_components: &EntitiesAndComponents,
+    entity: Entity,
+) -> Option<&dyn Any> {
+    let components = entities_and_components.components.get(entity.entity_id)?;
+    get_stored::<T>(&entities_and_components.storages, components, entity)
+        .map(|component| component as &dyn Any)
+}
+
+/// Visits the components of an entity without knowing their types, see EntitiesAndComponents::visit_components
+/// This is meant for generic tooling like saving or an inspector
+pub trait ComponentVisitor {
+    /// Called with every component, type_id is TypeId::of::<T>() and name is the type name of the component
+    /// The component can be downcast to its type with downcast_ref
+    fn visit(&mut self, type_id: TypeId, name: &'static str, component: &dyn Any);
+}
+
+impl EntitiesAndComponents {
+    /// Gets the types (TypeId::of::<T>()) of the components on an entity, in the order they were added
+    /// Overwriting a component keeps its place, removing and adding it again moves it to the end
+    /// panics if the entity does not exist
+    pub fn type_ids_on_entity(&self, entity: Entity) -> Vec<TypeId> {
+        self.storage_type_ids_on_entity(entity)
+            .into_iter()
+            .map(|type_id| *self.component_type_ids.get(&type_id).unwrap_or(&type_id))
+            .collect()
+    }
+
+    /// Calls the visitor with every component on an entity, in the same order as type_ids_on_entity
+    /// Components with a custom storage are visited too
+    /// panics if the entity does not exist
+    pub fn visit_components(&self, entity: Entity, visitor: &mut dyn ComponentVisitor) {
+        for type_id in self.storage_type_ids_on_entity(entity) {
+            let Some(component) = self
+                .component_accessors
+                .get(&type_id)
+                .and_then(|accessor| accessor(self, entity))
+            else {
+                continue;
+            };
+            visitor.visit(
+                *self.component_type_ids.get(&type_id).unwrap_or(&type_id),
+                self.component_type_names
+                    .get(&type_id)
+                    .copied()
+                    .unwrap_or("an unnamed component"),
+                component,
+            );
+        }
+    }
+
+    fn storage_type_ids_on_entity(&self, entity: Entity) -> Vec<TypeId> {
+        if !self.does_entity_exist(entity) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        self.change_tracker.component_types(entity.entity_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity;
+
+    struct Health;
+
+    // collects the names of the components, and reads the one type it knows
+    #[derive(Default)]
+    struct NameCollector {
+        names: Vec<&'static str>,
+        x: Option<f32>,
+    }
+
+    impl ComponentVisitor for NameCollector {
+        fn visit(&mut self, type_id: TypeId, name: &'static str, component: &dyn Any) {
+            self.names.push(name);
+            if type_id == TypeId::of::<Position>() {
+                self.x = Some(component.downcast_ref::<Position>().unwrap().x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_components() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Velocity, Position { x: 2.0 }));
+        entities_and_components.add_component_to(entity, Health);
+        // overwriting keeps the order
+        entities_and_components.add_component_to(entity, Velocity);
+
+        let mut visitor = NameCollector::default();
+        entities_and_components.visit_components(entity, &mut visitor);
+        assert_eq!(
+            visitor.names,
+            vec![
+                std::any::type_name::<Velocity>(),
+                std::any::type_name::<Position>(),
+                std::any::type_name::<Health>(),
+            ]
+        );
+        assert_eq!(visitor.x, Some(2.0));
+        assert_eq!(
+            entities_and_components.type_ids_on_entity(entity),
+            vec![
+                TypeId::of::<Velocity>(),
+                TypeId::of::<Position>(),
+                TypeId::of::<Health>(),
+            ]
+        );
+    }
+}
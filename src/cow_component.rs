@@ -0,0 +1,105 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// A component whose value can be shared between many entities, for large data that is read often and written rarely
+/// Cloning a CowComponent only shares the value, so every entity given a clone uses the same memory
+/// Reading, including through get_components, uses the shared value,
+/// the value is only cloned the first time it is accessed mutably, including through get_components_mut,
+/// and only if it is still shared at that point
+pub struct CowComponent<T> {
+    value: Arc<T>,
+}
+
+impl<T> CowComponent<T> {
+    /// Creates a new CowComponent that doesn't share its value with anything yet
+    pub fn new(value: T) -> Self {
+        CowComponent {
+            value: Arc::new(value),
+        }
+    }
+
+    /// Checks if the value is shared with another CowComponent, meaning the next mutable access will clone it
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.value) > 1
+    }
+}
+
+impl<T> Clone for CowComponent<T> {
+    fn clone(&self) -> Self {
+        CowComponent {
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> Deref for CowComponent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone> DerefMut for CowComponent<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    struct MeshTable {
+        handles: Vec<u32>,
+    }
+
+    impl Clone for MeshTable {
+        fn clone(&self) -> Self {
+            CLONES.fetch_add(1, Ordering::Relaxed);
+            MeshTable {
+                handles: self.handles.clone(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cow_component_clones_on_first_write() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let table = CowComponent::new(MeshTable {
+            handles: vec![1, 2, 3],
+        });
+        let entities = (0..10)
+            .map(|_| entities_and_components.add_entity_with((table.clone(),)))
+            .collect::<Vec<Entity>>();
+        drop(table);
+
+        for entity in &entities {
+            let (table,) =
+                entities_and_components.get_components::<(CowComponent<MeshTable>,)>(*entity);
+            assert_eq!(table.handles, vec![1, 2, 3]);
+            assert!(table.is_shared());
+        }
+        assert_eq!(CLONES.load(Ordering::Relaxed), 0);
+
+        let (table,) =
+            entities_and_components.get_components_mut::<(CowComponent<MeshTable>,)>(entities[0]);
+        table.handles.push(4);
+        table.handles.push(5);
+        assert!(!table.is_shared());
+        assert_eq!(CLONES.load(Ordering::Relaxed), 1);
+
+        let (other_table,) =
+            entities_and_components.get_components::<(CowComponent<MeshTable>,)>(entities[1]);
+        assert_eq!(other_table.handles, vec![1, 2, 3]);
+        let (table,) =
+            entities_and_components.get_components::<(CowComponent<MeshTable>,)>(entities[0]);
+        assert_eq!(table.handles, vec![1, 2, 3, 4, 5]);
+    }
+}
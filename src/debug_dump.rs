@@ -0,0 +1,127 @@
+use crate::*;
+use std::fmt::Write;
+
+impl EntitiesAndComponents {
+    /// Builds a human readable report of everything in the world, to paste into a bug report
+    /// It has the entity count, the number of entities with every component type, every archetype,
+    /// the resources and how much space has been allocated
+    /// Component types are listed by their type name, sorted so the same world always gives the same report
+    pub fn debug_dump(&self) -> String {
+        let mut dump = String::new();
+        // writing to a String can't fail
+        self.write_debug_dump(&mut dump).unwrap();
+        dump
+    }
+
+    fn write_debug_dump(&self, dump: &mut String) -> std::fmt::Result {
+        let disabled = self
+            .disabled_entities()
+            .map_or(0, |disabled| disabled.len());
+        writeln!(dump, "World debug dump")?;
+        writeln!(dump, "Frame: {}", self.frame)?;
+        writeln!(
+            dump,
+            "Entities: {} ({disabled} disabled)",
+            self.get_entity_count_include_disabled()
+        )?;
+
+        let mut components = self
+            .entities_with_components
+            .iter()
+            .filter(|(_, entities)| !entities.is_empty())
+            .map(|(type_id, entities)| (self.storage_type_name(type_id), entities.len()))
+            .collect::<Vec<(&str, usize)>>();
+        components.sort();
+        writeln!(dump, "Components: {} types", components.len())?;
+        for (name, count) in components {
+            writeln!(dump, "    {name}: {count}")?;
+        }
+
+        // archetypes() gives TypeId::of::<T>(), but the names are stored by the TypeId the component is stored under
+        let names = self
+            .component_type_ids
+            .iter()
+            .map(|(storage_type_id, type_id)| (*type_id, self.storage_type_name(storage_type_id)))
+            .collect::<FxHashMap<TypeId, &str>>();
+        let mut archetypes = self
+            .archetypes()
+            .map(|(_, component_types, entity_count)| {
+                let mut component_names = component_types
+                    .iter()
+                    .map(|type_id| {
+                        names
+                            .get(type_id)
+                            .copied()
+                            .unwrap_or("an unnamed component")
+                    })
+                    .collect::<Vec<&str>>();
+                component_names.sort();
+                (component_names.join(", "), entity_count)
+            })
+            .collect::<Vec<(String, usize)>>();
+        archetypes.sort();
+        writeln!(dump, "Archetypes: {}", archetypes.len())?;
+        for (component_names, entity_count) in archetypes {
+            writeln!(dump, "    [{component_names}]: {entity_count}")?;
+        }
+
+        let mut resources = self
+            .resources
+            .values()
+            .map(|resource| resource.type_name())
+            .collect::<Vec<&str>>();
+        resources.sort();
+        writeln!(dump, "Resources: {}", resources.len())?;
+        for name in resources {
+            writeln!(dump, "    {name}")?;
+        }
+
+        writeln!(dump, "Capacity:")?;
+        writeln!(dump, "    entities: {}", self.entities.capacity())?;
+        writeln!(dump, "    component maps: {}", self.components.capacity())?;
+        match self.max_entities {
+            Some(max_entities) => writeln!(dump, "    max entities: {max_entities}")?,
+            None => writeln!(dump, "    max entities: unlimited")?,
+        }
+        writeln!(dump, "    queued commands: {}", self.commands.len())?;
+        Ok(())
+    }
+
+    fn storage_type_name(&self, storage_type_id: &TypeId) -> &'static str {
+        self.component_type_names
+            .get(storage_type_id)
+            .copied()
+            .unwrap_or("an unnamed component")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+
+    struct Velocity;
+
+    struct Gravity;
+
+    impl Resource for Gravity {}
+
+    #[test]
+    fn test_debug_dump() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        for _ in 0..3 {
+            entities_and_components.add_entity_with((Position, Velocity));
+        }
+        let still = entities_and_components.add_entity_with((Position,));
+        entities_and_components.disable_entity(still);
+        entities_and_components.add_resource(Gravity);
+
+        let dump = entities_and_components.debug_dump();
+        assert!(dump.contains("Entities: 4 (1 disabled)"));
+        assert!(dump.contains(&format!("{}: 4", std::any::type_name::<Position>())));
+        assert!(dump.contains(&format!("{}: 3", std::any::type_name::<Velocity>())));
+        assert!(dump.contains(std::any::type_name::<Gravity>()));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::*;
+use std::fmt::Debug;
+
+// formats the component of one registered type on an entity, if the entity has it
+pub(crate) type ComponentFormatter = fn(&EntitiesAndComponents, Entity) -> Option<String>;
+
+impl EntitiesAndComponents {
+    /// Registers a name for a component type so it can be found by eval_path
+    /// Registering another type with the same name replaces it
+    pub fn register_component_name<T: Component + Debug>(&mut self, name: &str) {
+        self.component_formatters
+            .insert(name.to_string(), format_component::<T>);
+    }
+
+    /// Resolves a path like "entity:3/Position" to the Debug representation of the component, for debug consoles
+    /// The first part picks the entity, "entity:N" is the Nth entity and "stable:N" is the entity with the stable id N,
+    /// the second part is a component name registered with register_component_name
+    /// Fields can't be resolved yet, so a path with more than two parts gives None
+    /// returns None if the path is malformed or the entity or component does not exist
+    pub fn eval_path(&self, path: &str) -> Option<String> {
+        let mut parts = path.split('/');
+        let entity = self.resolve_path_entity(parts.next()?)?;
+        let formatter = self.component_formatters.get(parts.next()?)?;
+        if parts.next().is_some() {
+            return None;
+        }
+        formatter(self, entity)
+    }
+
+    fn resolve_path_entity(&self, part: &str) -> Option<Entity> {
+        let (kind, id) = part.split_once(':')?;
+        let id = id.parse::<u64>().ok()?;
+        match kind {
+            "entity" => self.get_nth_entity(usize::try_from(id).ok()?),
+            "stable" => self.get_entity_by_stable_id(id),
+            _ => None,
+        }
+    }
+}
+
+fn format_component<T: Component + Debug>(
+    entities_and_components: &EntitiesAndComponents,
+    entity: Entity,
+) -> Option<String> {
+    entities_and_components
+        .try_get_component::<T>(entity)
+        .map(|component| format!("{:?}", component))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[test]
+    fn test_eval_path() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_component_name::<Position>("Position");
+
+        entities_and_components.add_entity();
+        let entity = entities_and_components.add_entity_with((Position { x: 1.0, y: 2.0 },));
+        entities_and_components.set_stable_id(entity, 42);
+
+        assert_eq!(
+            entities_and_components.eval_path("entity:1/Position"),
+            Some("Position { x: 1.0, y: 2.0 }".to_string())
+        );
+        assert_eq!(
+            entities_and_components.eval_path("stable:42/Position"),
+            Some("Position { x: 1.0, y: 2.0 }".to_string())
+        );
+
+        // the first entity has no position
+        assert_eq!(entities_and_components.eval_path("entity:0/Position"), None);
+        assert_eq!(entities_and_components.eval_path("entity:5/Position"), None);
+        assert_eq!(entities_and_components.eval_path("entity:1/Velocity"), None);
+        assert_eq!(
+            entities_and_components.eval_path("entity:1/Position/x"),
+            None
+        );
+        assert_eq!(entities_and_components.eval_path("Position"), None);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::*;
+
+/// What happens to a child when its parent is removed with despawn_hierarchy_with_policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DespawnAction {
+    /// The child is removed along with its own children, like with remove_entity
+    Destroy,
+    /// The child is kept and attached to the removed entity's parent, or becomes a root entity if there is none
+    Reparent,
+}
+
+impl EntitiesAndComponents {
+    /// Removes an entity and its children, except the children the policy keeps with DespawnAction::Reparent
+    /// For example the items in a container can be dropped into the room when the container breaks
+    /// The policy is only asked about the direct children, a kept child keeps all of its own children
+    /// If the entity does not exist, the error handler is called, or it panics if there is no handler
+    pub fn despawn_hierarchy_with_policy(
+        &mut self,
+        entity: Entity,
+        policy: impl Fn(Entity) -> DespawnAction,
+    ) {
+        if self.frozen_mutation() {
+            return;
+        }
+        if !self.does_entity_exist(entity) {
+            return self.report_error(EcsError::EntityNotFound { entity });
+        }
+
+        let parent = self.get_parent(entity);
+        for child in self.get_children(entity) {
+            if policy(child) == DespawnAction::Reparent {
+                self.remove_parent(child);
+                if let Some(parent) = parent {
+                    self.set_parent(child, parent);
+                }
+            }
+        }
+        self.remove_entity(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dropped;
+
+    #[test]
+    fn test_despawn_hierarchy_with_policy() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let room = entities_and_components.add_entity();
+        let container = entities_and_components.add_entity();
+        let item = entities_and_components.add_entity_with((Dropped,));
+        let lid = entities_and_components.add_entity();
+        let hinge = entities_and_components.add_entity();
+        let item_part = entities_and_components.add_entity();
+        entities_and_components.set_parent(container, room);
+        entities_and_components.set_parent(item, container);
+        entities_and_components.set_parent(lid, container);
+        entities_and_components.set_parent(hinge, lid);
+        entities_and_components.set_parent(item_part, item);
+
+        let dropped = entities_and_components
+            .get_entities_with_component::<Dropped>()
+            .copied()
+            .collect::<Vec<Entity>>();
+        entities_and_components.despawn_hierarchy_with_policy(container, |child| {
+            if dropped.contains(&child) {
+                DespawnAction::Reparent
+            } else {
+                DespawnAction::Destroy
+            }
+        });
+
+        assert!(!entities_and_components.does_entity_exist(container));
+        assert!(!entities_and_components.does_entity_exist(lid));
+        assert!(!entities_and_components.does_entity_exist(hinge));
+        assert!(entities_and_components.does_entity_exist(item));
+        assert!(entities_and_components.does_entity_exist(item_part));
+        assert_eq!(entities_and_components.get_parent(item), Some(room));
+        assert_eq!(entities_and_components.get_children(room), vec![item]);
+        assert_eq!(entities_and_components.get_parent(item_part), Some(item));
+    }
+}
@@ -0,0 +1,148 @@
+use crate::*;
+
+impl World {
+    /// Runs only the systems whose is_read_only returns true, for an inspection pass like computing stats
+    /// Nothing else runs, resources are not updated and the frame count stays the same
+    /// Read only systems can't change components, but they can still queue commands, which are never applied here
+    /// In debug builds this panics if a system queued a command or the world changed,
+    /// since that means the system isn't really read only
+    pub fn run_diagnostics(&mut self) {
+        let tick = self.entities_and_components.current_tick();
+        let entity_count = self
+            .entities_and_components
+            .get_entity_count_include_disabled();
+        let mut systems = self
+            .systems
+            .iter()
+            .filter(|(_, system)| system.is_read_only())
+            .collect::<Vec<(DefaultKey, &Box<dyn SystemWrapper + Send + Sync>)>>();
+        let queues = run_with_own_commands(
+            &mut self.entities_and_components,
+            &mut systems,
+            |(_, system), thread_safe_entities_and_components| {
+                system.run_readonly(thread_safe_entities_and_components)
+            },
+        );
+
+        if cfg!(debug_assertions) {
+            for ((system_id, _), commands) in systems.iter().zip(queues) {
+                let name = self
+                    .system_name(&SystemHandle {
+                        system_id: *system_id,
+                    })
+                    .unwrap();
+                assert!(
+                    commands.is_empty(),
+                    "System {name:?} is read only but queued {} commands in run_diagnostics",
+                    commands.len()
+                );
+            }
+            assert!(
+                self.entities_and_components.current_tick() == tick
+                    && self
+                        .entities_and_components
+                        .get_entity_count_include_disabled()
+                        == entity_count,
+                "The world changed during run_diagnostics, a read only system is not read only"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Health(u32);
+
+    // counts the living entities
+    struct StatsSystem {
+        alive: AtomicUsize,
+    }
+
+    impl System for StatsSystem {
+        fn is_read_only(&self) -> bool {
+            true
+        }
+
+        fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+            let alive = engine
+                .get_entities_with_component::<Health>()
+                .filter(|entity| {
+                    let (health,) = engine.get_components::<(Health,)>(**entity);
+                    health.0 > 0
+                })
+                .count();
+            self.alive.store(alive, Ordering::Relaxed);
+        }
+    }
+
+    // claims to be read only, but removes the dead entities
+    struct CleanupSystem {}
+
+    impl System for CleanupSystem {
+        fn is_read_only(&self) -> bool {
+            true
+        }
+
+        fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+            for entity in engine.get_entities() {
+                let (health,) = engine.get_components::<(Health,)>(entity);
+                if health.0 == 0 {
+                    engine.commands().remove_entity(entity);
+                }
+            }
+        }
+    }
+
+    // would change the world if it ran
+    struct DamageSystem {}
+
+    impl System for DamageSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            for entity in engine.get_entities() {
+                engine.remove_entity(entity);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_diagnostics() {
+        let mut world = World::new();
+        for health in [0, 10, 20] {
+            world
+                .entities_and_components
+                .add_entity_with((Health(health),));
+        }
+        let stats = world.add_system(StatsSystem {
+            alive: AtomicUsize::new(0),
+        });
+        world.add_system(DamageSystem {});
+
+        world.run_diagnostics();
+
+        assert_eq!(world.entities_and_components.get_entity_count(), 3);
+        assert_eq!(world.frame(), 0);
+        let stats = world.systems[stats.system_id]
+            .as_any()
+            .downcast_ref::<StatsSystem>()
+            .unwrap();
+        assert_eq!(stats.alive.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is read only but queued 1 commands")]
+    fn test_run_diagnostics_panics_on_mutation() {
+        let mut world = World::new();
+        for health in [0, 10, 20] {
+            world
+                .entities_and_components
+                .add_entity_with((Health(health),));
+        }
+        world.add_system(CleanupSystem {});
+
+        world.run_diagnostics();
+    }
+}
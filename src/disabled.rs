@@ -0,0 +1,326 @@
+use crate::*;
+
+/// A tag for entities that are paused, like a hibernating level section or a pooled object
+/// Disabled entities are skipped by get_entities, get_nth_entity, get_entity_count, get_entities_with_component, entities_with_any,
+/// get_entity_count_with_component, get_entity_with_component, select, and everything built on them
+/// (query_where, count_matching, min_by_component, max_by_component, changed_since, for_each_entity_mut,
+/// for_each_component_mut, find_map_mut, for_each_pair, run_query_with_commands, iter_entities_sorted_by_key,
+/// with_component_buffer_mut, any_with_component, entities_with_component_rev, entities_in_layers),
+/// and single_entity_step is not run for them
+/// The first eight have _include_disabled variants that return disabled entities too
+/// Disabled entities can still be accessed directly, for example with get_components
+pub struct Disabled;
+
+impl EntitiesAndComponents {
+    /// Disables an entity by adding the Disabled tag to it
+    /// panics if the entity does not exist
+    pub fn disable_entity(&mut self, entity: Entity) {
+        self.add_component_to(entity, Disabled);
+    }
+
+    /// Enables an entity by removing the Disabled tag from it
+    /// panics if the entity does not exist
+    pub fn enable_entity(&mut self, entity: Entity) {
+        self.remove_component_from::<Disabled>(entity);
+    }
+
+    /// Checks if an entity has the Disabled tag
+    pub fn is_entity_disabled(&self, entity: Entity) -> bool {
+        is_in(self.disabled_entities(), &entity)
+    }
+
+    // the number of disabled entities with the component type, by the TypeId it is stored under
+    pub(crate) fn disabled_count(&self, type_id: TypeId) -> usize {
+        self.disabled_counts.get(&type_id).copied().unwrap_or(0)
+    }
+
+    // called after a component was added to or removed from an entity, type_id is the TypeId it is stored under
+    // adding or removing Disabled itself counts or uncounts every component of the entity
+    pub(crate) fn count_disabled_component(
+        &mut self,
+        entity: Entity,
+        type_id: TypeId,
+        added: bool,
+    ) {
+        let disabled_type_id = TypeId::of::<Box<Disabled>>();
+        if type_id == disabled_type_id {
+            let mut type_ids =
+                self.entity_storage_type_ids(entity, &self.components[entity.entity_id]);
+            if !added {
+                type_ids.push(disabled_type_id);
+            }
+            self.count_disabled(type_ids, added);
+        } else if self.is_entity_disabled(entity) {
+            self.count_disabled([type_id], added);
+        }
+    }
+
+    // called when all the components of an entity are added or removed at once, for example when it is despawned
+    // the components have to be in place, so this is called after they are added and before they are removed
+    pub(crate) fn count_disabled_entity(&mut self, entity: Entity, added: bool) {
+        let components = &self.components[entity.entity_id];
+        if components.contains::<Box<Disabled>>() {
+            let type_ids = self.entity_storage_type_ids(entity, components);
+            self.count_disabled(type_ids, added);
+        }
+    }
+
+    fn count_disabled(&mut self, type_ids: impl IntoIterator<Item = TypeId>, added: bool) {
+        for type_id in type_ids {
+            let count = self.disabled_counts.entry(type_id).or_insert(0);
+            if added {
+                *count += 1;
+            } else {
+                *count -= 1;
+            }
+        }
+    }
+
+    // the TypeIds every component of the entity is stored under, in the default storage and the custom storages
+    fn entity_storage_type_ids(&self, entity: Entity, components: &Map<dyn Any>) -> Vec<TypeId> {
+        let mut type_ids = components.as_raw().keys().copied().collect::<Vec<TypeId>>();
+        for storage in self.storages.values() {
+            let type_id = storage.index_type_id();
+            if is_in(self.entities_with_components.get(&type_id), &entity) {
+                type_ids.push(type_id);
+            }
+        }
+        type_ids
+    }
+
+    /// gets the disabled entities, or None if there are none so callers can skip filtering
+    pub(crate) fn disabled_entities(&self) -> Option<&SecondaryMap<DefaultKey, Entity>> {
+        self.entities_with_components
+            .get(&TypeId::of::<Box<Disabled>>())
+            .filter(|disabled| !disabled.is_empty())
+    }
+}
+
+pub(crate) fn is_in(entities: Option<&SecondaryMap<DefaultKey, Entity>>, entity: &Entity) -> bool {
+    entities.is_some_and(|entities| entities.contains_key(entity.entity_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity {
+        x: f32,
+    }
+
+    struct MovementSystem {}
+
+    impl System for MovementSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            if let (Some(position), Some(velocity)) =
+                single_entity.try_get_components_mut::<(Position, Velocity)>()
+            {
+                position.x += velocity.x;
+            }
+        }
+
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    fn world_with_one_disabled() -> (World, Entity, Entity) {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let enabled =
+            entities_and_components.add_entity_with((Position { x: 0.0 }, Velocity { x: 1.0 }));
+        let disabled =
+            entities_and_components.add_entity_with((Position { x: 10.0 }, Velocity { x: 1.0 }));
+        entities_and_components.disable_entity(disabled);
+        (world, enabled, disabled)
+    }
+
+    #[test]
+    fn test_disabled_excluded_from_entity_lists() {
+        let (world, enabled, disabled) = world_with_one_disabled();
+        let entities_and_components = &world.entities_and_components;
+
+        assert!(entities_and_components.is_entity_disabled(disabled));
+        assert_eq!(entities_and_components.get_entities(), vec![enabled]);
+        assert_eq!(
+            entities_and_components.get_entities_include_disabled(),
+            vec![enabled, disabled]
+        );
+        assert_eq!(
+            entities_and_components
+                .iter_entities()
+                .collect::<Vec<Entity>>(),
+            vec![enabled]
+        );
+        assert_eq!(
+            entities_and_components
+                .iter_entities_include_disabled()
+                .count(),
+            2
+        );
+
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Position>()
+                .copied()
+                .collect::<Vec<Entity>>(),
+            vec![enabled]
+        );
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component_include_disabled::<Position>()
+                .count(),
+            2
+        );
+
+        assert_eq!(
+            entities_and_components
+                .entities_with_any::<(Position, Velocity)>()
+                .collect::<Vec<Entity>>(),
+            vec![enabled]
+        );
+        assert_eq!(
+            entities_and_components
+                .entities_with_any_include_disabled::<(Position, Velocity)>()
+                .count(),
+            2
+        );
+
+        assert_eq!(entities_and_components.get_nth_entity(1), None);
+        assert_eq!(
+            entities_and_components.get_nth_entity_include_disabled(1),
+            Some(disabled)
+        );
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+        assert_eq!(
+            entities_and_components.get_entity_count_include_disabled(),
+            2
+        );
+
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            1
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component_include_disabled::<Position>(),
+            2
+        );
+        assert_eq!(
+            entities_and_components.get_entity_with_component::<Position>(1),
+            None
+        );
+        assert_eq!(
+            entities_and_components.get_entity_with_component_include_disabled::<Position>(1),
+            Some(disabled)
+        );
+    }
+
+    #[test]
+    fn test_disabled_counts() {
+        let (mut world, enabled, disabled) = world_with_one_disabled();
+        let entities_and_components = &mut world.entities_and_components;
+
+        // components added to and removed from a disabled entity are counted as disabled
+        entities_and_components.add_component_to(enabled, 1_u32);
+        entities_and_components.add_component_to(disabled, 2_u32);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<u32>(),
+            1
+        );
+        entities_and_components.remove_component_from::<Velocity>(disabled);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            1
+        );
+
+        entities_and_components.enable_entity(disabled);
+        assert_eq!(entities_and_components.get_entity_count(), 2);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<u32>(),
+            2
+        );
+
+        entities_and_components.disable_entity(enabled);
+        entities_and_components.swap_all_components(enabled, disabled);
+        assert!(entities_and_components.is_entity_disabled(disabled));
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            0
+        );
+
+        entities_and_components.remove_entity(disabled);
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            1
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Disabled>(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_disabled_excluded_from_queries() {
+        let (mut world, enabled, _) = world_with_one_disabled();
+        let entities_and_components = &mut world.entities_and_components;
+
+        assert_eq!(
+            entities_and_components
+                .query_where(|_, _: &Position| true)
+                .map(|(entity, _)| entity)
+                .collect::<Vec<Entity>>(),
+            vec![enabled]
+        );
+        assert_eq!(
+            entities_and_components
+                .max_by_component(|position: &Position| position.x)
+                .map(|(entity, _)| entity),
+            Some(enabled)
+        );
+
+        let tick = entities_and_components.current_tick();
+        for entity in entities_and_components.get_entities_include_disabled() {
+            entities_and_components.try_get_component_mut::<Position>(entity);
+        }
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(tick)
+                .collect::<Vec<Entity>>(),
+            vec![enabled]
+        );
+
+        let mut visited = vec![];
+        entities_and_components
+            .for_each_entity_mut(|single_entity| visited.push(single_entity.entity));
+        entities_and_components
+            .for_each_component_mut::<Position>(|entity, _, _| visited.push(entity));
+        assert_eq!(visited, vec![enabled, enabled]);
+    }
+
+    #[test]
+    fn test_disabled_skipped_by_single_entity_step() {
+        let (mut world, enabled, disabled) = world_with_one_disabled();
+        world.add_system(MovementSystem {});
+        world.run();
+
+        let entities_and_components = &mut world.entities_and_components;
+        let (position,) = entities_and_components.get_components::<(Position,)>(enabled);
+        assert_eq!(*position, Position { x: 1.0 });
+        let (position,) = entities_and_components.get_components::<(Position,)>(disabled);
+        assert_eq!(*position, Position { x: 10.0 });
+
+        // enabling it again makes it move
+        entities_and_components.enable_entity(disabled);
+        world.run();
+        let (position,) = world
+            .entities_and_components
+            .get_components::<(Position,)>(disabled);
+        assert_eq!(*position, Position { x: 11.0 });
+    }
+}
@@ -0,0 +1,150 @@
+use crate::*;
+
+/// A component that keeps last frame's value next to this frame's value
+/// Systems read the previous value of any entity with read and write their own current value with write,
+/// so the order entities are updated in does not matter, even in single_entity_step
+/// The buffers are swapped by swap_double_buffers, which World::run calls at the start of every frame
+/// The type has to be registered with register_double_buffered for its buffers to be swapped
+pub struct DoubleBuffered<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T: Clone> DoubleBuffered<T> {
+    /// Creates a new DoubleBuffered with both buffers set to value
+    pub fn new(value: T) -> Self {
+        DoubleBuffered {
+            previous: value.clone(),
+            current: value,
+        }
+    }
+}
+
+impl<T> DoubleBuffered<T> {
+    /// Gets the value from the previous frame
+    pub fn read(&self) -> &T {
+        &self.previous
+    }
+
+    /// Gets the value for the current frame mutably
+    /// After a swap this holds the value from two frames ago, so it should be overwritten, not updated
+    pub fn write(&mut self) -> &mut T {
+        &mut self.current
+    }
+
+    fn swap(&mut self) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Registers DoubleBuffered<T> so its buffers are swapped by swap_double_buffers
+    /// Registering the same type more than once does nothing
+    pub fn register_double_buffered<T: Component>(&mut self) {
+        self.double_buffer_swaps
+            .insert(TypeId::of::<T>(), swap_double_buffers_of::<T>);
+    }
+
+    /// Swaps the buffers of every registered DoubleBuffered component, making the current values readable
+    /// World::run calls this at the start of every frame, so it only has to be called manually without World::run
+    pub fn swap_double_buffers(&mut self) {
+        if self.double_buffer_swaps.is_empty() {
+            return;
+        }
+        if self.frozen_mutation() {
+            return;
+        }
+
+        let swaps = self
+            .double_buffer_swaps
+            .values()
+            .copied()
+            .collect::<Vec<fn(&mut EntitiesAndComponents)>>();
+        for swap in swaps {
+            swap(self);
+        }
+    }
+}
+
+fn swap_double_buffers_of<T: Component>(entities_and_components: &mut EntitiesAndComponents) {
+    let type_id = TypeId::of::<Box<DoubleBuffered<T>>>();
+    let Some(entities) = entities_and_components
+        .entities_with_components
+        .get(&type_id)
+    else {
+        return;
+    };
+
+    for entity_id in entities.keys() {
+        if let Some(double_buffered) = entities_and_components
+            .components
+            .get_mut(entity_id)
+            .and_then(|components| components.get_mut::<Box<DoubleBuffered<T>>>())
+        {
+            double_buffered.swap();
+            entities_and_components
+                .change_tracker
+                .component_changed(entity_id, type_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    // moves every boid halfway towards the average position of the others
+    struct FlockSystem {}
+
+    impl System for FlockSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            let entities = engine.get_entities();
+            for &entity in &entities {
+                let mut sum = 0.0;
+                for &neighbor in &entities {
+                    if neighbor != entity {
+                        let (position,) =
+                            engine.get_components::<(DoubleBuffered<Position>,)>(neighbor);
+                        sum += position.read().x;
+                    }
+                }
+                let average = sum / (entities.len() - 1) as f32;
+
+                let (position,) = engine.get_components_mut::<(DoubleBuffered<Position>,)>(entity);
+                let x = position.read().x;
+                *position.write() = Position {
+                    x: x + (average - x) / 2.0,
+                };
+            }
+        }
+    }
+
+    #[test]
+    fn test_double_buffered_boids_step() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_double_buffered::<Position>();
+
+        let boids = [0.0, 3.0, 6.0].map(|x| {
+            entities_and_components.add_entity_with((DoubleBuffered::new(Position { x }),))
+        });
+
+        world.add_system(FlockSystem {});
+        world.run();
+
+        // every boid moved using only the positions from before the step,
+        // even though the boids before it had already written their new positions
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.swap_double_buffers();
+        for (boid, expected) in boids.into_iter().zip([2.25, 3.0, 3.75]) {
+            let (position,) =
+                entities_and_components.get_components::<(DoubleBuffered<Position>,)>(boid);
+            assert_eq!(*position.read(), Position { x: expected });
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::*;
+
+/// Remembers how far through the world's entities a piece of work has got, see EntitiesAndComponents::cursor_advance
+/// This lets expensive per-entity work be spread over many frames while entities are spawned and removed in between
+/// Entities removed before the cursor reaches them are skipped, and no entity is returned twice
+#[derive(Debug, Default)]
+pub struct EntityCursor {
+    // the entities left to return, in reverse so the next one can be popped off the end
+    remaining: Vec<Entity>,
+    // every entity that has been returned or is still to come, only kept when new entities are included
+    seen: Option<FxHashSet<Entity>>,
+    started: bool,
+}
+
+impl EntityCursor {
+    /// Creates a cursor over the entities that exist when it is first advanced
+    /// Entities spawned after that are not returned
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a cursor that also returns entities spawned while it is being advanced, once it has reached the end
+    pub fn including_new() -> Self {
+        EntityCursor {
+            seen: Some(FxHashSet::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Checks if the cursor has been advanced past the last entity
+    /// A cursor that includes new entities can continue after this if more entities are spawned
+    pub fn is_finished(&self) -> bool {
+        self.started && self.remaining.is_empty()
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Returns up to n entities that the cursor hasn't returned yet, fewer once it reaches the end
+    /// Disabled entities are included, since the cursor is meant for work like saving that covers the whole world
+    pub fn cursor_advance(&self, cursor: &mut EntityCursor, n: usize) -> Vec<Entity> {
+        if !cursor.started || (cursor.remaining.is_empty() && cursor.seen.is_some()) {
+            cursor.started = true;
+            self.refill_cursor(cursor);
+        }
+
+        let mut entities = Vec::with_capacity(n.min(cursor.remaining.len()));
+        while entities.len() < n {
+            let Some(entity) = cursor.remaining.pop() else {
+                break;
+            };
+            if self.does_entity_exist(entity) {
+                entities.push(entity);
+            }
+        }
+        entities
+    }
+
+    fn refill_cursor(&self, cursor: &mut EntityCursor) {
+        let entities = self.iter_entities_include_disabled();
+        cursor.remaining = match &mut cursor.seen {
+            Some(seen) => entities.filter(|entity| seen.insert(*entity)).collect(),
+            None => entities.collect(),
+        };
+        cursor.remaining.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_advance() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entities = (0..10)
+            .map(|_| entities_and_components.add_entity())
+            .collect::<Vec<Entity>>();
+
+        let mut cursor = EntityCursor::including_new();
+        let mut processed = FxHashSet::default();
+        let mut spawned = Vec::new();
+        for frame in 0..10 {
+            for entity in entities_and_components.cursor_advance(&mut cursor, 3) {
+                assert!(processed.insert(entity), "{entity:?} was processed twice");
+            }
+
+            // the world changes between frames
+            if frame == 0 {
+                entities_and_components.remove_entity(entities[8]);
+                entities_and_components.remove_entity(entities[1]);
+                spawned.push(entities_and_components.add_entity());
+            }
+        }
+
+        assert!(cursor.is_finished());
+        // entities[1] was already processed, entities[8] was removed before the cursor got to it
+        assert_eq!(processed.len(), 10);
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(processed.contains(entity), i != 8);
+        }
+        assert!(processed.contains(&spawned[0]));
+
+        // without new entities the cursor stops at the entities that existed when it started
+        let mut cursor = EntityCursor::new();
+        let first = entities_and_components.cursor_advance(&mut cursor, 100);
+        entities_and_components.add_entity();
+        assert_eq!(first.len(), 9);
+        assert!(entities_and_components
+            .cursor_advance(&mut cursor, 100)
+            .is_empty());
+    }
+}
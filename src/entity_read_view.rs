@@ -0,0 +1,127 @@
+use crate::*;
+use std::marker::PhantomData;
+
+/// Immutable access to the components of one entity, except the one borrowed mutably by get_mut_and_rest
+pub struct EntityReadView<'a> {
+    entities_and_components: *const EntitiesAndComponents,
+    entity: Entity,
+    // the type borrowed mutably, TypeId::of::<T>()
+    excluded: TypeId,
+    lifetime: PhantomData<&'a EntitiesAndComponents>,
+}
+
+impl<'a> EntityReadView<'a> {
+    /// Gets the entity the view is of
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Gets a reference to a component on the entity
+    /// If the component does not exist on the entity, it will return None
+    /// panics if T is the component borrowed mutably
+    pub fn try_get<T: Component>(&self) -> Option<&'a T> {
+        if TypeId::of::<T>() == self.excluded {
+            panic!(
+                "Component of type {type:?} is borrowed mutably, use the mutable reference instead",
+                type = std::any::type_name::<T>()
+            );
+        }
+        // SAFETY: the only component borrowed mutably is the excluded one, which is never read here,
+        // and the world itself is borrowed mutably by get_mut_and_rest for as long as the view is alive
+        let entities_and_components = unsafe { &*self.entities_and_components };
+        entities_and_components.record_read::<T>();
+        get_stored::<T>(
+            &entities_and_components.storages,
+            &entities_and_components.components[self.entity.entity_id],
+            self.entity,
+        )
+    }
+
+    /// Gets a reference to a component on the entity
+    /// panics if the component does not exist on the entity or is the component borrowed mutably
+    pub fn get<T: Component>(&self) -> &'a T {
+        self.try_get::<T>().unwrap_or_else(|| {
+            panic!(
+                "Component of type {type:?} does not exist on entity {entity:?}",
+                type = std::any::type_name::<T>(),
+                entity = self.entity
+            );
+        })
+    }
+
+    /// Checks if the entity has a certain component, the one borrowed mutably counts as well
+    pub fn has<T: Component>(&self) -> bool {
+        TypeId::of::<T>() == self.excluded || self.try_get::<T>().is_some()
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets a mutable reference to the component T on an entity,
+    /// along with a view that can read every other component on the same entity
+    /// Useful for updating one component based on the rest of the entity
+    /// panics if the entity does not exist or does not have T
+    pub fn get_mut_and_rest<T: Component>(
+        &mut self,
+        entity: Entity,
+    ) -> (&mut T, EntityReadView<'_>) {
+        let (component,) = self.get_components_mut::<(T,)>(entity);
+        let pointer = component as *mut T;
+        let view = EntityReadView {
+            entities_and_components: self as *const EntitiesAndComponents,
+            entity,
+            excluded: TypeId::of::<T>(),
+            lifetime: PhantomData,
+        };
+        // SAFETY: the view never reads T, so the mutable reference is the only reference to the component,
+        // every other component is stored separately from it and is only ever read while both are alive
+        (unsafe { &mut *pointer }, view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sprite {
+        tint: f32,
+    }
+
+    struct Health(f32);
+
+    struct Status {
+        poisoned: bool,
+    }
+
+    #[test]
+    fn test_get_mut_and_rest() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((
+            Sprite { tint: 1.0 },
+            Health(0.5),
+            Status { poisoned: true },
+        ));
+
+        let (sprite, rest) = entities_and_components.get_mut_and_rest::<Sprite>(entity);
+        assert!(rest.has::<Sprite>());
+        sprite.tint = rest.get::<Health>().0;
+        if rest.get::<Status>().poisoned {
+            sprite.tint /= 2.0;
+        }
+        assert!(rest.try_get::<u32>().is_none());
+
+        let (sprite,) = entities_and_components.get_components::<(Sprite,)>(entity);
+        assert_eq!(sprite.tint, 0.25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_get_mut_and_rest_excludes_the_mutable_component() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Sprite { tint: 1.0 },));
+
+        let (_, rest) = entities_and_components.get_mut_and_rest::<Sprite>(entity);
+        rest.get::<Sprite>();
+    }
+}
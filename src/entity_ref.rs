@@ -0,0 +1,158 @@
+use crate::*;
+
+// calls the function with the EntityRef field of the holder's component, if the holder still has the component
+pub(crate) type EntityRefField =
+    Box<dyn Fn(&mut EntitiesAndComponents, Entity, &mut dyn FnMut(&mut EntityRef)) + Send + Sync>;
+
+// where entity_refs[target] = every holder with an EntityRef that was pointed at the target, and how to reach its field
+pub(crate) type EntityRefs = SecondaryMap<DefaultKey, Vec<(Entity, EntityRefField)>>;
+
+/// A weak reference to an entity, stored as a field of a component and set with EntitiesAndComponents::set_entity_ref
+/// When the entity it points at is removed, the reference is cleared, so upgrade returns None
+/// instead of a handle to an entity that no longer exists
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntityRef {
+    target: Option<Entity>,
+}
+
+impl EntityRef {
+    /// Creates a reference that doesn't point at anything, to be set later with set_entity_ref
+    pub fn dead() -> Self {
+        EntityRef { target: None }
+    }
+
+    /// Gets the entity the reference points at, None if it was never set or the entity has been removed
+    pub fn upgrade(&self) -> Option<Entity> {
+        self.target
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Points the EntityRef field of a component on holder at target, field picks the field out of the component
+    /// The world remembers the field, so it is cleared when target is removed, which only costs as much
+    /// as the number of references to target
+    /// Setting the field directly would skip this, so only set it through this function
+    /// panics if holder does not exist or does not have the component T
+    pub fn set_entity_ref<T: Component>(
+        &mut self,
+        holder: Entity,
+        target: Entity,
+        field: fn(&mut T) -> &mut EntityRef,
+    ) {
+        let alive = self.does_entity_exist(target);
+        let (component,) = self.get_components_mut::<(T,)>(holder);
+        field(component).target = alive.then_some(target);
+        if !alive {
+            return;
+        }
+
+        let field: EntityRefField = Box::new(move |entities_and_components, holder, f| {
+            if !entities_and_components.does_entity_exist(holder) {
+                return;
+            }
+            if let (Some(component),) =
+                entities_and_components.try_get_components_mut::<(T,)>(holder)
+            {
+                f(field(component));
+            }
+        });
+        if let Some(refs) = self.entity_refs.entry(target.entity_id) {
+            refs.or_default().push((holder, field));
+        }
+    }
+
+    // clears every reference to an entity that is leaving the world
+    pub(crate) fn clear_entity_refs(&mut self, entity_id: DefaultKey) {
+        let Some(refs) = self.entity_refs.remove(entity_id) else {
+            return;
+        };
+        let target = Entity { entity_id };
+        for (holder, field) in refs {
+            // the field may have been pointed at another entity since
+            field(self, holder, &mut |entity_ref| {
+                if entity_ref.target == Some(target) {
+                    entity_ref.target = None;
+                }
+            });
+        }
+    }
+
+    // the components holding the EntityRefs were swapped between the two entities, see swap_all_components
+    pub(crate) fn swap_entity_ref_holders(&mut self, a: Entity, b: Entity) {
+        for refs in self.entity_refs.values_mut() {
+            for (holder, _) in refs.iter_mut() {
+                if *holder == a {
+                    *holder = b;
+                } else if *holder == b {
+                    *holder = a;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn remap_entity_refs(&mut self, entity_ids: &EntityIds) {
+        let mut entity_refs = std::mem::take(&mut self.entity_refs);
+        remap_keys(&mut entity_refs, entity_ids);
+        for refs in entity_refs.values_mut() {
+            for (holder, field) in refs.iter_mut() {
+                *holder = remap_entity(*holder, entity_ids);
+                field(self, *holder, &mut |entity_ref| {
+                    entity_ref.target = entity_ref
+                        .target
+                        .map(|target| remap_entity(target, entity_ids));
+                });
+            }
+        }
+        self.entity_refs = entity_refs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Target {
+        entity: EntityRef,
+    }
+
+    #[test]
+    fn test_entity_ref_cleared_on_despawn() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let enemy = entities_and_components.add_entity();
+        let other_enemy = entities_and_components.add_entity();
+        let turrets = [0, 1, 2].map(|_| {
+            entities_and_components.add_entity_with((Target {
+                entity: EntityRef::dead(),
+            },))
+        });
+        entities_and_components
+            .set_entity_ref(turrets[0], enemy, |target: &mut Target| &mut target.entity);
+        entities_and_components
+            .set_entity_ref(turrets[1], enemy, |target: &mut Target| &mut target.entity);
+        entities_and_components.set_entity_ref(turrets[2], other_enemy, |target: &mut Target| {
+            &mut target.entity
+        });
+        // turret 1 switched targets, so removing enemy must not clear it
+        entities_and_components.set_entity_ref(turrets[1], other_enemy, |target: &mut Target| {
+            &mut target.entity
+        });
+
+        entities_and_components.remove_entity(enemy);
+
+        let targets = turrets.map(|turret| {
+            let (target,) = entities_and_components.get_components::<(Target,)>(turret);
+            target.entity.upgrade()
+        });
+        assert_eq!(targets, [None, Some(other_enemy), Some(other_enemy)]);
+
+        // references survive compact with the new handle
+        entities_and_components.remove_entity(turrets[0]);
+        let moved = entities_and_components.compact();
+        let (target,) = entities_and_components.get_components::<(Target,)>(moved[&turrets[2]]);
+        assert_eq!(target.entity.upgrade(), Some(moved[&other_enemy]));
+        entities_and_components.remove_entity(moved[&other_enemy]);
+        let (target,) = entities_and_components.get_components::<(Target,)>(moved[&turrets[1]]);
+        assert_eq!(target.entity.upgrade(), None);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::Entity;
+use std::fmt;
+
+/// Errors returned by the fallible methods of EntitiesAndComponents
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcsError {
+    /// An entity could not be added because the world already has the maximum number of entities
+    /// set with set_max_entities
+    CapacityExceeded {
+        /// The entity limit that was hit
+        max_entities: usize,
+    },
+    /// An entity handle was used after the entity was removed, or it was never valid
+    EntityNotFound {
+        /// The entity that does not exist
+        entity: Entity,
+    },
+    /// A component that an entity does not have was accessed
+    ComponentNotFound {
+        /// The entity without the component
+        entity: Entity,
+        /// The name of the component type
+        component: &'static str,
+    },
+    /// add_component_by_name was called with a name that no component type was registered under with register_factory
+    FactoryNotFound,
+    /// WorldPatch::from_bytes was given bytes that are not a patch encoded with WorldPatch::to_bytes
+    InvalidPatch,
+    /// apply_patch was given a patch with a component whose net id was not registered with register_replicated
+    UnknownNetId {
+        /// The net id that no component type was registered with
+        net_id: u16,
+    },
+    /// apply_patch was given a patch with a component that Replicated::decode could not decode
+    InvalidComponent {
+        /// The net id of the component type
+        net_id: u16,
+    },
+    /// The world was mutated while it was frozen with freeze, this is only returned in release builds
+    Frozen,
+}
+
+impl fmt::Display for EcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EcsError::CapacityExceeded { max_entities } => write!(
+                f,
+                "The world already has the maximum of {max_entities} entities"
+            ),
+            EcsError::EntityNotFound { entity } => write!(
+                f,
+                "Entity ID {entity:?} does not exist, was the Entity ID edited?"
+            ),
+            EcsError::ComponentNotFound { entity, component } => write!(
+                f,
+                "Component of type {component:?} does not exist on entity {entity:?}"
+            ),
+            EcsError::FactoryNotFound => write!(
+                f,
+                "No component type has been registered under that name with register_factory"
+            ),
+            EcsError::Frozen => write!(
+                f,
+                "The world is frozen and can't be mutated, call thaw first"
+            ),
+            EcsError::InvalidPatch => write!(f, "The bytes are not a patch encoded with to_bytes"),
+            EcsError::UnknownNetId { net_id } => write!(
+                f,
+                "No component type has been registered with the net id {net_id} with register_replicated"
+            ),
+            EcsError::InvalidComponent { net_id } => write!(
+                f,
+                "The component with the net id {net_id} could not be decoded"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EcsError {}
+
+/// Returned by clone_subset when some of the components on the entities can't be cloned
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotCloneableError {
+    /// The names of the component types that have not been registered with register_clone, sorted
+    pub components: Vec<&'static str>,
+}
+
+impl fmt::Display for NotCloneableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "These components have not been registered with register_clone: {}",
+            self.components.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NotCloneableError {}
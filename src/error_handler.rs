@@ -0,0 +1,90 @@
+use crate::*;
+
+pub(crate) type ErrorHandler = Box<dyn Fn(EcsError) + Send + Sync>;
+
+impl EntitiesAndComponents {
+    /// Sets a handler that is called with recoverable errors instead of panicking, the operation that failed does nothing
+    /// Recoverable errors are stale entity handles passed to remove_entity, add_component_to and remove_component_from,
+    /// and missing components in with_component_mut
+    /// The handler can log the error, count it or panic itself
+    pub fn set_error_handler(&mut self, handler: Box<dyn Fn(EcsError) + Send + Sync>) {
+        self.error_handler = Some(handler);
+    }
+
+    /// Removes the error handler, so recoverable errors panic again
+    pub fn clear_error_handler(&mut self) {
+        self.error_handler = None;
+    }
+
+    /// Calls f with a mutable reference to a component on an entity
+    /// If the entity does not exist or does not have the component, the error handler is called and f is skipped,
+    /// or it panics if there is no handler
+    pub fn with_component_mut<T: Component>(&mut self, entity: Entity, f: impl FnOnce(&mut T)) {
+        if !self.does_entity_exist(entity) {
+            return self.report_error(EcsError::EntityNotFound { entity });
+        }
+        match self.try_get_components_mut::<(T,)>(entity) {
+            (Some(component),) => f(component),
+            (None,) => self.report_error(EcsError::ComponentNotFound {
+                entity,
+                component: std::any::type_name::<T>(),
+            }),
+        }
+    }
+
+    // calls the error handler, or panics with the error if there is none
+    pub(crate) fn report_error(&self, error: EcsError) {
+        match &self.error_handler {
+            Some(handler) => handler(error),
+            None => panic!("{error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Health(u32);
+
+    struct Shield;
+
+    #[test]
+    fn test_error_handler() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let errors = Arc::new(AtomicUsize::new(0));
+        let counter = errors.clone();
+        entities_and_components.set_error_handler(Box::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        let entity = entities_and_components.add_entity_with((Health(10),));
+        entities_and_components.with_component_mut(entity, |health: &mut Health| health.0 -= 1);
+        assert_eq!(errors.load(Ordering::SeqCst), 0);
+
+        // the entity has no Shield, so the closure is skipped
+        entities_and_components.with_component_mut(entity, |_: &mut Shield| {
+            panic!("the entity has no shield");
+        });
+        assert_eq!(errors.load(Ordering::SeqCst), 1);
+
+        // a stale handle
+        entities_and_components.remove_entity(entity);
+        entities_and_components.remove_entity(entity);
+        entities_and_components.add_component_to(entity, Shield);
+        assert_eq!(errors.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not exist on entity")]
+    fn test_missing_component_panics_without_handler() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity_with((Health(10),));
+
+        entities_and_components.with_component_mut(entity, |_: &mut Shield| {});
+    }
+}
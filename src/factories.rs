@@ -0,0 +1,88 @@
+use crate::*;
+
+// adds a default constructed component of one registered type to an entity
+pub(crate) type ComponentFactory = fn(&mut EntitiesAndComponents, Entity);
+
+fn add_default_component<T: Component + Default>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+) {
+    entities_and_components.add_component_to(entity, T::default());
+}
+
+impl EntitiesAndComponents {
+    /// Registers a name for a component type so add_component_by_name can add it, for config files and scripts
+    /// Registering another type with the same name replaces it
+    pub fn register_factory<T: Component + Default>(&mut self, name: &str) {
+        self.factories
+            .insert(name.to_string(), add_default_component::<T>);
+    }
+
+    /// Adds the default value of the component type registered with register_factory under the name
+    /// If the entity already has the component, it is replaced with the default
+    /// returns an error if no type is registered under the name or the entity does not exist
+    pub fn add_component_by_name(
+        &mut self,
+        entity: Entity,
+        type_name: &str,
+    ) -> Result<(), EcsError> {
+        let factory = *self
+            .factories
+            .get(type_name)
+            .ok_or(EcsError::FactoryNotFound)?;
+        if !self.does_entity_exist(entity) {
+            return Err(EcsError::EntityNotFound { entity });
+        }
+        factory(self, entity);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    impl Default for Health {
+        fn default() -> Self {
+            Health(100)
+        }
+    }
+
+    #[test]
+    fn test_add_component_by_name() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_factory::<Position>("Position");
+        entities_and_components.register_factory::<Health>("Health");
+
+        let entity = entities_and_components.add_entity();
+        for name in ["Position", "Health"] {
+            entities_and_components
+                .add_component_by_name(entity, name)
+                .unwrap();
+        }
+        let (position, health) =
+            entities_and_components.get_components::<(Position, Health)>(entity);
+        assert_eq!(*position, Position { x: 0.0, y: 0.0 });
+        assert_eq!(*health, Health(100));
+
+        assert_eq!(
+            entities_and_components.add_component_by_name(entity, "Velocity"),
+            Err(EcsError::FactoryNotFound)
+        );
+        entities_and_components.remove_entity(entity);
+        assert_eq!(
+            entities_and_components.add_component_by_name(entity, "Health"),
+            Err(EcsError::EntityNotFound { entity })
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::*;
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex};
+
+// the size of a new chunk, a bigger allocation gets a chunk big enough for it
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// one block of memory that allocations are bumped out of
+struct Chunk {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// the memory is only reached through the regions handed out of it, which never overlap
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+impl Chunk {
+    fn new(size: usize) -> Self {
+        let layout = Layout::from_size_align(size, 16).unwrap();
+        // the size is never 0
+        let ptr = unsafe { alloc(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            handle_alloc_error(layout);
+        };
+        Chunk { ptr, layout }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+// the chunks of a FrameScratch and how much of them has been handed out this frame
+struct Bump {
+    chunks: Vec<Arc<Chunk>>,
+    // the chunk allocations are bumped out of, the chunks before it are used up
+    current: usize,
+    // how many bytes of the current chunk are used
+    offset: usize,
+    // how many bytes have been handed out since the last reset
+    allocated: usize,
+}
+
+impl Bump {
+    // hands out a region fitting layout, with the chunk it is in so the region can be kept alive
+    fn alloc(&mut self, layout: Layout) -> (NonNull<u8>, Arc<Chunk>) {
+        loop {
+            let Some(chunk) = self.chunks.get(self.current) else {
+                self.chunks.push(Arc::new(Chunk::new(
+                    CHUNK_SIZE.max(layout.size() + layout.align()),
+                )));
+                continue;
+            };
+            let start = chunk.ptr.as_ptr() as usize + self.offset;
+            let padding = start.next_multiple_of(layout.align()) - start;
+            if self.offset + padding + layout.size() > chunk.layout.size() {
+                // the rest of the chunk is left unused until the next frame
+                self.current += 1;
+                self.offset = 0;
+                continue;
+            }
+
+            let ptr = unsafe { chunk.ptr.as_ptr().add(self.offset + padding) };
+            self.offset += padding + layout.size();
+            self.allocated += layout.size();
+            return (NonNull::new(ptr).unwrap(), chunk.clone());
+        }
+    }
+
+    fn reset(&mut self) {
+        // a chunk still holding a ScratchVec from an earlier frame is left to it and freed when it is dropped,
+        // only the scratch can hand out new references to a chunk, so a chunk nothing else holds stays unused
+        self.chunks.retain(|chunk| Arc::strong_count(chunk) == 1);
+        self.current = 0;
+        self.offset = 0;
+        self.allocated = 0;
+    }
+}
+
+/// A resource with a bump allocator for temporary storage that only lives for one frame,
+/// so systems don't pressure the allocator with new Vecs every frame
+/// alloc_vec hands out Vecs from a few big chunks of memory, and everything allocated is freed at once
+/// at the start of every World::run, after which the chunks are reused, add it with add_resource to use it
+/// A vec that is kept past the end of its frame stays valid, its chunk is just not reused until it is dropped
+pub struct FrameScratch {
+    bump: Arc<Mutex<Bump>>,
+}
+
+impl FrameScratch {
+    /// Creates a new scratch, it doesn't allocate until the first vec is pushed to
+    pub fn new() -> Self {
+        FrameScratch {
+            bump: Arc::new(Mutex::new(Bump {
+                chunks: Vec::new(),
+                current: 0,
+                offset: 0,
+                allocated: 0,
+            })),
+        }
+    }
+
+    /// Gets an empty vec whose memory comes from the scratch
+    /// Growing it moves it to a bigger region of the scratch, the old region is freed with the rest of the frame
+    /// It can be taken out of the world, so components can be accessed while it is in use
+    pub fn alloc_vec<T>(&self) -> ScratchVec<T> {
+        ScratchVec {
+            ptr: NonNull::dangling(),
+            len: 0,
+            // zero sized values never need memory
+            capacity: if std::mem::size_of::<T>() == 0 {
+                usize::MAX
+            } else {
+                0
+            },
+            chunk: None,
+            bump: self.bump.clone(),
+            values: PhantomData,
+        }
+    }
+
+    /// Gets how many bytes have been handed out since the scratch was last reset
+    pub fn bytes_allocated_this_frame(&self) -> usize {
+        self.bump.lock().unwrap().allocated
+    }
+
+    /// Gets how many chunks of memory the scratch is bumping allocations out of
+    pub fn chunk_count(&self) -> usize {
+        self.bump.lock().unwrap().chunks.len()
+    }
+
+    /// Frees everything allocated this frame at once so the memory can be reused,
+    /// World::run calls this through early_update
+    pub fn reset(&mut self) {
+        self.bump.lock().unwrap().reset();
+    }
+}
+
+impl Default for FrameScratch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resource for FrameScratch {
+    fn early_update(&mut self) {
+        self.reset();
+    }
+
+    // reset before any other resource can use it
+    fn priority(&self) -> i32 {
+        i32::MIN
+    }
+
+    fn runs_while_paused(&self) -> bool {
+        true
+    }
+}
+
+/// A vec from FrameScratch::alloc_vec, its values are dropped with it but its memory is only freed with the frame
+pub struct ScratchVec<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+    // keeps the memory the values are in alive, None until something is pushed
+    chunk: Option<Arc<Chunk>>,
+    bump: Arc<Mutex<Bump>>,
+    values: PhantomData<T>,
+}
+
+// the vec owns its values, the chunk and the allocator are already thread safe
+unsafe impl<T: Send> Send for ScratchVec<T> {}
+unsafe impl<T: Sync> Sync for ScratchVec<T> {}
+
+impl<T> ScratchVec<T> {
+    /// Adds a value to the end
+    pub fn push(&mut self, value: T) {
+        if self.len == self.capacity {
+            self.grow();
+        }
+        unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Removes the last value and returns it
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { self.ptr.as_ptr().add(self.len).read() })
+    }
+
+    /// Drops every value, the capacity is kept
+    pub fn clear(&mut self) {
+        let len = self.len;
+        // set first so a panicking drop can't make the values be dropped twice
+        self.len = 0;
+        unsafe {
+            std::ptr::drop_in_place(std::ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), len))
+        };
+    }
+
+    /// Gets how many values fit before the vec has to grow
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn grow(&mut self) {
+        let capacity = (self.capacity * 2).max(4);
+        let layout = Layout::array::<T>(capacity).expect("ScratchVec is too big");
+        let (ptr, chunk) = self.bump.lock().unwrap().alloc(layout);
+        let ptr = ptr.cast::<T>();
+        unsafe { std::ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len) };
+        self.ptr = ptr;
+        self.capacity = capacity;
+        self.chunk = Some(chunk);
+    }
+}
+
+impl<T> Deref for ScratchVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for ScratchVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Extend<T> for ScratchVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T> Drop for ScratchVec<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    // collects the positions into a scratch vec every frame
+    struct CollectSystem {}
+
+    impl System for CollectSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            let mut xs = engine
+                .get_resource::<FrameScratch>()
+                .unwrap()
+                .alloc_vec::<f32>();
+            for entity in engine.get_entities() {
+                let (position,) = engine.get_components_mut::<(Position,)>(entity);
+                position.x += 1.0;
+                xs.push(position.x);
+            }
+            assert_eq!(xs.len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_frame_scratch() {
+        let mut world = World::new();
+        for x in 0..3 {
+            world
+                .entities_and_components
+                .add_entity_with((Position { x: x as f32 },));
+        }
+        world
+            .entities_and_components
+            .add_resource(FrameScratch::new());
+        world.add_system(CollectSystem {});
+
+        world.run();
+        let scratch = world
+            .entities_and_components
+            .get_resource::<FrameScratch>()
+            .unwrap();
+        // room for 4 f32s was bumped out of one chunk
+        assert_eq!(scratch.bytes_allocated_this_frame(), 16);
+        assert_eq!(scratch.chunk_count(), 1);
+
+        world.run();
+        let scratch = world
+            .entities_and_components
+            .get_resource::<FrameScratch>()
+            .unwrap();
+        // the scratch was reset at the start of the frame and its chunk was reused
+        assert_eq!(scratch.bytes_allocated_this_frame(), 16);
+        assert_eq!(scratch.chunk_count(), 1);
+    }
+
+    #[test]
+    fn test_scratch_vec_kept_past_reset() {
+        let mut scratch = FrameScratch::new();
+        let mut kept = scratch.alloc_vec::<String>();
+        kept.extend(["a", "b", "c", "d", "e"].map(String::from));
+        assert_eq!(kept.pop(), Some("e".to_string()));
+
+        // the chunk kept is holding on to is not reused after the reset
+        scratch.reset();
+        let mut other = scratch.alloc_vec::<String>();
+        other.extend(["f", "g"].map(String::from));
+        assert_eq!(&*kept, &["a", "b", "c", "d"]);
+        assert_eq!(&*other, &["f", "g"]);
+        assert_eq!(scratch.chunk_count(), 1);
+
+        drop(kept);
+        drop(other);
+        scratch.reset();
+        assert_eq!(scratch.chunk_count(), 1);
+        assert_eq!(scratch.bytes_allocated_this_frame(), 0);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::*;
+
+// the histories of one component type, with T erased so every type can be kept in one map
+pub(crate) trait ErasedHistory {
+    // called before T is written to, saves the value it has now
+    fn record(&mut self, storages: &Storages, components: &Map<dyn Any>, entity: Entity);
+
+    // gives an entity that just got the component an empty history
+    fn start(&mut self, entity_id: DefaultKey);
+
+    // forgets the history of an entity that lost the component or was removed
+    fn forget(&mut self, entity_id: DefaultKey);
+
+    fn set_depth(&mut self, depth: usize);
+
+    // moves every history to the entity it was moved to by compact
+    fn remap(&mut self, entity_ids: &EntityIds);
+
+    // exchanges the histories of two entities, see swap_all_components
+    fn swap(&mut self, a: DefaultKey, b: DefaultKey);
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+// the previous values of T on every entity that has T
+struct History<T> {
+    depth: usize,
+    entities: SecondaryMap<DefaultKey, EntityHistory<T>>,
+}
+
+struct EntityHistory<T> {
+    // oldest first
+    values: Vec<T>,
+}
+
+impl<T: Component + Clone> EntityHistory<T> {
+    fn push(&mut self, value: T, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        if self.values.len() == depth {
+            self.values.remove(0);
+        }
+        self.values.push(value);
+    }
+}
+
+impl<T: Component + Clone> ErasedHistory for History<T> {
+    fn record(&mut self, storages: &Storages, components: &Map<dyn Any>, entity: Entity) {
+        // the entry is never added here, this can run in parallel for different entities
+        let Some(history) = self.entities.get_mut(entity.entity_id) else {
+            return;
+        };
+        let Some(current) = get_stored::<T>(storages, components, entity) else {
+            return;
+        };
+        history.push(current.clone(), self.depth);
+    }
+
+    fn start(&mut self, entity_id: DefaultKey) {
+        self.entities.insert(
+            entity_id,
+            EntityHistory {
+                values: Vec::with_capacity(self.depth),
+            },
+        );
+    }
+
+    fn forget(&mut self, entity_id: DefaultKey) {
+        self.entities.remove(entity_id);
+    }
+
+    fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        for history in self.entities.values_mut() {
+            let excess = history.values.len().saturating_sub(depth);
+            history.values.drain(..excess);
+        }
+    }
+
+    fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.entities, entity_ids);
+    }
+
+    fn swap(&mut self, a: DefaultKey, b: DefaultKey) {
+        let history_a = self.entities.remove(a);
+        let history_b = self.entities.remove(b);
+        if let Some(history_b) = history_b {
+            self.entities.insert(a, history_b);
+        }
+        if let Some(history_a) = history_a {
+            self.entities.insert(b, history_a);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Starts keeping the last depth values of T on every entity, for debugging what changed a value
+    /// The value is kept every time it is replaced with add_component_to or accessed mutably,
+    /// even if the access leaves it unchanged
+    /// Registering T again changes the depth
+    /// Keeping a history clones T on every mutable access, so this is meant for debugging, not for release builds
+    pub fn register_history<T: Component + Clone>(&mut self, depth: usize) {
+        let type_id = TypeId::of::<Box<T>>();
+        if let Some(history) = self.histories.get_mut(&type_id) {
+            history.set_depth(depth);
+            return;
+        }
+
+        let mut history = History::<T> {
+            depth,
+            entities: SecondaryMap::new(),
+        };
+        for entity in self.get_entities_with_component_include_disabled::<T>() {
+            history.start(entity.entity_id);
+        }
+        self.histories.insert(type_id, Box::new(history));
+    }
+
+    /// Gets the previous values of T on an entity, oldest first, see register_history
+    /// Empty if T has no history or the value hasn't been written to yet
+    /// panics if the entity does not exist
+    pub fn component_history<T: Component + Clone>(&self, entity: Entity) -> &[T] {
+        if !self.components.contains_key(entity.entity_id) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        self.histories
+            .get(&TypeId::of::<Box<T>>())
+            .and_then(|history| history.as_any().downcast_ref::<History<T>>())
+            .and_then(|history| history.entities.get(entity.entity_id))
+            .map_or(&[], |entity_history| entity_history.values.as_slice())
+    }
+
+    // called before T is written to, saves the value it has now
+    pub(crate) fn record_history(&mut self, entity: Entity, type_id: TypeId) {
+        if let (Some(history), Some(components)) = (
+            self.histories.get_mut(&type_id),
+            self.components.get(entity.entity_id),
+        ) {
+            history.record(&self.storages, components, entity);
+        }
+    }
+
+    // called after T was added to an entity that didn't have it
+    pub(crate) fn start_history(&mut self, entity: Entity, type_id: TypeId) {
+        if let Some(history) = self.histories.get_mut(&type_id) {
+            history.start(entity.entity_id);
+        }
+    }
+
+    // called after T was removed from an entity, or with every type when the entity was removed
+    pub(crate) fn forget_history(&mut self, entity_id: DefaultKey, type_id: Option<TypeId>) {
+        match type_id {
+            Some(type_id) => {
+                if let Some(history) = self.histories.get_mut(&type_id) {
+                    history.forget(entity_id);
+                }
+            }
+            None => {
+                for history in self.histories.values_mut() {
+                    history.forget(entity_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    #[test]
+    fn test_component_history() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_history::<Position>(3);
+        let entity = entities_and_components.add_entity_with((Position { x: 0.0 },));
+        assert!(entities_and_components
+            .component_history::<Position>(entity)
+            .is_empty());
+
+        for x in 1..5 {
+            let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+            position.x = x as f32;
+        }
+        entities_and_components.add_component_to(entity, Position { x: 10.0 });
+
+        // only the last 3 values before the current one are kept
+        assert_eq!(
+            entities_and_components.component_history::<Position>(entity),
+            &[
+                Position { x: 2.0 },
+                Position { x: 3.0 },
+                Position { x: 4.0 }
+            ]
+        );
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 10.0);
+
+        // accessing the value mutably is recorded even if it isn't changed
+        let other = entities_and_components.add_entity_with((Position { x: 0.0 },));
+        entities_and_components.try_get_component_mut::<Position>(other);
+        assert_eq!(
+            entities_and_components.component_history::<Position>(other),
+            &[Position { x: 0.0 }]
+        );
+
+        entities_and_components.register_history::<Position>(1);
+        entities_and_components
+            .try_get_component_mut::<Position>(other)
+            .unwrap()
+            .x = 1.0;
+        assert_eq!(
+            entities_and_components.component_history::<Position>(other),
+            &[Position { x: 0.0 }]
+        );
+        entities_and_components
+            .try_get_component_mut::<Position>(other)
+            .unwrap()
+            .x = 2.0;
+        assert_eq!(
+            entities_and_components.component_history::<Position>(other),
+            &[Position { x: 1.0 }]
+        );
+        assert_eq!(
+            entities_and_components.component_history::<Position>(entity),
+            &[Position { x: 4.0 }]
+        );
+
+        // writes through the component buffer are recorded too
+        entities_and_components.with_component_buffer_mut::<Position>(|buffer, _| {
+            for position in buffer {
+                position.x += 1.0;
+            }
+        });
+        assert_eq!(
+            entities_and_components.component_history::<Position>(entity),
+            &[Position { x: 10.0 }]
+        );
+
+        // the history is forgotten with the component
+        entities_and_components.remove_component_from::<Position>(other);
+        entities_and_components.add_component_to(other, Position { x: 5.0 });
+        assert!(entities_and_components
+            .component_history::<Position>(other)
+            .is_empty());
+    }
+}
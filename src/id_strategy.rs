@@ -0,0 +1,107 @@
+use crate::*;
+
+/// How entities are numbered, set with World::with_id_strategy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdStrategy {
+    /// Entities only have their Entity handle, and the slot of a removed entity is reused by a later one
+    #[default]
+    Recycling,
+    /// Every entity also gets an external id that is higher than every id before it and is never reused,
+    /// even after the entity is removed, which is useful for matching entities up in logs
+    Monotonic,
+}
+
+// the external ids of IdStrategy::Monotonic
+pub(crate) struct ExternalIds {
+    next: u64,
+    // where ids[entity_id]
+    ids: SecondaryMap<DefaultKey, u64>,
+}
+
+impl ExternalIds {
+    pub(crate) fn new() -> Self {
+        ExternalIds {
+            next: 0,
+            ids: SecondaryMap::new(),
+        }
+    }
+
+    pub(crate) fn entity_added(&mut self, entity_id: DefaultKey) {
+        self.ids.insert(entity_id, self.next);
+        self.next += 1;
+    }
+
+    pub(crate) fn entity_removed(&mut self, entity_id: DefaultKey) {
+        self.ids.remove(entity_id);
+    }
+
+    pub(crate) fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.ids, entity_ids);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gets how entities are numbered in this world
+    pub fn id_strategy(&self) -> IdStrategy {
+        match self.external_ids {
+            Some(_) => IdStrategy::Monotonic,
+            None => IdStrategy::Recycling,
+        }
+    }
+
+    /// Gets the external id of an entity, which is never reused, see IdStrategy::Monotonic
+    /// returns None if the world uses IdStrategy::Recycling or the entity does not exist
+    pub fn get_external_id(&self, entity: Entity) -> Option<u64> {
+        self.external_ids
+            .as_ref()?
+            .ids
+            .get(entity.entity_id)
+            .copied()
+    }
+}
+
+impl World {
+    /// Creates a new world that numbers its entities with the given strategy
+    pub fn with_id_strategy(id_strategy: IdStrategy) -> Self {
+        let mut world = World::new();
+        world.entities_and_components.external_ids = match id_strategy {
+            IdStrategy::Recycling => None,
+            IdStrategy::Monotonic => Some(ExternalIds::new()),
+        };
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monotonic_ids() {
+        let mut world = World::with_id_strategy(IdStrategy::Monotonic);
+        let entities_and_components = &mut world.entities_and_components;
+        assert_eq!(entities_and_components.id_strategy(), IdStrategy::Monotonic);
+
+        let first = entities_and_components.add_entity();
+        let second = entities_and_components.add_entity();
+        let second_id = entities_and_components.get_external_id(second).unwrap();
+        assert!(second_id > entities_and_components.get_external_id(first).unwrap());
+
+        entities_and_components.remove_entity(second);
+        assert_eq!(entities_and_components.get_external_id(second), None);
+        // the new entity reuses the slot of the removed one, but not its external id
+        let third = entities_and_components.add_entity();
+        assert!(entities_and_components.get_external_id(third).unwrap() > second_id);
+
+        let mut recycling = World::new();
+        let entity = recycling.entities_and_components.add_entity();
+        assert_eq!(
+            recycling.entities_and_components.id_strategy(),
+            IdStrategy::Recycling
+        );
+        assert_eq!(
+            recycling.entities_and_components.get_external_id(entity),
+            None
+        );
+    }
+}
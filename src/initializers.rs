@@ -0,0 +1,152 @@
+use crate::*;
+
+// calls the user's function for every entity that got one component type since it last ran
+type InitFn = Box<dyn FnMut(&mut EntitiesAndComponents, Vec<Entity>) + Send + Sync>;
+
+// sets up the entities that got one component type since it last ran,
+// its entities are kept in the PendingInit with the same index
+pub(crate) struct Initializer {
+    init: InitFn,
+}
+
+// the entities that got a component type since an initializer last ran, filled in by add_component_to,
+// so unlike added it doesn't miss anything when a lot of components are added in one frame
+pub(crate) struct PendingInit {
+    // the TypeId the component is stored under
+    type_id: TypeId,
+    entities: Vec<Entity>,
+}
+
+impl EntitiesAndComponents {
+    pub(crate) fn queue_initializers(&mut self, entity: Entity, type_id: TypeId) {
+        if self.change_tracker.is_suppressed() {
+            return;
+        }
+        for pending in self.pending_inits.iter_mut() {
+            if pending.type_id == type_id {
+                pending.entities.push(entity);
+            }
+        }
+    }
+
+    // the entities that were removed by compact are dropped
+    pub(crate) fn remap_pending_inits(&mut self, entity_ids: &EntityIds) {
+        for pending in self.pending_inits.iter_mut() {
+            pending.entities = pending
+                .entities
+                .iter()
+                .filter_map(|entity| entity_ids.get(&entity.entity_id).copied())
+                .collect();
+        }
+    }
+}
+
+impl World {
+    /// Adds an initializer that is called once for every entity that gets the component T,
+    /// for example to attach a physics body when a Collider is added
+    /// Initializers run at the start of run, after the resources update and before the systems,
+    /// so an entity that got T during a frame is set up at the start of the next one
+    /// Only entities that get T after the initializer is added are initialized,
+    /// and adds made while change tracking is suppressed are not seen, see suppress_change_tracking
+    /// Entities moved in with transfer_with count as getting T
+    /// Initializers run in the order they were added, and don't run while the world is paused
+    pub fn add_initializer<T: Component>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut EntitiesAndComponents) + Send + Sync + 'static,
+    ) {
+        self.entities_and_components
+            .pending_inits
+            .push(PendingInit {
+                type_id: TypeId::of::<Box<T>>(),
+                entities: Vec::new(),
+            });
+        self.initializers.push(Initializer {
+            init: Box::new(move |entities_and_components, entities| {
+                let mut seen = FxHashSet::default();
+                for entity in entities {
+                    // an earlier initializer may have removed it, and it may have got T more than once
+                    if entities_and_components.does_entity_exist(entity) && seen.insert(entity) {
+                        f(entity, entities_and_components);
+                    }
+                }
+            }),
+        });
+    }
+
+    pub(crate) fn run_initializers(&mut self) {
+        for (index, initializer) in self.initializers.iter_mut().enumerate() {
+            // anything the initializer adds itself is picked up next time
+            let Some(pending) = self.entities_and_components.pending_inits.get_mut(index) else {
+                continue;
+            };
+            let entities = std::mem::take(&mut pending.entities);
+            (initializer.init)(&mut self.entities_and_components, entities);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Collider;
+
+    struct Body {
+        initialized: u32,
+    }
+
+    #[test]
+    fn test_initializer_runs_once() {
+        let mut world = World::new();
+        world.add_initializer::<Collider>(|entity, entities_and_components| {
+            match entities_and_components.try_get_component_mut::<Body>(entity) {
+                Some(body) => body.initialized += 1,
+                None => entities_and_components.add_component_to(entity, Body { initialized: 1 }),
+            }
+        });
+
+        let entity = world.entities_and_components.add_entity_with((Collider,));
+        let other = world.entities_and_components.add_entity();
+        world.run();
+        world.run();
+
+        let (body,) = world
+            .entities_and_components
+            .get_components::<(Body,)>(entity);
+        assert_eq!(body.initialized, 1);
+        assert!(world
+            .entities_and_components
+            .try_get_component::<Body>(other)
+            .is_none());
+
+        // a component added during a frame is initialized at the start of the next one
+        world
+            .entities_and_components
+            .add_component_to(other, Collider);
+        world.run();
+        let (body,) = world
+            .entities_and_components
+            .get_components::<(Body,)>(other);
+        assert_eq!(body.initialized, 1);
+    }
+
+    #[test]
+    fn test_initializer_sees_every_add() {
+        let mut world = World::new();
+        world.add_initializer::<Collider>(|entity, entities_and_components| {
+            entities_and_components.add_component_to(entity, Body { initialized: 1 });
+        });
+
+        // more adds in one frame than added remembers
+        let entities = (0..MAX_STRUCTURAL_EVENTS + 10)
+            .map(|_| world.entities_and_components.add_entity_with((Collider,)))
+            .collect::<Vec<Entity>>();
+        world.run();
+        for entity in entities {
+            assert!(world
+                .entities_and_components
+                .try_get_component::<Body>(entity)
+                .is_some());
+        }
+    }
+}
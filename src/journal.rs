@@ -0,0 +1,421 @@
+use crate::*;
+use std::collections::VecDeque;
+
+// removes the component an entry was recorded for, by its type
+type ComponentRemover = fn(&mut EntitiesAndComponents, Entity);
+
+// an entity that was despawned, with what's needed to bring it back
+pub(crate) struct DespawnedEntity {
+    entity: Entity,
+    // only set if the parent was despawned along with the entity
+    parent: Option<Entity>,
+    stable_id: Option<u64>,
+    components: Vec<(Box<dyn Any>, BoxedInserter)>,
+}
+
+enum JournalEntry {
+    Spawn(Entity),
+    // an entity and its children, in the order they were despawned, parents before their children
+    Despawn(Vec<DespawnedEntity>),
+    ComponentAdded {
+        entity: Entity,
+        remove: ComponentRemover,
+        // the component that was overwritten, if there was one
+        previous: Option<(Box<dyn Any>, BoxedInserter)>,
+    },
+    ComponentRemoved {
+        entity: Entity,
+        component: Box<dyn Any>,
+        add: BoxedInserter,
+    },
+    // swapping the components again undoes it
+    Swap(Entity, Entity),
+}
+
+/// Records structural changes so they can be undone, see EntitiesAndComponents::undo_last
+/// Nothing is recorded until a depth is set
+pub(crate) struct Journal {
+    entries: VecDeque<JournalEntry>,
+    depth: usize,
+    // false while an entry is being undone, so undoing it isn't recorded as a new change
+    recording: bool,
+}
+
+impl Journal {
+    pub(crate) fn new() -> Self {
+        Journal {
+            entries: VecDeque::new(),
+            depth: 0,
+            recording: true,
+        }
+    }
+
+    // forgets every entry, for when the entities they refer to have been moved
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn is_recording(&self) -> bool {
+        self.recording && self.depth > 0
+    }
+
+    fn record(&mut self, entry: JournalEntry) {
+        if !self.is_recording() {
+            return;
+        }
+        if self.entries.len() == self.depth {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Sets how many structural changes are remembered for undo_last, the oldest ones are forgotten first
+    /// Spawns, despawns, swap_all_components and adding and removing components are recorded, including the ones made by set_parent and similar methods
+    /// Removed components are kept in the journal instead of being dropped, so they can be put back
+    /// A depth of 0, the default, turns recording off and forgets everything that was recorded
+    pub fn set_journal_depth(&mut self, depth: usize) {
+        self.journal.depth = depth;
+        while self.journal.entries.len() > depth {
+            self.journal.entries.pop_front();
+        }
+    }
+
+    /// Reverses the most recent recorded structural change, returns false if there was nothing to undo
+    /// A despawned entity comes back as a new entity together with the children that were despawned with it,
+    /// and the older changes that refer to it are updated to refer to the new entity
+    /// Its components are added back like with add_component_to, and it gets its stable id back if no other entity took it,
+    /// but its relationships and the parent it was removed from are not restored
+    /// Changes to entities that no longer exist, for example because they were moved with transfer_with, are skipped
+    pub fn undo_last(&mut self) -> bool {
+        if self.frozen_mutation() {
+            return false;
+        }
+        let Some(entry) = self.journal.entries.pop_back() else {
+            return false;
+        };
+
+        self.journal.recording = false;
+        match entry {
+            JournalEntry::Spawn(entity) => {
+                if self.does_entity_exist(entity) {
+                    self.remove_entity(entity);
+                }
+            }
+            JournalEntry::Despawn(despawned) => {
+                let mut new_entities = FxHashMap::default();
+                for despawned_entity in despawned {
+                    let new_entity = self.add_entity();
+                    for (component, add) in despawned_entity.components {
+                        add(self, new_entity, component);
+                    }
+                    if let Some(stable_id) = despawned_entity.stable_id {
+                        if self.get_entity_by_stable_id(stable_id).is_none() {
+                            self.set_stable_id(new_entity, stable_id);
+                        }
+                    }
+                    if let Some(parent) = despawned_entity
+                        .parent
+                        .and_then(|parent| new_entities.get(&parent))
+                    {
+                        self.set_parent(new_entity, *parent);
+                    }
+                    new_entities.insert(despawned_entity.entity, new_entity);
+                    self.journal_entity_replaced(despawned_entity.entity, new_entity);
+                }
+            }
+            JournalEntry::ComponentAdded {
+                entity,
+                remove,
+                previous,
+            } => {
+                if self.does_entity_exist(entity) {
+                    remove(self, entity);
+                    if let Some((component, add)) = previous {
+                        add(self, entity, component);
+                    }
+                }
+            }
+            JournalEntry::ComponentRemoved {
+                entity,
+                component,
+                add,
+            } => {
+                if self.does_entity_exist(entity) {
+                    add(self, entity, component);
+                }
+            }
+            JournalEntry::Swap(a, b) => {
+                if self.does_entity_exist(a) && self.does_entity_exist(b) {
+                    self.swap_all_components(a, b);
+                }
+            }
+        }
+        self.journal.recording = true;
+        true
+    }
+
+    pub(crate) fn journal_spawn(&mut self, entity: Entity) {
+        self.journal.record(JournalEntry::Spawn(entity));
+    }
+
+    // keeps the components of an entity that is being despawned, parent is set if the parent is despawned too
+    pub(crate) fn journal_despawned_entity(
+        &self,
+        despawned: &mut Vec<DespawnedEntity>,
+        entity: Entity,
+        parent: Option<Entity>,
+        stable_id: Option<u64>,
+        components: Map<dyn Any>,
+    ) {
+        if !self.journal.is_recording() {
+            return;
+        }
+        let components = components
+            .into_raw()
+            .into_iter()
+            // the hierarchy is rebuilt from the parents, and relationships aren't restored
+            .filter(|(type_id, _)| !self.linked_components.contains(type_id))
+            .filter_map(|(type_id, component)| {
+                Some((component, *self.component_restorers.get(&type_id)?))
+            })
+            .collect();
+        despawned.push(DespawnedEntity {
+            entity,
+            parent,
+            stable_id,
+            components,
+        });
+    }
+
+    pub(crate) fn journal_despawn(&mut self, despawned: Vec<DespawnedEntity>) {
+        if !despawned.is_empty() {
+            self.journal.record(JournalEntry::Despawn(despawned));
+        }
+    }
+
+    pub(crate) fn journal_swap(&mut self, a: Entity, b: Entity) {
+        self.journal.record(JournalEntry::Swap(a, b));
+    }
+
+    pub(crate) fn journal_component_added<T: Component>(
+        &mut self,
+        entity: Entity,
+        previous: Option<T>,
+    ) {
+        if !self.journal.is_recording() {
+            return;
+        }
+        self.journal.record(JournalEntry::ComponentAdded {
+            entity,
+            remove: remove_component_of::<T>,
+            previous: previous.map(|previous| {
+                (
+                    Box::new(previous) as Box<dyn Any>,
+                    add_boxed_component_of::<T> as BoxedInserter,
+                )
+            }),
+        });
+    }
+
+    pub(crate) fn journal_component_removed<T: Component>(&mut self, entity: Entity, component: T) {
+        if !self.journal.is_recording() {
+            return;
+        }
+        self.journal.record(JournalEntry::ComponentRemoved {
+            entity,
+            component: Box::new(component),
+            add: add_boxed_component_of::<T>,
+        });
+    }
+
+    // points the remaining entries about an entity that was brought back at its new id
+    fn journal_entity_replaced(&mut self, old_entity: Entity, new_entity: Entity) {
+        let replace = |entity: &mut Entity| {
+            if *entity == old_entity {
+                *entity = new_entity;
+            }
+        };
+        for entry in self.journal.entries.iter_mut() {
+            match entry {
+                JournalEntry::Spawn(entity) => replace(entity),
+                JournalEntry::Despawn(despawned) => {
+                    for despawned_entity in despawned {
+                        replace(&mut despawned_entity.entity);
+                        if let Some(parent) = &mut despawned_entity.parent {
+                            replace(parent);
+                        }
+                    }
+                }
+                JournalEntry::ComponentAdded { entity, .. } => replace(entity),
+                JournalEntry::ComponentRemoved { entity, .. } => replace(entity),
+                JournalEntry::Swap(a, b) => {
+                    replace(a);
+                    replace(b);
+                }
+            }
+        }
+    }
+}
+
+// adds back a component taken out of a despawned entity's component map, where it is stored as a Box<T>
+pub(crate) fn restore_component_of<T: Component>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    component: Box<dyn Any>,
+) {
+    let Ok(component) = component.downcast::<Box<T>>() else {
+        panic!(
+            "Stored component is not a {}, the type id does not match the component",
+            std::any::type_name::<T>()
+        );
+    };
+    entities_and_components.add_component_to(entity, **component);
+}
+
+fn remove_component_of<T: Component>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+) {
+    entities_and_components.remove_component_from::<T>(entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn test_undo_add_component() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.set_journal_depth(10);
+
+        let entity = entities_and_components.add_entity_with((Name("crate"),));
+        entities_and_components.add_component_to(entity, Position { x: 1.0 });
+
+        assert!(entities_and_components.undo_last());
+        assert!(entities_and_components.does_entity_exist(entity));
+        assert!(entities_and_components
+            .try_get_component::<Position>(entity)
+            .is_none());
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            0
+        );
+        // the rest of the entity is untouched
+        let (name,) = entities_and_components.get_components::<(Name,)>(entity);
+        assert_eq!(*name, Name("crate"));
+    }
+
+    #[test]
+    fn test_undo_overwrite_remove_and_despawn() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.set_journal_depth(10);
+
+        let entity = entities_and_components.add_entity_with((Position { x: 1.0 },));
+        entities_and_components.add_component_to(entity, Position { x: 2.0 });
+        entities_and_components.remove_component_from::<Position>(entity);
+        entities_and_components.add_component_to(entity, Name("crate"));
+        entities_and_components.remove_entity(entity);
+
+        // the despawned entity comes back as a new entity with its components
+        assert!(entities_and_components.undo_last());
+        assert!(!entities_and_components.does_entity_exist(entity));
+        let entity = entities_and_components
+            .get_entity_with_component::<Name>(0)
+            .unwrap();
+
+        // the older changes now refer to the new entity
+        assert!(entities_and_components.undo_last());
+        assert!(entities_and_components
+            .try_get_component::<Name>(entity)
+            .is_none());
+        assert!(entities_and_components.undo_last());
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 2.0 });
+        assert!(entities_and_components.undo_last());
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 1.0 });
+
+        // undoing doesn't record anything new
+        assert!(entities_and_components.undo_last());
+        assert!(entities_and_components.undo_last());
+        assert_eq!(entities_and_components.get_entity_count(), 0);
+        assert!(!entities_and_components.undo_last());
+    }
+
+    struct Follows;
+
+    #[test]
+    fn test_undo_despawn_hierarchy() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let target = entities_and_components.add_entity();
+        let parent = entities_and_components.add_entity_with((Name("parent"),));
+        let child = entities_and_components.add_entity_with((Name("child"), Position { x: 1.0 }));
+        entities_and_components.set_parent(child, parent);
+        entities_and_components.set_stable_id(parent, 7);
+        entities_and_components.relate::<Follows>(parent, target);
+        entities_and_components.set_journal_depth(10);
+
+        entities_and_components.remove_entity(parent);
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+
+        // the parent and its child come back in one step
+        assert!(entities_and_components.undo_last());
+        assert!(!entities_and_components.undo_last());
+        assert_eq!(entities_and_components.get_entity_count(), 3);
+        let parent = entities_and_components.get_entity_by_stable_id(7).unwrap();
+        let (name,) = entities_and_components.get_components::<(Name,)>(parent);
+        assert_eq!(*name, Name("parent"));
+        let children = entities_and_components.get_children(parent);
+        assert_eq!(children.len(), 1);
+        let (name, position) =
+            entities_and_components.get_components::<(Name, Position)>(children[0]);
+        assert_eq!(*name, Name("child"));
+        assert_eq!(*position, Position { x: 1.0 });
+        // the components went through the normal add path
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Name>(),
+            2
+        );
+        let mask = entities_and_components.component_mask::<(Name, Position)>();
+        assert!(entities_and_components.matches_mask(children[0], mask));
+        // relationships are not restored
+        assert!(entities_and_components
+            .relations_from::<Follows>(parent)
+            .is_empty());
+        assert!(entities_and_components
+            .relations_to::<Follows>(target)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_journal_depth() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        // nothing is recorded by default
+        entities_and_components.add_entity();
+        assert!(!entities_and_components.undo_last());
+
+        entities_and_components.set_journal_depth(2);
+        for _ in 0..5 {
+            entities_and_components.add_entity();
+        }
+        assert!(entities_and_components.undo_last());
+        assert!(entities_and_components.undo_last());
+        assert!(!entities_and_components.undo_last());
+        assert_eq!(entities_and_components.get_entity_count(), 4);
+    }
+}
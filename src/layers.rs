@@ -0,0 +1,73 @@
+use crate::*;
+
+/// The layers an entity is on, one bit per layer, for filtering like collision or render layers
+/// An entity can be on several layers at once, for example Layer(0b101) is on layers 0 and 2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Layer(pub u32);
+
+impl Layer {
+    /// Checks if the entity is on any of the layers in the mask
+    pub fn intersects(&self, mask: u32) -> bool {
+        self.0 & mask != 0
+    }
+}
+
+impl EntitiesAndComponents {
+    /// returns an iterator over the entities with a Layer on any of the layers in the mask, except disabled ones
+    /// This checks every entity with a Layer
+    pub fn entities_in_layers(&self, mask: u32) -> impl Iterator<Item = Entity> + '_ {
+        self.get_entities_with_component::<Layer>()
+            .filter(move |entity| {
+                get_stored::<Layer>(&self.storages, &self.components[entity.entity_id], **entity)
+                    .is_some_and(|layer| layer.intersects(mask))
+            })
+            .copied()
+    }
+}
+
+impl<'a> EntitiesAndComponentsThreadSafe<'a> {
+    /// returns an iterator over the entities with a Layer on any of the layers in the mask, except disabled ones
+    pub fn entities_in_layers(&self, mask: u32) -> impl Iterator<Item = Entity> + '_ {
+        self.entities_and_components.entities_in_layers(mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: u32 = 1 << 0;
+    const ENEMY: u32 = 1 << 1;
+    const PICKUP: u32 = 1 << 2;
+
+    #[test]
+    fn test_entities_in_layers() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let player = entities_and_components.add_entity_with((Layer(PLAYER),));
+        let enemy = entities_and_components.add_entity_with((Layer(ENEMY),));
+        let both = entities_and_components.add_entity_with((Layer(PLAYER | ENEMY),));
+        let pickup = entities_and_components.add_entity_with((Layer(PICKUP),));
+        let disabled = entities_and_components.add_entity_with((Layer(ENEMY),));
+        entities_and_components.disable_entity(disabled);
+        entities_and_components.add_entity();
+
+        let mut hit_by_player = entities_and_components
+            .entities_in_layers(ENEMY | PICKUP)
+            .collect::<Vec<Entity>>();
+        hit_by_player.sort();
+        let mut expected = vec![enemy, both, pickup];
+        expected.sort();
+        assert_eq!(hit_by_player, expected);
+
+        let mut players = entities_and_components
+            .entities_in_layers(PLAYER)
+            .collect::<Vec<Entity>>();
+        players.sort();
+        let mut expected = vec![player, both];
+        expected.sort();
+        assert_eq!(players, expected);
+
+        assert_eq!(entities_and_components.entities_in_layers(0).count(), 0);
+    }
+}
@@ -4,13 +4,147 @@
 
 #[doc = include_str!("../README.md")]
 use anymap::Map;
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
-use rustc_hash::FxHashMap;
+#[cfg(not(feature = "single-threaded"))]
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelIterator,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
 use slotmap::{DefaultKey, SecondaryMap, SlotMap};
 use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// lets the derive macros refer to the crate by its name from inside it as well
+extern crate self as ABC_ECS;
+
+/// emits a structured debug event for a structural change when the tracing feature is enabled,
+/// and compiles to nothing when it is not
+macro_rules! trace_structural_change {
+    ($($event:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($event)*);
+    };
+}
+
+mod access_stats;
+mod archetypes;
+mod attributes;
+mod borrow_scope;
+mod boxed_component;
+mod change_detection;
+mod clone_subset;
+mod commands;
+mod compact;
+mod component_handle;
+mod component_mask;
+mod component_status;
+mod component_storage;
+mod component_visitor;
+mod cow_component;
+mod debug_dump;
+mod debug_path;
+mod despawn_policy;
+mod diagnostics;
+mod disabled;
+mod double_buffered;
+mod entity_cursor;
+mod entity_read_view;
+mod entity_ref;
+mod error;
+mod error_handler;
+mod factories;
+mod frame_scratch;
+mod history;
+mod id_strategy;
+mod initializers;
+mod journal;
+mod layers;
+mod locked_resource;
 mod macros;
+mod mailbox;
+mod merge_resource;
+mod neighbors;
+mod net_id;
+mod query_cache;
+mod query_filter;
+mod ref_count;
+mod relations;
+mod replication;
+mod resource_lifecycle;
+mod schedule_dot;
+mod serialize;
+mod stable_id;
+mod swap_components;
+mod system_access;
+mod systems;
+mod tag_query;
+mod temporary_tags;
+#[cfg(feature = "test-util")]
+mod test_util;
+mod transfer;
+#[cfg(feature = "access-stats")]
+use access_stats::AccessStats;
+pub use archetypes::*;
+use archetypes::{ArchetypeEmptyHook, ArchetypeTable, ArchetypeTransition};
+pub use attributes::*;
+pub use borrow_scope::*;
+use boxed_component::{add_boxed_component_of, BoxedInserter};
+use change_detection::ChangeTracker;
+pub use change_detection::*;
+use clone_subset::ComponentCloner;
+use commands::run_with_own_commands;
+pub use commands::*;
+use compact::{remap_entity, remap_keys, EntityIds, EntityRemap};
+pub use component_handle::*;
+use component_mask::ComponentMasks;
+pub use component_mask::*;
+pub use component_status::*;
+pub use component_storage::*;
+use component_storage::{get_stored, get_stored_mut, insert_stored, remove_stored, Storages};
+use component_visitor::ComponentAccessor;
+pub use component_visitor::*;
+pub use cow_component::*;
+use debug_path::ComponentFormatter;
+pub use despawn_policy::*;
+pub use disabled::*;
+pub use double_buffered::*;
+pub use entity_cursor::*;
+pub use entity_read_view::*;
+use entity_ref::EntityRefs;
+pub use entity_ref::*;
+pub use error::*;
+use error_handler::ErrorHandler;
+use factories::ComponentFactory;
+pub use frame_scratch::*;
+use history::ErasedHistory;
+use id_strategy::ExternalIds;
+pub use id_strategy::*;
+use initializers::{Initializer, PendingInit};
+use journal::{restore_component_of, DespawnedEntity, Journal};
+pub use layers::*;
+pub use locked_resource::*;
 pub use macros::*;
-use rayon::prelude::ParallelSliceMut;
+use mailbox::ErasedMailboxes;
+pub use merge_resource::*;
+use net_id::NetIds;
+use query_cache::CachedQuery;
+pub use query_filter::*;
+#[cfg(not(feature = "single-threaded"))]
+use rayon::prelude::{ParallelSlice, ParallelSliceMut};
+use relations::RelationCleanup;
+use replication::Replicator;
+pub use replication::*;
+pub use serialize::*;
+use stable_id::StableIds;
+use swap_components::swap_entries_back;
+pub use system_access::*;
+pub use systems::*;
+pub use tag_query::*;
+use temporary_tags::TemporaryTags;
+pub use temporary_tags::*;
+#[cfg(feature = "test-util")]
+pub use test_util::*;
 
 struct Children {
     children: Vec<Entity>,
@@ -22,7 +156,7 @@ struct Parent(Entity);
 // indexed into arrays of components for now...
 /// An entity is a unique identifier for an object in the game engine
 /// The entity itself does not hold any data, it is a key to access data from the EntitiesAndComponents struct
-#[derive(Clone, Copy, PartialEq, Debug, PartialOrd, Eq, Ord)]
+#[derive(Clone, Copy, PartialEq, Debug, PartialOrd, Eq, Ord, Hash)]
 pub struct Entity {
     pub(crate) entity_id: DefaultKey,
 }
@@ -31,9 +165,27 @@ pub struct Entity {
 /// They are a sort of blend between an entity and a system,
 /// they have their own update method that is called every frame like a system
 /// But unlike a system, they can be accessed by systems
+/// Every frame World::run updates resources in three phases, each in order of priority:
+/// early_update and update before anything else, then update_with_world, and late_update after all the systems have run
 pub trait Resource: 'static + Sized {
-    /// This method is called every frame
+    /// This method is called every frame, right after early_update
     fn update(&mut self) {}
+    /// This method is called every frame before any resource is updated with the world
+    fn early_update(&mut self) {}
+    /// This method is called every frame after every resource has had its early update and before the systems run
+    /// It has exclusive access to the world, but the resource itself can't be accessed through the world while it runs
+    fn update_with_world(&mut self, _world: &mut EntitiesAndComponents) {}
+    /// This method is called every frame after all the systems have run
+    fn late_update(&mut self) {}
+    /// Resources with a lower priority are updated first in every phase,
+    /// resources with the same priority are updated in no particular order
+    fn priority(&self) -> i32 {
+        0
+    }
+    /// Should return true if the resource keeps being updated while the world is paused, see World::set_paused
+    fn runs_while_paused(&self) -> bool {
+        false
+    }
     /// This method is needed to allow the resource to be downcast
     fn as_any(&self) -> &dyn Any {
         self
@@ -44,22 +196,57 @@ pub trait Resource: 'static + Sized {
     }
 }
 
-trait ResourceWrapper {
+/// A type erased Resource, Resource itself can't be used as a trait object
+/// It is implemented for every Resource and is what EntitiesAndComponents::for_each_resource_mut gives out
+pub trait ResourceWrapper {
+    /// Calls Resource::update
     fn update(&mut self);
+    /// Calls Resource::early_update
+    fn early_update(&mut self);
+    /// Calls Resource::update_with_world
+    fn update_with_world(&mut self, world: &mut EntitiesAndComponents);
+    /// Calls Resource::late_update
+    fn late_update(&mut self);
+    /// Calls Resource::priority
+    fn priority(&self) -> i32;
+    /// Calls Resource::runs_while_paused
+    fn runs_while_paused(&self) -> bool;
+    /// Gets the resource as Any, so it can be downcast to its type
     fn as_any(&self) -> &dyn Any;
+    /// Gets the resource as Any mutably, so it can be downcast to its type
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Gets the type name of the resource
+    fn type_name(&self) -> &'static str;
 }
 
 impl<T: Resource> ResourceWrapper for T {
     fn update(&mut self) {
         self.update();
     }
+    fn early_update(&mut self) {
+        self.early_update();
+    }
+    fn update_with_world(&mut self, world: &mut EntitiesAndComponents) {
+        self.update_with_world(world);
+    }
+    fn late_update(&mut self) {
+        self.late_update();
+    }
+    fn priority(&self) -> i32 {
+        self.priority()
+    }
+    fn runs_while_paused(&self) -> bool {
+        self.runs_while_paused()
+    }
     fn as_any(&self) -> &dyn Any {
         self
     }
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
 }
 
 /// This struct holds all the entities and components in the game engine
@@ -68,94 +255,680 @@ pub struct EntitiesAndComponents {
     entities: SlotMap<DefaultKey, Entity>,
     pub(crate) components: SlotMap<DefaultKey, Map<dyn Any + 'static>>, // where components[entity_id][component_id]
     entities_with_components: FxHashMap<TypeId, SecondaryMap<DefaultKey, Entity>>,
+    // how many disabled entities have each component type, by the TypeId it is stored under
+    disabled_counts: FxHashMap<TypeId, usize>,
+    // maps the TypeId a component is stored under (Box<T>) to the TypeId of the component itself (T)
+    component_type_ids: FxHashMap<TypeId, TypeId>,
+    // the type name of every component, by the TypeId it is stored under
+    component_type_names: FxHashMap<TypeId, &'static str>,
+    // gets a component as &dyn Any, by the TypeId it is stored under, see visit_components
+    component_accessors: FxHashMap<TypeId, ComponentAccessor>,
+    // adds a component taken out of an entity's components back to an entity, by the TypeId it is stored under, see undo_last
+    component_restorers: FxHashMap<TypeId, BoxedInserter>,
+    // the entities every initializer of the World still has to set up, see add_initializer
+    pending_inits: Vec<PendingInit>,
+    // the bit of every component type and which components every entity has, see component_mask
+    component_masks: ComponentMasks,
+    // adds a Box<dyn Any> holding a T as a T, by TypeId::of::<T>(), see add_boxed_component
+    boxed_inserters: FxHashMap<TypeId, BoxedInserter>,
+    // the component types stored with set_storage instead of in components
+    storages: Storages,
+    // copies a component into another world, by the TypeId it is stored under, see clone_subset
+    cloners: FxHashMap<TypeId, ComponentCloner>,
+    // the component types that keep their previous values, by the TypeId they are stored under, see register_history
+    histories: FxHashMap<TypeId, Box<dyn ErasedHistory>>,
+    // the components registered with register_component_name, by name
+    component_formatters: FxHashMap<String, ComponentFormatter>,
+    // the components registered with register_factory, by name
+    factories: FxHashMap<String, ComponentFactory>,
+    net_ids: NetIds,
+    // how to encode and decode the components registered with register_replicated, by net id
+    replicators: FxHashMap<u16, Replicator>,
     /// resources holds all the resources that are not components and do not have any relation to entities
     /// they are read only and can be accessed by any system
     /// Resources have their own trait, Resource, which has an update method that is called every frame
     pub(crate) resources: FxHashMap<TypeId, Box<dyn ResourceWrapper>>,
+    /// structural changes that have been deferred until the next flush
+    commands: Commands,
+    change_tracker: ChangeTracker,
+    // swaps the buffers of every DoubleBuffered<T> for each registered T
+    double_buffer_swaps: FxHashMap<TypeId, fn(&mut EntitiesAndComponents)>,
+    // the messages sent with send_to, by TypeId::of::<M>()
+    mailboxes: FxHashMap<TypeId, Box<dyn ErasedMailboxes>>,
+    // unlinks a removed entity from the entities it is related to, for each relationship type used with relate
+    relation_cleanups: FxHashMap<TypeId, RelationCleanup>,
+    // fixes the Entity handles inside internal components after compact, for each component type that has them
+    entity_remaps: FxHashMap<TypeId, EntityRemap>,
+    // the EntityRef fields pointing at every entity, see set_entity_ref
+    entity_refs: EntityRefs,
+    // the TypeIds of the components that link an entity to others by its handle, Box<T>,
+    // they stay with the handle in swap_all_components
+    linked_components: FxHashSet<TypeId>,
+    max_entities: Option<usize>,
+    // called instead of panicking for recoverable errors, see set_error_handler
+    error_handler: Option<ErrorHandler>,
+    // every archetype that has been seen and the archetype of every entity, see archetypes
+    archetype_table: ArchetypeTable,
+    // called with the component types of an archetype when its last entity is removed
+    archetype_empty_hooks: Vec<ArchetypeEmptyHook>,
+    // goes up with every structural change, see archetype_generation
+    archetype_generation: u64,
+    // the queries cached with cache_query, by name
+    cached_queries: FxHashMap<String, CachedQuery>,
+    // the countdowns of the tags added with add_temporary_tag, by TypeId::of::<T>()
+    temporary_tags: FxHashMap<TypeId, TemporaryTags>,
+    // the tick the system that is running last ran at, for query_filtered, 0 outside of World::run
+    system_last_run: u64,
+    // the archetype changes since the last drain, None until track_archetype_transitions is called
+    archetype_transitions: Option<Vec<ArchetypeTransition>>,
+    // the structural changes that can be undone with undo_last
+    journal: Journal,
+    // how many times World::run has been called
+    frame: u64,
+    // skips everything that doesn't run while paused in World::run, see set_paused
+    paused: bool,
+    stable_ids: StableIds,
+    // the ids of IdStrategy::Monotonic, None with IdStrategy::Recycling
+    external_ids: Option<ExternalIds>,
+    // how many times each retained entity has been retained, see retain_entity
+    ref_counts: SecondaryMap<DefaultKey, u32>,
+    #[cfg(feature = "access-stats")]
+    access_stats: AccessStats,
+    frozen: bool,
 }
 
 impl EntitiesAndComponents {
     /// Creates a new EntitiesAndComponents struct
+    /// The iteration order only depends on the operations made on the world, so it is the same every run,
+    /// since the internal maps use FxHash, which has no random seed
     pub fn new() -> Self {
         // not sure what the capacity should be here
         EntitiesAndComponents {
             entities: SlotMap::with_capacity(100),
             components: SlotMap::with_capacity(100),
             entities_with_components: FxHashMap::with_capacity_and_hasher(3, Default::default()),
+            disabled_counts: FxHashMap::default(),
+            component_type_ids: FxHashMap::default(),
+            component_type_names: FxHashMap::default(),
+            component_accessors: FxHashMap::default(),
+            component_restorers: FxHashMap::default(),
+            pending_inits: Vec::new(),
+            component_masks: ComponentMasks::new(),
+            boxed_inserters: FxHashMap::default(),
+            storages: FxHashMap::default(),
+            cloners: FxHashMap::default(),
+            histories: FxHashMap::default(),
+            component_formatters: FxHashMap::default(),
+            factories: FxHashMap::default(),
+            net_ids: NetIds::new(),
+            replicators: FxHashMap::default(),
             resources: FxHashMap::default(),
+            commands: Commands::new(),
+            change_tracker: ChangeTracker::new(),
+            double_buffer_swaps: FxHashMap::default(),
+            mailboxes: FxHashMap::default(),
+            relation_cleanups: FxHashMap::default(),
+            entity_remaps: FxHashMap::default(),
+            entity_refs: SecondaryMap::new(),
+            linked_components: [TypeId::of::<Box<Parent>>(), TypeId::of::<Box<Children>>()]
+                .into_iter()
+                .collect(),
+            max_entities: None,
+            error_handler: None,
+            archetype_table: ArchetypeTable::default(),
+            archetype_empty_hooks: Vec::new(),
+            archetype_generation: 0,
+            cached_queries: FxHashMap::default(),
+            temporary_tags: FxHashMap::default(),
+            system_last_run: 0,
+            archetype_transitions: None,
+            journal: Journal::new(),
+            frame: 0,
+            paused: false,
+            stable_ids: StableIds::new(),
+            external_ids: None,
+            ref_counts: SecondaryMap::new(),
+            #[cfg(feature = "access-stats")]
+            access_stats: AccessStats::new(),
+            frozen: false,
         }
     }
 
+    /// The same as new, which is already deterministic
+    #[doc(hidden)]
+    #[deprecated(note = "new already has the same iteration order every run, use it instead")]
+    pub fn with_deterministic_hashing() -> Self {
+        Self::new()
+    }
+
     /// Adds an entity to the game engine
     /// Returns the entity
+    /// panics if the world already has the maximum number of entities, see try_add_entity
     pub fn add_entity(&mut self) -> Entity {
+        self.try_add_entity()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Adds an entity to the game engine
+    /// Returns an error instead of the entity if the world already has the maximum number of entities
+    pub fn try_add_entity(&mut self) -> Result<Entity, EcsError> {
+        self.check_not_frozen()?;
+        self.check_capacity()?;
         let entity_id = self.components.insert(Map::new());
         self.entities.insert(Entity { entity_id });
+        self.archetype_generation += 1;
+        self.refresh_archetype(Entity { entity_id });
+        self.change_tracker.entity_added(entity_id);
+        if let Some(external_ids) = &mut self.external_ids {
+            external_ids.entity_added(entity_id);
+        }
+        trace_structural_change!(entity = ?entity_id, "spawn");
+        self.journal_spawn(Entity { entity_id });
+
+        Ok(Entity { entity_id })
+    }
 
-        Entity { entity_id }
+    /// Adds an entity to the game engine and returns a SingleMutEntity for it,
+    /// so its components can be added right away, for example
+    /// spawn().insert(Position { x: 0.0 }).insert(Velocity { x: 1.0 }).id()
+    /// panics if the world already has the maximum number of entities
+    pub fn spawn(&mut self) -> SingleMutEntity<'_> {
+        let entity = self.add_entity();
+        SingleMutEntity {
+            entity,
+            entities_and_components: self,
+        }
     }
 
     /// Adds an entity to the game engine with components
+    /// panics if the world already has the maximum number of entities, see try_add_entity_with,
+    /// or if the tuple has the same component type more than once
     pub fn add_entity_with<T: OwnedComponents<Input = T>>(&mut self, components: T) -> Entity {
         let entity = <T>::make_entity_with_components(self, components);
         entity
     }
 
+    /// Adds an entity to the game engine with components
+    /// Returns an error instead of the entity if the world already has the maximum number of entities,
+    /// in which case the components are dropped
+    pub fn try_add_entity_with<T: OwnedComponents<Input = T>>(
+        &mut self,
+        components: T,
+    ) -> Result<Entity, EcsError> {
+        self.check_not_frozen()?;
+        self.check_capacity()?;
+        Ok(self.add_entity_with(components))
+    }
+
+    /// Adds an entity with components built from a resource, for example starting health from a Difficulty resource
+    /// The resource is only borrowed while f runs, so the entity can be spawned right after
+    /// panics if the resource does not exist or the world already has the maximum number of entities
+    pub fn spawn_from_resource<R: Resource, T: OwnedComponents<Input = T>>(
+        &mut self,
+        f: impl FnOnce(&R) -> T,
+    ) -> Entity {
+        let resource = self.get_resource::<R>().unwrap_or_else(|| {
+            panic!(
+                "Resource of type {type:?} does not exist, was the type edited?",
+                type = std::any::type_name::<R>()
+            );
+        });
+        let components = f(resource);
+        self.add_entity_with(components)
+    }
+
+    /// Limits the number of entities in the world, None removes the limit
+    /// Once the limit is reached try_add_entity and try_add_entity_with return an error, and add_entity and add_entity_with panic
+    /// Setting a limit lower than the current number of entities does not remove any entities
+    pub fn set_max_entities(&mut self, max_entities: Option<usize>) {
+        self.max_entities = max_entities;
+    }
+
+    fn check_capacity(&self) -> Result<(), EcsError> {
+        match self.max_entities {
+            Some(max_entities) if self.entities.len() >= max_entities => {
+                Err(EcsError::CapacityExceeded { max_entities })
+            }
+            _ => Ok(()),
+        }
+    }
+
     /// Removes an entity from the game engine
     /// This will also remove all children of the entity
+    /// The entity's whole component map is taken out at once, so only the index of each of its component types is touched
+    /// If the entity does not exist, the error handler is called, or it panics if there is no handler
     pub fn remove_entity(&mut self, entity: Entity) {
+        if self.frozen_mutation() {
+            return;
+        }
+        if !self.does_entity_exist(entity) {
+            return self.report_error(EcsError::EntityNotFound { entity });
+        }
         self.remove_parent(entity);
-        let children = self
-            .try_get_components::<(Children,)>(entity)
-            .0
-            .unwrap_or(&Children { children: vec![] })
-            .children
-            .clone();
+        self.despawn_detached(entity);
+    }
 
-        for child in children {
-            self.remove_entity(child);
+    /// Removes every entity with the component T, including disabled ones, along with their children
+    /// The set of entities with T is taken out of the index at once instead of being updated one entity at a time
+    pub fn despawn_all_with<T: Component>(&mut self) {
+        if self.frozen_mutation() {
+            return;
         }
+        let Some(entities) = self
+            .entities_with_components
+            .get_mut(&TypeId::of::<Box<T>>())
+            .map(std::mem::take)
+        else {
+            return;
+        };
 
-        match self.components.get(entity.entity_id) {
-            Some(components) => {
-                for type_id in components.as_raw().keys() {
-                    match self.entities_with_components.get_mut(&type_id) {
-                        Some(entities) => {
-                            entities.remove(entity.entity_id);
-                        }
-                        None => {}
-                    }
-                }
+        for entity in entities.values() {
+            // it may have been removed already as the child of another entity with T
+            if self.does_entity_exist(*entity) {
+                self.remove_entity(*entity);
             }
-            None => {}
         }
+        // the entities were already out of the index, so a T in a custom storage wasn't uncounted
+        self.disabled_counts.remove(&TypeId::of::<Box<T>>());
+    }
+
+    // the entity's parent no longer knows about it, either it was detached or the parent is being despawned as well
+    fn despawn_detached(&mut self, entity: Entity) {
+        // the whole hierarchy is one change for undo_last
+        let mut despawned = Vec::new();
+        self.despawn_tree(entity, None, &mut despawned);
+        self.journal_despawn(despawned);
+    }
+
+    // parent is the entity's parent if it is being despawned as well
+    fn despawn_tree(
+        &mut self,
+        entity: Entity,
+        parent: Option<Entity>,
+        despawned: &mut Vec<DespawnedEntity>,
+    ) {
+        trace_structural_change!(entity = ?entity.entity_id, "despawn");
+        if self.components.contains_key(entity.entity_id) {
+            self.count_disabled_entity(entity, false);
+        }
+        let Some(mut components) = self.components.remove(entity.entity_id) else {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        };
+        let stable_id = self.get_stable_id(entity);
+        self.forget_entity(entity.entity_id);
+
+        for type_id in components.as_raw().keys() {
+            if let Some(entities) = self.entities_with_components.get_mut(type_id) {
+                entities.remove(entity.entity_id);
+            }
+        }
+        if !self.storages.is_empty() {
+            self.remove_from_storages(entity);
+        }
+        self.archetype_left(&components);
+        self.forget_relations(entity, &mut components);
+
+        let children = components.remove::<Box<Children>>();
+        self.journal_despawned_entity(despawned, entity, parent, stable_id, components);
+        // the children point at an entity that is already gone, so they can't detach themselves
+        if let Some(children) = children {
+            for child in children.children {
+                self.despawn_tree(child, Some(entity), despawned);
+            }
+        }
+    }
+
+    /// removes the entity and everything stored about it outside of its components
+    fn forget_entity(&mut self, entity_id: DefaultKey) {
+        self.entities.remove(entity_id);
+        self.archetype_generation += 1;
+        self.archetype_table.entity_removed(entity_id);
+        self.change_tracker.entity_removed(entity_id);
+        self.component_masks.entity_removed(entity_id);
+        self.stable_ids.entity_removed(entity_id);
+        if let Some(external_ids) = &mut self.external_ids {
+            external_ids.entity_removed(entity_id);
+        }
+        self.ref_counts.remove(entity_id);
+        if !self.entity_refs.is_empty() {
+            self.clear_entity_refs(entity_id);
+        }
+        if !self.histories.is_empty() {
+            self.forget_history(entity_id, None);
+        }
+        for mailboxes in self.mailboxes.values_mut() {
+            mailboxes.forget(entity_id);
+        }
+        for temporary_tags in self.temporary_tags.values_mut() {
+            temporary_tags.frames_left.remove(entity_id);
+        }
+    }
+
+    /// returns an iterator over all the entities in the game engine, except disabled ones
+    /// Nothing is allocated, so prefer this over get_entities unless the world has to be changed while iterating
+    pub fn iter_entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        let disabled = self.disabled_entities();
+        self.entities
+            .values()
+            .filter(move |entity| !is_in(disabled, entity))
+            .copied()
+    }
 
-        self.components.remove(entity.entity_id);
-        self.entities.remove(entity.entity_id);
+    /// returns an iterator over all the entities in the game engine, including disabled ones
+    pub fn iter_entities_include_disabled(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.values().copied()
     }
 
-    /// Gets a reference to all the entities in the game engine
-    /// Should rarely if ever be used
+    /// Gets a copy of all the entities in the game engine, except disabled ones
+    /// Only needed when the world is changed while iterating, otherwise use iter_entities
     pub fn get_entities(&self) -> Vec<Entity> {
-        // clone the entities vector
-        self.entities.values().cloned().collect::<Vec<Entity>>()
+        self.iter_entities().collect::<Vec<Entity>>()
+    }
+
+    /// Gets a copy of all the entities in the game engine, including disabled ones
+    /// Only needed when the world is changed while iterating, otherwise use iter_entities_include_disabled
+    pub fn get_entities_include_disabled(&self) -> Vec<Entity> {
+        self.iter_entities_include_disabled()
+            .collect::<Vec<Entity>>()
+    }
+
+    /// Gets a copy of all the entities in the game engine sorted by a key, except disabled ones
+    /// This makes logic that depends on the order entities are processed in reproducible, for example by sorting by stable id
+    /// The sort is stable and key is only called once per entity
+    pub fn iter_entities_sorted_by_key<K: Ord>(&self, key: impl Fn(Entity) -> K) -> Vec<Entity> {
+        let mut entities = self.get_entities();
+        entities.sort_by_cached_key(|entity| key(*entity));
+        entities
+    }
+
+    /// Calls f with a SingleMutEntity for every entity in the game engine, one at a time
+    /// Unlike single_entity_step this is serial, so any mutation is allowed
+    /// The entities are collected before iterating, structural changes take effect immediately but
+    /// entities added during iteration will not be visited and entities removed during iteration will be skipped
+    pub fn for_each_entity_mut(&mut self, mut f: impl FnMut(SingleMutEntity)) {
+        for entity in self.get_entities() {
+            if !self.does_entity_exist(entity) {
+                continue;
+            }
+
+            f(SingleMutEntity {
+                entity,
+                entities_and_components: self,
+            });
+        }
+    }
+
+    /// Calls f for every entity with the component T, with the component and the rest of the world mutably
+    /// The component is detached from its entity while f runs, so f can change anything else in the world,
+    /// including spawning entities and mutating other entities' components
+    /// The entities are collected before iterating, entities that get T during iteration will not be visited
+    /// If f removes the entity or its T the component is dropped, if f adds a new T to the entity that one is kept
+    pub fn for_each_component_mut<T: Component>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut T, &mut EntitiesAndComponents),
+    ) {
+        if self.frozen_mutation() {
+            return;
+        }
+        let type_id = TypeId::of::<Box<T>>();
+        let entities = self
+            .get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+
+        for entity in entities {
+            // an earlier call may have removed the entity or the component
+            let Some(mut component) = self
+                .components
+                .get_mut(entity.entity_id)
+                .and_then(|components| remove_stored::<T>(&mut self.storages, components, entity))
+            else {
+                continue;
+            };
+            if let Some(entities) = self.entities_with_components.get_mut(&type_id) {
+                entities.remove(entity.entity_id);
+            }
+
+            let generation = self.archetype_generation;
+            f(entity, &mut component, self);
+
+            // the change tracker only forgets the component if f removed it
+            if self.change_tracker.get(entity.entity_id, type_id).is_none() {
+                self.archetype_generation += 1;
+                self.refresh_archetype(entity);
+                continue;
+            }
+            if let Some(components) = self.components.get_mut(entity.entity_id) {
+                if get_stored::<T>(&self.storages, components, entity).is_none() {
+                    insert_stored(&mut self.storages, components, entity, component);
+                    self.change_tracker
+                        .component_changed(entity.entity_id, type_id);
+                }
+                if let Some(entities) = self.entities_with_components.get_mut(&type_id) {
+                    entities.insert(entity.entity_id, entity);
+                }
+                // f may have disabled the entity while T was taken out
+                self.count_disabled_component(entity, type_id, true);
+            }
+            // anything f added or removed was put in an archetype without T
+            if self.archetype_generation != generation {
+                self.refresh_archetype(entity);
+            }
+        }
+    }
+
+    /// Calls f for every entity with the component T until it returns Some, and returns that
+    /// The entities after the one that returned Some are not visited, so it is cheap to find the first match and change it
+    /// Every component f is called with counts as changed, see changed_since
+    pub fn find_map_mut<T: Component, R>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut T) -> Option<R>,
+    ) -> Option<R> {
+        if self.frozen_mutation() {
+            return None;
+        }
+        self.record_write::<T>();
+        let type_id = TypeId::of::<Box<T>>();
+        let entities = self.entities_with_components.get(&type_id)?;
+        let disabled = self
+            .entities_with_components
+            .get(&TypeId::of::<Box<Disabled>>());
+
+        for entity in entities.values() {
+            if is_in(disabled, entity) {
+                continue;
+            }
+            let Some(component) = get_stored_mut::<T>(
+                &mut self.storages,
+                &mut self.components[entity.entity_id],
+                *entity,
+            ) else {
+                continue;
+            };
+            self.change_tracker
+                .component_changed(entity.entity_id, type_id);
+            if let Some(result) = f(*entity, component) {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    /// Copies every T into one contiguous buffer and calls f with it, so it can be processed in bulk, for example with SIMD
+    /// The entities slice has the entity of every component at the same index
+    /// Afterwards the components are copied back, and every one of them counts as changed
+    /// Disabled entities are skipped
+    pub fn with_component_buffer_mut<T: Component + Copy>(
+        &mut self,
+        f: impl FnOnce(&mut [T], &[Entity]),
+    ) {
+        if self.frozen_mutation() {
+            return;
+        }
+        self.record_write::<T>();
+        let mut entities = Vec::new();
+        let mut buffer = Vec::new();
+        for entity in self.get_entities_with_component::<T>() {
+            if let Some(component) =
+                get_stored::<T>(&self.storages, &self.components[entity.entity_id], *entity)
+            {
+                entities.push(*entity);
+                buffer.push(*component);
+            }
+        }
+
+        f(&mut buffer, &entities);
+
+        let type_id = TypeId::of::<Box<T>>();
+        for (entity, value) in entities.into_iter().zip(buffer) {
+            // marked before the write, so the history records the value from before f
+            self.mark_changed(entity, type_id);
+            if let Some(component) = get_stored_mut::<T>(
+                &mut self.storages,
+                &mut self.components[entity.entity_id],
+                entity,
+            ) {
+                *component = value;
+            }
+        }
+    }
+
+    /// Calls f for every entity with the component T in parallel, except disabled ones, with the component mutably
+    /// Every entity's component is its own, so they are handed out directly without building a SingleMutEntity for each,
+    /// this is the lean way to update one component on every entity
+    /// Every component f is called with counts as changed
+    /// A custom storage can only hand out one mutable reference at a time, so if T has one f is called on one entity after another
+    pub fn par_for_each_mut<T: Component + Send + Sync>(
+        &mut self,
+        f: impl Fn(Entity, &mut T) + Sync,
+    ) {
+        if self.frozen_mutation() {
+            return;
+        }
+        self.record_write::<T>();
+        let type_id = TypeId::of::<Box<T>>();
+        let entities = self
+            .get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+        for entity in &entities {
+            self.mark_changed(*entity, type_id);
+        }
+        if self.storages.contains_key(&TypeId::of::<T>()) {
+            for entity in entities {
+                if let Some(component) = get_stored_mut::<T>(
+                    &mut self.storages,
+                    &mut self.components[entity.entity_id],
+                    entity,
+                ) {
+                    f(entity, component);
+                }
+            }
+            return;
+        }
+
+        // the index has every entity once, so none of the references alias
+        let components = entities
+            .into_iter()
+            .filter_map(|entity| {
+                let component: *mut T =
+                    &mut **self.components[entity.entity_id].get_mut::<Box<T>>()?;
+                Some((entity, unsafe { &mut *component }))
+            })
+            .collect::<Vec<(Entity, &mut T)>>();
+        #[cfg(not(feature = "single-threaded"))]
+        components
+            .into_par_iter()
+            .for_each(|(entity, component)| f(entity, component));
+        #[cfg(feature = "single-threaded")]
+        components
+            .into_iter()
+            .for_each(|(entity, component)| f(entity, component));
+    }
+
+    /// Calls f once for every pair of entities with all the components in T, for example to check them for collisions
+    /// Every pair is visited once, so with n entities f is called n * (n - 1) / 2 times
+    /// Disabled entities are skipped, and every component f is called with counts as changed
+    /// panics if a component in T has a custom storage, see get_many_mut
+    pub fn for_each_pair<T>(
+        &mut self,
+        mut f: impl for<'b> FnMut(
+            (Entity, <T as ComponentsMut<'b>>::Result),
+            (Entity, <T as ComponentsMut<'b>>::Result),
+        ),
+    ) where
+        T: for<'b> ComponentsMut<'b> + ComponentTypes + 'static,
+    {
+        if self.frozen_mutation() {
+            return;
+        }
+        let type_ids = T::storage_type_ids();
+        let Some(first) = type_ids
+            .first()
+            .and_then(|type_id| self.entities_with_components.get(type_id))
+        else {
+            return;
+        };
+        let disabled = self
+            .entities_with_components
+            .get(&TypeId::of::<Box<Disabled>>());
+        let entities = {
+            let has_all = self.has_all_matcher(&type_ids[1..]);
+            first
+                .values()
+                .filter(|entity| !is_in(disabled, entity) && has_all(entity))
+                .copied()
+                .collect::<Vec<Entity>>()
+        };
+
+        // indexes instead of iterators, so the world can be borrowed again for every pair
+        for i in 0..entities.len() {
+            for j in i + 1..entities.len() {
+                let [a, b] = self.get_many_mut::<T, 2>([entities[i], entities[j]]);
+                f((entities[i], a), (entities[j], b));
+            }
+        }
     }
 
-    /// Gets a copy of an entity at a certain index
+    /// Gets a copy of an entity at a certain index, skipping disabled ones
     pub fn get_nth_entity(&self, index: usize) -> Option<Entity> {
         // get the nth entity
-        if let Some(entity) = self.entities.values().nth(index) {
-            Some(entity.clone())
-        } else {
-            None
-        }
+        self.iter_entities().nth(index)
+    }
+
+    /// Gets a copy of an entity at a certain index, including disabled ones
+    pub fn get_nth_entity_include_disabled(&self, index: usize) -> Option<Entity> {
+        self.iter_entities_include_disabled().nth(index)
     }
 
-    /// Gets the number of entities in the game engine
+    /// Gets the number of entities in the game engine, except disabled ones
     pub fn get_entity_count(&self) -> usize {
+        self.entities.len() - self.disabled_count(TypeId::of::<Box<Disabled>>())
+    }
+
+    /// Gets the number of entities in the game engine, including disabled ones
+    pub fn get_entity_count_include_disabled(&self) -> usize {
         self.entities.len()
     }
 
+    /// Gets the current frame, which is the number of times World::run has been called
+    /// It goes up at the very start of World::run, so systems see 1 in the first frame
+    pub fn current_frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Pauses or resumes the world, while paused World::run skips every system and resource update
+    /// except those whose runs_while_paused returns true, and registered double buffers are not swapped
+    /// The change takes effect from the next frame, so a system that keeps running while paused can resume the world
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Checks if the world is paused, see set_paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
     /// Gets a reference to all the components on an entity
     /// Returns an AnyMap, which can be used to get a reference to a component
     /// This should rarely if ever be used
@@ -172,6 +945,8 @@ impl EntitiesAndComponents {
         &mut self,
         entity: Entity,
     ) -> &mut anymap::Map<(dyn Any + 'static)> {
+        // the map has to be returned, so a frozen world is only reported
+        self.frozen_mutation();
         self.components
             .get_mut(entity.entity_id)
             .unwrap_or_else(|| {
@@ -181,8 +956,10 @@ impl EntitiesAndComponents {
 
     /// Gets a reference to a component on an entity
     /// If the component does not exist on the entity, it will return None
-    /// panics if the entity does not exist
+    /// panics if the entity does not exist, or if T has a custom storage, use try_get_components for those
     pub fn try_get_component<T: Component>(&self, entity: Entity) -> Option<&Box<T>> {
+        self.assert_default_storage::<T>();
+        self.record_read::<T>();
         self.components
             .get(entity.entity_id)
             .unwrap_or_else(|| {
@@ -193,8 +970,11 @@ impl EntitiesAndComponents {
 
     /// Gets a mutable reference to a component on an entity
     /// If the component does not exist on the entity, it will return None
-    /// panics if the entity does not exist
+    /// panics if the entity does not exist, or if T has a custom storage, use try_get_components_mut for those
     pub fn try_get_component_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut Box<T>> {
+        self.assert_default_storage::<T>();
+        self.record_write::<T>();
+        self.mark_changed(entity, TypeId::of::<Box<T>>());
         self.components
             .get_mut(entity.entity_id)
             .unwrap_or_else(|| {
@@ -203,6 +983,27 @@ impl EntitiesAndComponents {
             .get_mut::<Box<T>>()
     }
 
+    // components with a custom storage aren't boxed, so they can't be returned as a Box<T>
+    fn assert_default_storage<T: Component>(&self) {
+        if self.storages.contains_key(&TypeId::of::<T>()) {
+            panic!(
+                "Component of type {type:?} has a custom storage, use try_get_components instead",
+                type = std::any::type_name::<T>()
+            );
+        }
+    }
+
+    /// Gets a copy of a component on an entity, or the default value if the entity does not have it
+    /// This never panics, an entity that does not exist also gives the default value
+    pub fn get_component_or_default<T: Component + Default + Clone>(&self, entity: Entity) -> T {
+        self.record_read::<T>();
+        self.components
+            .get(entity.entity_id)
+            .and_then(|components| get_stored::<T>(&self.storages, components, entity))
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Gets a tuple of references to components on an entity
     /// If the component does not exist on the entity, it will panic
     /// panics if the entity does not exist
@@ -223,6 +1024,32 @@ impl EntitiesAndComponents {
         <T>::get_components_mut(self, entity)
     }
 
+    /// Gets mutable references to the same components on several entities at once
+    /// If the component does not exist on an entity, it will panic
+    /// panics if an entity does not exist or is in the array more than once,
+    /// or if a component has a custom storage, since a storage can only hand out one mutable reference at a time
+    pub fn get_many_mut<'a, T: ComponentsMut<'a> + ComponentTypes + 'static, const N: usize>(
+        &'a mut self,
+        entities: [Entity; N],
+    ) -> [T::Result; N] {
+        for type_id in T::type_ids() {
+            if self.storages.contains_key(&type_id) {
+                panic!("A component with a custom storage can't be borrowed mutably on several entities at once");
+            }
+        }
+        for i in 0..N {
+            for j in i + 1..N {
+                assert_ne!(
+                    entities[i], entities[j],
+                    "You cannot borrow the same entity mutably more than once!"
+                );
+            }
+        }
+        let pointer = self as *mut EntitiesAndComponents;
+        // SAFETY: the entities are all different, so the references to their components never overlap
+        entities.map(|entity| <T>::get_components_mut(unsafe { &mut *pointer }, entity))
+    }
+
     /// Gets a tuple of references to components on an entity
     /// If the component does not exist on the entity it will return None
     /// panics if the entity does not exist
@@ -245,16 +1072,33 @@ impl EntitiesAndComponents {
 
     /// Adds a component to an entity
     /// If the component already exists on the entity, it will be overwritten
-    /// panics if the entity does not exist
+    /// If the entity does not exist, the error handler is called and nothing is added, or it panics if there is no handler
     pub fn add_component_to<T: Component>(&mut self, entity: Entity, component: T) {
+        if self.frozen_mutation() {
+            return;
+        }
+        if !self.histories.is_empty() {
+            self.record_history(entity, TypeId::of::<Box<T>>());
+        }
         // add the component to the entity
-        let components = self
-            .components
-            .get_mut(entity.entity_id)
-            .unwrap_or_else(|| {
-                panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
-            });
-        components.insert(Box::new(component));
+        let Some(components) = self.components.get_mut(entity.entity_id) else {
+            return self.report_error(EcsError::EntityNotFound { entity });
+        };
+        let previous = insert_stored(&mut self.storages, components, entity, component);
+        let is_new = previous.is_none();
+        self.change_tracker
+            .component_added(entity.entity_id, TypeId::of::<Box<T>>());
+        self.component_masks.component_added(
+            entity.entity_id,
+            TypeId::of::<Box<T>>(),
+            &self.linked_components,
+        );
+        self.journal_component_added(entity, previous);
+        trace_structural_change!(
+            entity = ?entity.entity_id,
+            component = std::any::type_name::<T>(),
+            "component added"
+        );
 
         // add the entity to the list of entities with the component
         match self.entities_with_components.entry(TypeId::of::<Box<T>>()) {
@@ -265,22 +1109,108 @@ impl EntitiesAndComponents {
                 let mut new_map = SecondaryMap::new();
                 new_map.insert(entity.entity_id, entity);
                 entry.insert(new_map);
+                // first time this component type has been seen
+                self.component_type_ids
+                    .insert(TypeId::of::<Box<T>>(), TypeId::of::<T>());
+                self.component_type_names
+                    .insert(TypeId::of::<Box<T>>(), std::any::type_name::<T>());
+                self.component_accessors
+                    .insert(TypeId::of::<Box<T>>(), get_component_as_any::<T>);
+                self.component_restorers
+                    .insert(TypeId::of::<Box<T>>(), restore_component_of::<T>);
+                self.register_boxed_component::<T>();
+            }
+        }
+        if is_new {
+            self.count_disabled_component(entity, TypeId::of::<Box<T>>(), true);
+            self.archetype_transition(entity, TypeId::of::<T>(), true);
+            if !self.pending_inits.is_empty() {
+                self.queue_initializers(entity, TypeId::of::<Box<T>>());
+            }
+            if !self.histories.is_empty() {
+                self.start_history(entity, TypeId::of::<Box<T>>());
             }
         }
     }
 
-    /// Removes a component from an entity
-    /// If the component does not exist on the entity, it will do nothing
+    /// Replaces a component on an entity in place and returns the old one
+    /// Unlike add_component_to this is a change, not a structural change, so the entity keeps its archetype
+    /// and the component shows up in changed_since like any other mutable access
+    /// If the component does not exist on the entity, it is added like add_component_to and None is returned
     /// panics if the entity does not exist
-    pub fn remove_component_from<T: Component>(&mut self, entity: Entity) {
+    pub fn replace_component<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        match self.try_get_components_mut::<(T,)>(entity) {
+            (Some(existing),) => Some(std::mem::replace(existing, component)),
+            (None,) => {
+                self.add_component_to(entity, component);
+                None
+            }
+        }
+    }
+
+    /// Changes a component on an entity and adds the component f returns, if any, to the same entity
+    /// For example a velocity can be updated and a FastMoving marker added once it gets too high
+    /// panics if the entity does not exist or does not have the component T
+    pub fn update_and_maybe_insert<T: Component, U: Component>(
+        &mut self,
+        entity: Entity,
+        f: impl FnOnce(&mut T) -> Option<U>,
+    ) {
+        let (component,) = self.get_components_mut::<(T,)>(entity);
+        if let Some(new_component) = f(component) {
+            self.add_component_to(entity, new_component);
+        }
+    }
+
+    /// Adds a component to every entity in pairs, like calling add_component_to for each pair in order
+    /// The set of entities with T is grown once up front instead of while inserting,
+    /// so this is the fast way to write back results computed for many entities, for example in a prestep
+    /// If an entity does not exist, the error handler is called, or it panics if there is no handler
+    pub fn insert_components_bulk<T: Component>(&mut self, pairs: Vec<(Entity, T)>) {
+        let mut pairs = pairs.into_iter();
+        let Some((entity, component)) = pairs.next() else {
+            return;
+        };
+        // the first insert registers the type if it is new
+        self.add_component_to(entity, component);
+        if let Some(entities) = self
+            .entities_with_components
+            .get_mut(&TypeId::of::<Box<T>>())
+        {
+            entities.set_capacity(self.entities.capacity());
+        }
+        for (entity, component) in pairs {
+            self.add_component_to(entity, component);
+        }
+    }
+
+    /// Removes a component from an entity
+    /// Returns true if the entity had the component, if not it will do nothing and return false
+    /// If the entity does not exist, the error handler is called, or it panics if there is no handler
+    pub fn remove_component_from<T: Component>(&mut self, entity: Entity) -> bool {
+        if self.frozen_mutation() {
+            return false;
+        }
         // remove the component from the entity
-        let components = self
-            .components
-            .get_mut(entity.entity_id)
-            .unwrap_or_else(|| {
-                panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
-            });
-        components.remove::<Box<T>>();
+        let Some(components) = self.components.get_mut(entity.entity_id) else {
+            self.report_error(EcsError::EntityNotFound { entity });
+            return false;
+        };
+        let removed = remove_stored::<T>(&mut self.storages, components, entity);
+        let was_removed = removed.is_some();
+        self.change_tracker
+            .component_removed(entity.entity_id, TypeId::of::<Box<T>>());
+        self.component_masks
+            .component_removed(entity.entity_id, TypeId::of::<Box<T>>());
+        if let Some(removed) = removed {
+            self.journal_component_removed(entity, removed);
+            self.archetype_transition(entity, TypeId::of::<T>(), false);
+        }
+        trace_structural_change!(
+            entity = ?entity.entity_id,
+            component = std::any::type_name::<T>(),
+            "component removed"
+        );
 
         // remove the entity from the list of entities with the component
         match self
@@ -292,10 +1222,78 @@ impl EntitiesAndComponents {
             }
             None => {}
         }
+        if was_removed {
+            self.count_disabled_component(entity, TypeId::of::<Box<T>>(), false);
+            if !self.temporary_tags.is_empty() {
+                self.forget_temporary_tag(entity.entity_id, TypeId::of::<T>());
+            }
+        }
+        if !self.histories.is_empty() {
+            self.forget_history(entity.entity_id, Some(TypeId::of::<Box<T>>()));
+        }
+        was_removed
+    }
+
+    /// Adds a component to an entity without overwriting the components of the same type already on it
+    /// The components are stored in order in a ComponentVec<T> component
+    /// Returns the index of the component in the entity's ComponentVec<T>
+    /// panics if the entity does not exist
+    pub fn add_component_indexed<T: Component>(&mut self, entity: Entity, component: T) -> usize {
+        if let Some(component_vec) = self.try_get_component_mut::<ComponentVec<T>>(entity) {
+            component_vec.components.push(component);
+            return component_vec.components.len() - 1;
+        }
+
+        self.add_component_to(
+            entity,
+            ComponentVec {
+                components: vec![component],
+            },
+        );
+        0
+    }
+
+    /// Gets all the components of a type that were added with add_component_indexed
+    /// If the entity has none of them, it will return an empty slice
+    /// panics if the entity does not exist
+    pub fn get_components_indexed<T: Component>(&self, entity: Entity) -> &[T] {
+        match self.try_get_component::<ComponentVec<T>>(entity) {
+            Some(component_vec) => &component_vec.components,
+            None => &[],
+        }
+    }
+
+    /// Gets all the components of a type that were added with add_component_indexed mutably
+    /// If the entity has none of them, it will return an empty slice
+    /// panics if the entity does not exist
+    pub fn get_components_indexed_mut<T: Component>(&mut self, entity: Entity) -> &mut [T] {
+        match self.try_get_component_mut::<ComponentVec<T>>(entity) {
+            Some(component_vec) => &mut component_vec.components,
+            None => &mut [],
+        }
+    }
+
+    /// returns an iterator over all entities with a certain component, except disabled ones
+    pub fn get_entities_with_component<T: Component>(&self) -> impl Iterator<Item = &Entity> + '_ {
+        let disabled = self.disabled_entities();
+        self.get_entities_with_component_include_disabled::<T>()
+            .filter(move |entity| !is_in(disabled, entity))
+    }
+
+    /// returns an iterator over all entities with a certain component in the reverse order of get_entities_with_component,
+    /// except disabled ones
+    /// That order is by where the entities are stored, not when they got T, since removed entities make room for new ones
+    /// The entities are collected first, so this allocates
+    pub fn entities_with_component_rev<T: Component>(&self) -> impl Iterator<Item = Entity> {
+        self.get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>()
+            .into_iter()
+            .rev()
     }
 
-    /// returns an iterator over all entities with a certain component
-    pub fn get_entities_with_component<T: Component>(
+    /// returns an iterator over all entities with a certain component, including disabled ones
+    pub fn get_entities_with_component_include_disabled<T: Component>(
         &self,
     ) -> std::iter::Flatten<std::option::IntoIter<slotmap::secondary::Values<'_, DefaultKey, Entity>>>
     {
@@ -305,29 +1303,192 @@ impl EntitiesAndComponents {
         }
     }
 
-    /// gets the number of entities with a certain component
-    pub fn get_entity_count_with_component<T: Component>(&self) -> usize {
-        match self.entities_with_components.get(&TypeId::of::<Box<T>>()) {
-            Some(entities) => entities.len(),
-            None => 0,
+    /// Groups the entities with a certain component by a key taken from the component, except disabled ones
+    /// For example all the entities with the same mesh, so they can be drawn in one batch
+    /// The entities in each group are in the same order as get_entities_with_component
+    pub fn group_by<T: Component, K: Hash + Eq>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> HashMap<K, Vec<Entity>> {
+        self.record_read::<T>();
+        let mut groups: HashMap<K, Vec<Entity>> = HashMap::new();
+        for entity in self.get_entities_with_component::<T>() {
+            if let Some(component) =
+                get_stored::<T>(&self.storages, &self.components[entity.entity_id], *entity)
+            {
+                groups.entry(key(component)).or_default().push(*entity);
+            }
         }
+        groups
     }
 
-    /// gets the nth entity with a certain component
-    /// O(n) use get_entities_with_component if you need to iterate over all entities with a certain component
-    pub fn get_entity_with_component<T: Component>(&self, index: usize) -> Option<Entity> {
-        match self.entities_with_components.get(&TypeId::of::<Box<T>>()) {
-            Some(entities) => {
-                if let Some(entity) = entities.values().nth(index) {
-                    Some(entity.clone())
-                } else {
-                    None
-                }
+    /// returns an iterator over every pair of entities whose T has the same key, except disabled ones,
+    /// for example all the entities with the same GroupId for pairwise constraints
+    /// Each pair is returned once, with the entities in the same order as group_by, but the groups come in no particular order
+    /// The pairs are collected first, a group of n entities has n * (n - 1) / 2 pairs
+    pub fn iter_related_pairs<T: Component, K: Hash + Eq>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> impl Iterator<Item = (Entity, Entity)> {
+        let mut pairs = Vec::new();
+        for entities in self.group_by(key).into_values() {
+            for (i, a) in entities.iter().enumerate() {
+                pairs.extend(entities[i + 1..].iter().map(|b| (*a, *b)));
+            }
+        }
+        pairs.into_iter()
+    }
+
+    /// returns an iterator over all entities that have at least one of the components in the tuple, except disabled ones
+    /// each entity is only returned once, even if it has more than one of the components
+    pub fn entities_with_any<T: ComponentTypes>(&self) -> impl Iterator<Item = Entity> + '_ {
+        let disabled = self.disabled_entities();
+        self.entities_with_any_include_disabled::<T>()
+            .filter(move |entity| !is_in(disabled, entity))
+    }
+
+    /// returns an iterator over all entities that have at least one of the components in the tuple, including disabled ones
+    /// each entity is only returned once, even if it has more than one of the components
+    pub fn entities_with_any_include_disabled<T: ComponentTypes>(
+        &self,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let mut seen = FxHashSet::default();
+        T::storage_type_ids()
+            .into_iter()
+            .filter_map(|type_id| self.entities_with_components.get(&type_id))
+            .flat_map(|entities| entities.values())
+            .filter(move |entity| seen.insert(**entity))
+            .copied()
+    }
+
+    /// gets the number of entities with a certain component, except disabled ones
+    pub fn get_entity_count_with_component<T: Component>(&self) -> usize {
+        self.get_entity_count_with_component_include_disabled::<T>()
+            - self.disabled_count(TypeId::of::<Box<T>>())
+    }
+
+    /// Checks if any entity has a certain component, except disabled ones
+    /// O(1), so it is a cheap way to skip work that only applies to entities with T
+    pub fn any_with_component<T: Component>(&self) -> bool {
+        self.get_entity_count_with_component::<T>() > 0
+    }
+
+    /// gets the number of entities with a certain component, including disabled ones
+    pub fn get_entity_count_with_component_include_disabled<T: Component>(&self) -> usize {
+        match self.entities_with_components.get(&TypeId::of::<Box<T>>()) {
+            Some(entities) => entities.len(),
+            None => 0,
+        }
+    }
+
+    /// gets the nth entity with a certain component, skipping disabled ones
+    /// O(n) use get_entities_with_component if you need to iterate over all entities with a certain component
+    pub fn get_entity_with_component<T: Component>(&self, index: usize) -> Option<Entity> {
+        self.get_entities_with_component::<T>().nth(index).copied()
+    }
+
+    /// gets the nth entity with a certain component, including disabled ones
+    /// O(n) use get_entities_with_component_include_disabled if you need to iterate over all entities with a certain component
+    pub fn get_entity_with_component_include_disabled<T: Component>(
+        &self,
+        index: usize,
+    ) -> Option<Entity> {
+        match self.entities_with_components.get(&TypeId::of::<Box<T>>()) {
+            Some(entities) => {
+                if let Some(entity) = entities.values().nth(index) {
+                    Some(entity.clone())
+                } else {
+                    None
+                }
             }
             None => None,
         }
     }
 
+    /// returns an iterator over all entities with a certain component where the predicate returns true
+    /// useful for things like "all enemies with health below 10" or "all positions within bounds"
+    pub fn query_where<'a, T: Component>(
+        &'a self,
+        predicate: impl Fn(Entity, &T) -> bool + 'a,
+    ) -> impl Iterator<Item = (Entity, &'a T)> + 'a {
+        self.record_read::<T>();
+        self.get_entities_with_component::<T>()
+            .filter_map(move |entity| {
+                let component =
+                    get_stored::<T>(&self.storages, &self.components[entity.entity_id], *entity)?;
+                if predicate(*entity, component) {
+                    Some((*entity, component))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// counts the entities with a certain component where the predicate returns true, without allocating
+    /// useful for counters like "enemies remaining"
+    pub fn count_matching<T: Component>(&self, predicate: impl Fn(&T) -> bool) -> usize {
+        self.record_read::<T>();
+        self.get_entities_with_component::<T>()
+            .filter(|entity| {
+                get_stored::<T>(&self.storages, &self.components[entity.entity_id], **entity)
+                    .is_some_and(&predicate)
+            })
+            .count()
+    }
+
+    /// gets the entity with a certain component that has the smallest key, for example the weakest or closest target
+    /// returns None if no entity has the component
+    /// keys that can't be compared (like NaN) are skipped
+    pub fn min_by_component<T: Component, K: PartialOrd>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> Option<(Entity, &T)> {
+        self.best_by_component(key, std::cmp::Ordering::Less)
+    }
+
+    /// gets the entity with a certain component that has the largest key, for example the strongest or farthest target
+    /// returns None if no entity has the component
+    /// keys that can't be compared (like NaN) are skipped
+    pub fn max_by_component<T: Component, K: PartialOrd>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> Option<(Entity, &T)> {
+        self.best_by_component(key, std::cmp::Ordering::Greater)
+    }
+
+    fn best_by_component<T: Component, K: PartialOrd>(
+        &self,
+        key: impl Fn(&T) -> K,
+        wanted: std::cmp::Ordering,
+    ) -> Option<(Entity, &T)> {
+        self.record_read::<T>();
+        let mut best: Option<(Entity, &T, K)> = None;
+
+        for entity in self.get_entities_with_component::<T>() {
+            let Some(component) =
+                get_stored::<T>(&self.storages, &self.components[entity.entity_id], *entity)
+            else {
+                continue;
+            };
+            let component_key = key(component);
+
+            // NaN isn't comparable with anything, not even itself
+            if component_key.partial_cmp(&component_key).is_none() {
+                continue;
+            }
+
+            let is_better = match &best {
+                Some((_, _, best_key)) => component_key.partial_cmp(best_key) == Some(wanted),
+                None => true,
+            };
+            if is_better {
+                best = Some((*entity, component, component_key));
+            }
+        }
+
+        best.map(|(entity, component, _)| (entity, component))
+    }
+
     /// Gets a resource from the game engine
     pub fn get_resource<T: Resource>(&self) -> Option<&T> {
         match self.resources.get(&TypeId::of::<T>()) {
@@ -349,16 +1510,39 @@ impl EntitiesAndComponents {
 
     /// Adds a resource to the game engine
     pub fn add_resource<T: Resource>(&mut self, resource: T) {
+        if self.frozen_mutation() {
+            return;
+        }
+        trace_structural_change!(resource = std::any::type_name::<T>(), "resource added");
         self.resources.insert(TypeId::of::<T>(), Box::new(resource));
     }
 
+    /// Gets a copy of a resource, to be put back later with restore_resource
+    /// returns None if the resource does not exist
+    pub fn snapshot_resource<T: Resource + Clone>(&self) -> Option<T> {
+        self.get_resource::<T>().cloned()
+    }
+
+    /// Puts back a copy of a resource taken with snapshot_resource, replacing the current one
+    /// If the resource has been removed since, it is added again
+    pub fn restore_resource<T: Resource>(&mut self, snapshot: T) {
+        self.add_resource(snapshot);
+    }
+
     /// Removes a resource from the game engine
     pub fn remove_resource<T: Resource>(&mut self) {
+        if self.frozen_mutation() {
+            return;
+        }
+        trace_structural_change!(resource = std::any::type_name::<T>(), "resource removed");
         self.resources.remove(&TypeId::of::<T>());
     }
 
     /// Gets a resource from the game engine mutably, panics if the resource does not exist
     pub fn get_resource_mut<T: Resource>(&mut self) -> Option<&mut T> {
+        if self.frozen_mutation() {
+            return None;
+        }
         match self.resources.get_mut(&TypeId::of::<T>()) {
             Some(resource) => {
                 let resource = (&mut **resource)
@@ -381,6 +1565,49 @@ impl EntitiesAndComponents {
         self.entities.contains_key(entity.entity_id)
     }
 
+    /// Marks the world as read only, until thaw is called any attempt to mutate it will panic in debug builds
+    /// In release builds the error handler is called with EcsError::Frozen instead and the mutation is skipped,
+    /// or it panics if there is no handler
+    /// Mutable access that has to return a reference, like get_components_mut, is only reported
+    /// This includes adding or removing entities, components and resources and any mutable access to them
+    /// useful for making sure something that should only read the world (rendering, replication) doesn't change it
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    /// Allows the world to be mutated again after freeze
+    pub fn thaw(&mut self) {
+        self.frozen = false;
+    }
+
+    /// Checks if the world is frozen, see freeze
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    // mutating a frozen world panics in debug builds and is an EcsError::Frozen in release builds
+    pub(crate) fn check_not_frozen(&self) -> Result<(), EcsError> {
+        if !self.frozen {
+            return Ok(());
+        }
+        if cfg!(debug_assertions) {
+            panic!("{}", EcsError::Frozen);
+        }
+        Err(EcsError::Frozen)
+    }
+
+    // like check_not_frozen, but the error goes to the error handler
+    // returns true if the mutation has to be skipped
+    pub(crate) fn frozen_mutation(&self) -> bool {
+        match self.check_not_frozen() {
+            Ok(()) => false,
+            Err(error) => {
+                self.report_error(error);
+                true
+            }
+        }
+    }
+
     /// This function is used to help debug entities and components
     /// It will print out all the entities and components in the game engine
     /// it prints the type id of the components, not the actual type because that is not possible
@@ -391,7 +1618,7 @@ impl EntitiesAndComponents {
     /// This function is used to help debug entities and components
     /// broken for now
     fn tree(&self, depth: usize) {
-        let mut all_entities = self.get_entities();
+        let mut all_entities = self.get_entities_include_disabled();
         all_entities.sort();
 
         if depth == 0 {
@@ -511,7 +1738,7 @@ impl EntitiesAndComponents {
         &self,
     ) -> std::iter::Flatten<std::option::IntoIter<slotmap::secondary::Values<'_, DefaultKey, Entity>>>
     {
-        self.get_entities_with_component::<Children>()
+        self.get_entities_with_component_include_disabled::<Children>()
     }
 
     /// gets the entities with parents
@@ -519,7 +1746,7 @@ impl EntitiesAndComponents {
         &self,
     ) -> std::iter::Flatten<std::option::IntoIter<slotmap::secondary::Values<'_, DefaultKey, Entity>>>
     {
-        self.get_entities_with_component::<Parent>()
+        self.get_entities_with_component_include_disabled::<Parent>()
     }
 }
 
@@ -528,60 +1755,139 @@ impl EntitiesAndComponents {
 /// It will not allow any non send sync components to be accessed or added
 pub struct EntitiesAndComponentsThreadSafe<'a> {
     entities_and_components: &'a mut EntitiesAndComponents,
+    // snapshot of the entities for entity_chunks, taken the first time it is needed
+    entities: std::sync::OnceLock<Vec<Entity>>,
+    // the commands queued by the one system using this view, see commands
+    commands: std::sync::Mutex<Commands>,
 }
 
 impl<'b> EntitiesAndComponentsThreadSafe<'b> {
     fn new(entities_and_components: &'b mut EntitiesAndComponents) -> Self {
         EntitiesAndComponentsThreadSafe {
             entities_and_components: entities_and_components,
+            entities: std::sync::OnceLock::new(),
+            commands: std::sync::Mutex::new(Commands::new()),
         }
     }
 
+    /// returns an iterator over all the entities in slices of chunk_size, the last slice may be shorter
+    /// The slices can be handed to rayon for custom parallel read passes, for example
+    /// entity_chunks(64).collect::<Vec<&[Entity]>>().par_iter()
+    /// panics if chunk_size is 0
+    pub fn entity_chunks(&self, chunk_size: usize) -> std::slice::Chunks<'_, Entity> {
+        self.entities
+            .get_or_init(|| self.entities_and_components.get_entities())
+            .chunks(chunk_size)
+    }
+
     /// Adds an entity to the game engine
     /// Returns the entity
+    /// panics if the world already has the maximum number of entities, see try_add_entity
     pub fn add_entity(&mut self) -> Entity {
+        self.entities.take();
         self.entities_and_components.add_entity()
     }
 
+    /// Adds an entity to the game engine
+    /// Returns an error instead of the entity if the world already has the maximum number of entities
+    pub fn try_add_entity(&mut self) -> Result<Entity, EcsError> {
+        self.entities.take();
+        self.entities_and_components.try_add_entity()
+    }
+
     /// Adds an entity to the game engine with components
+    /// panics if the world already has the maximum number of entities, see try_add_entity_with
     pub fn add_entity_with<T: OwnedComponents<Input = T> + Send + Sync>(
         &mut self,
         components: T,
     ) -> Entity {
+        self.entities.take();
         self.entities_and_components.add_entity_with(components)
     }
 
+    /// Adds an entity to the game engine with components
+    /// Returns an error instead of the entity if the world already has the maximum number of entities
+    pub fn try_add_entity_with<T: OwnedComponents<Input = T> + Send + Sync>(
+        &mut self,
+        components: T,
+    ) -> Result<Entity, EcsError> {
+        self.entities.take();
+        self.entities_and_components.try_add_entity_with(components)
+    }
+
     /// Removes an entity from the game engine
     pub fn remove_entity(&mut self, entity: Entity) {
+        self.entities.take();
         self.entities_and_components.remove_entity(entity)
     }
 
-    /// Gets a reference to all the entities in the game engine
-    /// Should rarely if ever be used
+    /// Removes every entity with the component T, including disabled ones, along with their children
+    pub fn despawn_all_with<T: Component + Send + Sync>(&mut self) {
+        self.entities.take();
+        self.entities_and_components.despawn_all_with::<T>()
+    }
+
+    /// returns an iterator over all the entities in the game engine, except disabled ones
+    pub fn iter_entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities_and_components.iter_entities()
+    }
+
+    /// returns an iterator over all the entities in the game engine, including disabled ones
+    pub fn iter_entities_include_disabled(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities_and_components
+            .iter_entities_include_disabled()
+    }
+
+    /// Gets a copy of all the entities in the game engine, except disabled ones
+    /// Only needed when the world is changed while iterating, otherwise use iter_entities
     pub fn get_entities(&self) -> Vec<Entity> {
         self.entities_and_components.get_entities()
     }
 
-    /// Gets a copy of an entity at a certain index
+    /// Gets a copy of all the entities in the game engine, including disabled ones
+    pub fn get_entities_include_disabled(&self) -> Vec<Entity> {
+        self.entities_and_components.get_entities_include_disabled()
+    }
+
+    /// Gets a copy of an entity at a certain index, skipping disabled ones
     pub fn get_nth_entity(&self, index: usize) -> Option<Entity> {
         self.entities_and_components.get_nth_entity(index)
     }
 
-    /// Gets the number of entities in the game engine
+    /// Gets a copy of an entity at a certain index, including disabled ones
+    pub fn get_nth_entity_include_disabled(&self, index: usize) -> Option<Entity> {
+        self.entities_and_components
+            .get_nth_entity_include_disabled(index)
+    }
+
+    /// Gets the number of entities in the game engine, except disabled ones
     pub fn get_entity_count(&self) -> usize {
         self.entities_and_components.get_entity_count()
     }
 
+    /// Gets the number of entities in the game engine, including disabled ones
+    pub fn get_entity_count_include_disabled(&self) -> usize {
+        self.entities_and_components
+            .get_entity_count_include_disabled()
+    }
+
+    /// Gets the current frame, which is the number of times World::run has been called
+    pub fn current_frame(&self) -> u64 {
+        self.entities_and_components.current_frame()
+    }
+
     // get all components is impossible to ensure thread safety with
 
     /// Gets a reference to a component on an entity
     /// If the component does not exist on the entity, it will return None
+    /// panics if T has a custom storage, use try_get_components for those
     pub fn try_get_component<T: Component + Send + Sync>(&self, entity: Entity) -> Option<&Box<T>> {
         self.entities_and_components.try_get_component(entity)
     }
 
     /// Gets a mutable reference to a component on an entity
     /// If the component does not exist on the entity, it will return None
+    /// panics if T has a custom storage, use try_get_components_mut for those
     pub fn try_get_component_mut<T: Component + Send + Sync>(
         &mut self,
         entity: Entity,
@@ -589,6 +1895,15 @@ impl<'b> EntitiesAndComponentsThreadSafe<'b> {
         self.entities_and_components.try_get_component_mut(entity)
     }
 
+    /// Gets a copy of a component on an entity, or the default value if the entity does not have it
+    pub fn get_component_or_default<T: Component + Send + Sync + Default + Clone>(
+        &self,
+        entity: Entity,
+    ) -> T {
+        self.entities_and_components
+            .get_component_or_default(entity)
+    }
+
     /// Gets a tuple of references to components on an entity
     /// If the component does not exist on the entity, it will panic
     pub fn get_components<'a, T: ComponentsRef<'a> + Send + Sync + 'static>(
@@ -632,18 +1947,116 @@ impl<'b> EntitiesAndComponentsThreadSafe<'b> {
     }
 
     /// Removes a component from an entity
-    pub fn remove_component_from<T: Component + Send + Sync>(&mut self, entity: Entity) {
+    /// Returns true if the entity had the component
+    pub fn remove_component_from<T: Component + Send + Sync>(&mut self, entity: Entity) -> bool {
         self.entities_and_components
             .remove_component_from::<T>(entity)
     }
 
-    /// returns an iterator over all entities with a certain component
+    /// returns an iterator over all entities with a certain component, except disabled ones
     pub fn get_entities_with_component<T: Component + Send + Sync>(
         &self,
+    ) -> impl Iterator<Item = &Entity> + '_ {
+        self.entities_and_components
+            .get_entities_with_component::<T>()
+    }
+
+    /// returns an iterator over all entities with a certain component in the reverse order of get_entities_with_component,
+    /// except disabled ones
+    pub fn entities_with_component_rev<T: Component + Send + Sync>(
+        &self,
+    ) -> impl Iterator<Item = Entity> {
+        self.entities_and_components
+            .entities_with_component_rev::<T>()
+    }
+
+    /// returns an iterator over all entities with a certain component, including disabled ones
+    pub fn get_entities_with_component_include_disabled<T: Component + Send + Sync>(
+        &self,
     ) -> std::iter::Flatten<std::option::IntoIter<slotmap::secondary::Values<'_, DefaultKey, Entity>>>
     {
         self.entities_and_components
+            .get_entities_with_component_include_disabled::<T>()
+    }
+
+    /// Groups the entities with a certain component by a key taken from the component, except disabled ones
+    pub fn group_by<T: Component + Send + Sync, K: Hash + Eq>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> HashMap<K, Vec<Entity>> {
+        self.entities_and_components.group_by(key)
+    }
+
+    /// returns an iterator over every pair of entities whose T has the same key, except disabled ones
+    pub fn iter_related_pairs<T: Component + Send + Sync, K: Hash + Eq>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> impl Iterator<Item = (Entity, Entity)> {
+        self.entities_and_components.iter_related_pairs(key)
+    }
+
+    /// Calls f for every entity with the component T in parallel, except disabled ones, with the component mutably
+    /// If T has a custom storage f is called on one entity after another
+    pub fn par_for_each_mut<T: Component + Send + Sync>(
+        &mut self,
+        f: impl Fn(Entity, &mut T) + Sync,
+    ) {
+        self.entities_and_components.par_for_each_mut(f);
+    }
+
+    /// Groups the entities with a certain component by a key taken from the component, like group_by,
+    /// but the keys are computed on every thread, for example to build a broadphase grid in prestep
+    /// The result is exactly the same as group_by, however the work was split between the threads
+    pub fn par_partition<T: Component + Send + Sync, K: Hash + Eq + Send>(
+        &self,
+        key: impl Fn(&T) -> K + Sync,
+    ) -> HashMap<K, Vec<Entity>> {
+        self.entities_and_components.record_read::<T>();
+        let entities = self
             .get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+
+        let partition_chunk = |chunk: &[Entity]| {
+            // only the thread safe wrapper can be shared between threads, so it is captured instead of its fields
+            let thread_safe: &Self = self;
+            let entities_and_components = &*thread_safe.entities_and_components;
+            let mut groups: HashMap<K, Vec<Entity>> = HashMap::new();
+            for entity in chunk {
+                if let Some(component) = get_stored::<T>(
+                    &entities_and_components.storages,
+                    &entities_and_components.components[entity.entity_id],
+                    *entity,
+                ) {
+                    groups.entry(key(component)).or_default().push(*entity);
+                }
+            }
+            groups
+        };
+        let chunk_size = entities
+            .len()
+            .div_ceil(rayon::current_num_threads())
+            .max(64);
+        #[cfg(not(feature = "single-threaded"))]
+        let chunks = entities
+            .par_chunks(chunk_size)
+            .map(partition_chunk)
+            .collect::<Vec<HashMap<K, Vec<Entity>>>>();
+        #[cfg(feature = "single-threaded")]
+        let chunks = entities
+            .chunks(chunk_size)
+            .map(partition_chunk)
+            .collect::<Vec<HashMap<K, Vec<Entity>>>>();
+
+        // the chunks are merged in order, so every group keeps the order of get_entities_with_component
+        let mut chunks = chunks.into_iter();
+        let mut groups = chunks.next().unwrap_or_default();
+        for chunk in chunks {
+            for (key, entities) in chunk {
+                groups.entry(key).or_default().extend(entities);
+            }
+        }
+        groups
     }
 
     /// gets the number of entities with a certain component
@@ -652,6 +2065,11 @@ impl<'b> EntitiesAndComponentsThreadSafe<'b> {
             .get_entity_count_with_component::<T>()
     }
 
+    /// Checks if any entity has a certain component, except disabled ones
+    pub fn any_with_component<T: Component + Send + Sync>(&self) -> bool {
+        self.entities_and_components.any_with_component::<T>()
+    }
+
     /// gets the nth entity with a certain component
     /// O(n) use get_entities_with_component if you need to iterate over all entities with a certain component
     pub fn get_entity_with_component<T: Component + Send + Sync>(
@@ -662,6 +2080,40 @@ impl<'b> EntitiesAndComponentsThreadSafe<'b> {
             .get_entity_with_component::<T>(index)
     }
 
+    /// returns an iterator over all entities with a certain component where the predicate returns true
+    pub fn query_where<'a, T: Component + Send + Sync>(
+        &'a self,
+        predicate: impl Fn(Entity, &T) -> bool + 'a,
+    ) -> impl Iterator<Item = (Entity, &'a T)> + 'a {
+        self.entities_and_components.query_where::<T>(predicate)
+    }
+
+    /// counts the entities with a certain component where the predicate returns true, without allocating
+    pub fn count_matching<T: Component + Send + Sync>(
+        &self,
+        predicate: impl Fn(&T) -> bool,
+    ) -> usize {
+        self.entities_and_components.count_matching::<T>(predicate)
+    }
+
+    /// gets the entity with a certain component that has the smallest key
+    /// keys that can't be compared (like NaN) are skipped
+    pub fn min_by_component<T: Component + Send + Sync, K: PartialOrd>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> Option<(Entity, &T)> {
+        self.entities_and_components.min_by_component::<T, K>(key)
+    }
+
+    /// gets the entity with a certain component that has the largest key
+    /// keys that can't be compared (like NaN) are skipped
+    pub fn max_by_component<T: Component + Send + Sync, K: PartialOrd>(
+        &self,
+        key: impl Fn(&T) -> K,
+    ) -> Option<(Entity, &T)> {
+        self.entities_and_components.max_by_component::<T, K>(key)
+    }
+
     /// Gets a resource from the game engine
     pub fn get_resource<T: Resource + Send + Sync>(&self) -> Option<&T> {
         self.entities_and_components.get_resource::<T>()
@@ -740,15 +2192,16 @@ pub struct SingleMutEntity<'a> {
 impl<'a> SingleMutEntity<'a> {
     /// Gets a reference to a component on an entity
     pub fn get_component<T: Component + Send + Sync>(&self) -> &T {
-        self.entities_and_components
-            .try_get_component::<T>(self.entity)
-            .unwrap_or_else(|| {
-                panic!(
-                    "Component of type {type:?} does not exist on entity {entity:?}",
-                    type = std::any::type_name::<T>(),
-                    entity = self.entity
-                );
-            })
+        let (component,) = self
+            .entities_and_components
+            .try_get_components::<(T,)>(self.entity);
+        component.unwrap_or_else(|| {
+            panic!(
+                "Component of type {type:?} does not exist on entity {entity:?}",
+                type = std::any::type_name::<T>(),
+                entity = self.entity
+            );
+        })
     }
 
     /// Gets a reference to a resource
@@ -764,6 +2217,7 @@ impl<'a> SingleMutEntity<'a> {
     }
 
     /// Gets a mutable reference to a component on an entity
+    /// panics if T has a custom storage, use try_get_components for those
     pub fn try_get_component<T: Component + Send + Sync>(&self) -> Option<&Box<T>> {
         self.entities_and_components
             .try_get_component::<T>(self.entity)
@@ -771,23 +2225,31 @@ impl<'a> SingleMutEntity<'a> {
 
     /// Gets a tuple of references to components on an entity
     pub fn get_component_mut<T: Component + Send + Sync>(&mut self) -> &mut T {
-        self.entities_and_components
-            .try_get_component_mut::<T>(self.entity)
-            .unwrap_or_else(|| {
-                panic!(
-                    "Component of type {type:?} does not exist on entity {entity:?}",
-                    type = std::any::type_name::<T>(),
-                    entity = self.entity
-                );
-            })
+        let (component,) = self
+            .entities_and_components
+            .try_get_components_mut::<(T,)>(self.entity);
+        component.unwrap_or_else(|| {
+            panic!(
+                "Component of type {type:?} does not exist on entity {entity:?}",
+                type = std::any::type_name::<T>(),
+                entity = self.entity
+            );
+        })
     }
 
     /// Gets a mutable reference to a component on an entity
+    /// panics if T has a custom storage, use try_get_components_mut for those
     pub fn try_get_component_mut<T: Component + Send + Sync>(&mut self) -> Option<&mut Box<T>> {
         self.entities_and_components
             .try_get_component_mut::<T>(self.entity)
     }
 
+    /// Gets a copy of a component on this entity, or the default value if the entity does not have it
+    pub fn get_component_or_default<T: Component + Send + Sync + Default + Clone>(&self) -> T {
+        self.entities_and_components
+            .get_component_or_default::<T>(self.entity)
+    }
+
     /// Gets a tuple of references to components on an entity
     pub fn get_components<'b, T: ComponentsRef<'b> + Send + Sync + 'static>(&'b self) -> T::Result {
         <T>::get_components(self.entities_and_components, self.entity)
@@ -818,10 +2280,10 @@ impl<'a> SingleMutEntity<'a> {
     }
 
     /// Removes a component from an entity
-    /// If the component does not exist on the entity, it will do nothing
-    pub fn remove_component<T: Component + Send + Sync>(&mut self) {
+    /// Returns true if the entity had the component, if not it will do nothing and return false
+    pub fn remove_component<T: Component + Send + Sync>(&mut self) -> bool {
         self.entities_and_components
-            .remove_component_from::<T>(self.entity);
+            .remove_component_from::<T>(self.entity)
     }
 
     /// Adds a component to an entity
@@ -831,12 +2293,20 @@ impl<'a> SingleMutEntity<'a> {
             .add_component_to(self.entity, component);
     }
 
+    /// Adds a component to the entity and returns self, so calls can be chained
+    /// If the component already exists on the entity, it will be overwritten
+    pub fn insert<T: Component + Send + Sync>(&mut self, component: T) -> &mut Self {
+        self.add_component(component);
+        self
+    }
+
     /// Checks if an entity has a certain component
     /// Returns true if the entity has the component, false otherwise
     pub fn has_component<T: Component + Send + Sync>(&self) -> bool {
-        self.entities_and_components
-            .try_get_component::<T>(self.entity)
-            .is_some()
+        let (component,) = self
+            .entities_and_components
+            .try_get_components::<(T,)>(self.entity);
+        component.is_some()
     }
 
     /// Removes the entity from the game engine
@@ -850,13 +2320,20 @@ impl<'a> SingleMutEntity<'a> {
     pub fn get_entity(&self) -> Entity {
         self.entity
     }
+
+    /// Gets the entity that this struct is referencing, the same as get_entity
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
 }
 
+#[cfg(not(feature = "single-threaded"))]
 #[derive(Clone)]
 struct EntitiesAndComponentPtr {
     entities_and_components: *mut EntitiesAndComponents,
 }
 
+#[cfg(not(feature = "single-threaded"))]
 impl EntitiesAndComponentPtr {
     // turns the pointer into a mutable reference
     pub(crate) unsafe fn as_mut(&mut self) -> &mut EntitiesAndComponents {
@@ -865,7 +2342,9 @@ impl EntitiesAndComponentPtr {
 }
 
 // this is not really safe it's safe by not making it public and being careful with it
+#[cfg(not(feature = "single-threaded"))]
 unsafe impl Send for EntitiesAndComponentPtr {}
+#[cfg(not(feature = "single-threaded"))]
 unsafe impl Sync for EntitiesAndComponentPtr {}
 
 /*
@@ -884,12 +2363,35 @@ pub struct SystemHandle {
     system_id: DefaultKey,
 }
 
+/// Information about a system in the world, returned by World::system_info
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemInfo {
+    /// The name the system was added with, or the name of its type if it was added without one
+    pub name: String,
+    /// Whether the system implements prestep
+    pub implements_prestep: bool,
+    /// The tier the system's prestep runs in
+    pub prestep_tier: u8,
+    /// Whether the system implements single_entity_step
+    pub implements_single_entity_step: bool,
+    /// Whether the system only reads the world in the run phase
+    pub is_read_only: bool,
+    /// Whether the system keeps running while the world is paused
+    pub runs_while_paused: bool,
+}
+
 /// This struct is the main struct for the game engine
 pub struct World {
     /// This struct holds all the entities and components in the game engine
     pub entities_and_components: EntitiesAndComponents,
     //systems: Vec<Box<dyn System + Sync + Send>>,
     systems: SlotMap<DefaultKey, Box<dyn SystemWrapper + Send + Sync>>,
+    // the names of the systems added with add_system_named
+    system_names: SecondaryMap<DefaultKey, String>,
+    // the tick every system's run last finished at, see query_filtered
+    system_last_runs: SecondaryMap<DefaultKey, u64>,
+    // set up the entities that got a component, see add_initializer
+    initializers: Vec<Initializer>,
 }
 
 impl World {
@@ -898,6 +2400,9 @@ impl World {
         World {
             entities_and_components: EntitiesAndComponents::new(),
             systems: SlotMap::with_capacity(10),
+            system_names: SecondaryMap::new(),
+            system_last_runs: SecondaryMap::new(),
+            initializers: Vec::new(),
         }
     }
 
@@ -908,9 +2413,53 @@ impl World {
         }
     }
 
+    /// Adds a system to the world with a name, which is shown in system_info instead of the name of its type
+    pub fn add_system_named<T: System + Send + Sync + 'static>(
+        &mut self,
+        name: &str,
+        system: T,
+    ) -> SystemHandle {
+        let handle = self.add_system(system);
+        self.system_names.insert(handle.system_id, name.to_string());
+        handle
+    }
+
+    /// Gets the name of a system, which is the name it was added with or the name of its type
+    /// returns None if the system has been removed
+    pub fn system_name(&self, system: &SystemHandle) -> Option<&str> {
+        let wrapper = self.systems.get(system.system_id)?;
+        Some(
+            self.system_names
+                .get(system.system_id)
+                .map(|name| name.as_str())
+                .unwrap_or_else(|| wrapper.type_name()),
+        )
+    }
+
+    /// Gets information about every system in the world, in the order they were added
+    pub fn system_info(&self) -> Vec<SystemInfo> {
+        self.systems
+            .iter()
+            .map(|(system_id, system)| SystemInfo {
+                name: self
+                    .system_names
+                    .get(system_id)
+                    .cloned()
+                    .unwrap_or_else(|| system.type_name().to_string()),
+                implements_prestep: system.implements_prestep(),
+                prestep_tier: system.prestep_tier(),
+                implements_single_entity_step: system.implements_single_entity_step(),
+                is_read_only: system.is_read_only(),
+                runs_while_paused: system.runs_while_paused(),
+            })
+            .collect()
+    }
+
     /// Removes a system from the world based on the SystemHandle
     pub fn remove_system(&mut self, system: SystemHandle) {
         self.systems.remove(system.system_id);
+        self.system_names.remove(system.system_id);
+        self.system_last_runs.remove(system.system_id);
     }
 
     /// Removes all systems of a certain type from the world
@@ -925,40 +2474,109 @@ impl World {
 
         for key in systems_to_remove {
             self.systems.remove(key);
+            self.system_names.remove(key);
+            self.system_last_runs.remove(key);
         }
     }
 
+    /// Gets the number of times run has been called
+    pub fn frame(&self) -> u64 {
+        self.entities_and_components.current_frame()
+    }
+
+    /// Pauses or resumes the world, while paused run skips every system and resource update
+    /// except those whose runs_while_paused returns true, for example the input handling of a pause menu
+    pub fn set_paused(&mut self, paused: bool) {
+        self.entities_and_components.set_paused(paused);
+    }
+
+    /// Checks if the world is paused, see set_paused
+    pub fn is_paused(&self) -> bool {
+        self.entities_and_components.is_paused()
+    }
+
     /// Removes all systems from the world
     pub fn remove_all_systems(&mut self) {
         self.systems.clear();
+        self.system_names.clear();
+        self.system_last_runs.clear();
+    }
+
+    /// Runs f on the world right away, outside of run, for one off setup like spawning the first entities
+    /// Commands queued by f are flushed afterwards, so the world is fully up to date when this returns
+    pub fn run_once(&mut self, f: impl FnOnce(&mut EntitiesAndComponents)) {
+        f(&mut self.entities_and_components);
+        self.entities_and_components.flush();
     }
 
     /// Runs the world
     /// This will run all the systems in the world and update all the resources
+    /// Registered double buffers are swapped first, so what was written last frame can be read this frame
+    /// Then resources get their early updates, then their updates with the world,
+    /// then the initializers set up entities that got new components, then the systems run
+    /// and the resources get their late updates at the very end of the frame
+    /// While the world is paused only the systems and resources that run while paused are run, see set_paused
+    /// Mailboxes are cleared after the single_entity_step phase
+    /// Deferred commands are flushed after the resources update,
+    /// after the single_entity_step phase and after the run phase
+    /// Commands queued by systems running in parallel are applied in the order the systems were added, see Commands
     pub fn run(&mut self) {
-        for resource in self.entities_and_components.resources.values_mut() {
-            resource.update();
+        self.entities_and_components.frame += 1;
+        // pausing or resuming during the frame takes effect next frame
+        let paused = self.entities_and_components.paused;
+        #[cfg(feature = "access-stats")]
+        self.entities_and_components.reset_access_stats();
+        if !paused {
+            self.entities_and_components.swap_double_buffers();
+        }
+
+        self.entities_and_components.early_update_resources(paused);
+        self.entities_and_components
+            .update_resources_with_world(paused);
+
+        self.entities_and_components.flush();
+
+        if !paused && !self.initializers.is_empty() {
+            self.run_initializers();
+            self.entities_and_components.flush();
         }
 
         if self.systems.is_empty() {
+            self.entities_and_components.late_update_resources(paused);
             return;
         }
 
         // run the prestep function for each systems in parallel
         {
-            let thread_safe_entities_and_components =
-                EntitiesAndComponentsThreadSafe::new(&mut self.entities_and_components);
-
-            // check which systems implement the prestep function and collect mutable references to them
+            // check which systems implement the prestep function and collect mutable references to them,
+            // along with their position so their commands can be applied in the order the systems were added
             let mut systems_with_prestep = self
                 .systems
                 .values_mut()
-                .filter(|system| system.implements_prestep())
-                .collect::<Vec<&mut Box<dyn SystemWrapper + Sync + Send>>>();
-
-            systems_with_prestep
-                .par_iter_mut()
-                .for_each(|system| system.prestep(&thread_safe_entities_and_components));
+                .enumerate()
+                .filter(|(_, system)| system.implements_prestep())
+                .filter(|(_, system)| !paused || system.runs_while_paused())
+                .collect::<Vec<(usize, &mut Box<dyn SystemWrapper + Sync + Send>)>>();
+
+            // the sort is stable, so systems in the same tier keep the order they were added in
+            systems_with_prestep.sort_by_key(|(_, system)| system.prestep_tier());
+            let mut queues = Vec::with_capacity(systems_with_prestep.len());
+            for tier in systems_with_prestep.chunk_by_mut(|(_, system), (_, next_system)| {
+                system.prestep_tier() == next_system.prestep_tier()
+            }) {
+                let tier_queues = run_with_own_commands(
+                    &mut self.entities_and_components,
+                    tier,
+                    |(_, system), thread_safe_entities_and_components| {
+                        system.prestep(thread_safe_entities_and_components)
+                    },
+                );
+                queues.extend(tier.iter().map(|(order, _)| *order).zip(tier_queues));
+            }
+            queues.sort_by_key(|(order, _)| *order);
+            for (_, mut commands) in queues {
+                self.entities_and_components.commands.append(&mut commands);
+            }
         }
 
         {
@@ -967,9 +2585,34 @@ impl World {
                 .systems
                 .values()
                 .filter(|system| system.implements_single_entity_step())
+                .filter(|system| !paused || system.runs_while_paused())
                 .collect::<Vec<&Box<dyn SystemWrapper + Sync + Send>>>();
 
-            if !systems_with_single_entity_step.is_empty() {
+            // custom storages hand out &mut from one shared storage, which would alias between threads,
+            // so with any custom storage set the entities are stepped one after another
+            let step_serially = cfg!(feature = "single-threaded")
+                || !self.entities_and_components.storages.is_empty();
+
+            if step_serially {
+                for entity in self.entities_and_components.get_entities() {
+                    for system in systems_with_single_entity_step.as_slice() {
+                        if !self.entities_and_components.does_entity_exist(entity) {
+                            // the entity was removed in the single entity step function of a previous system
+                            break;
+                        }
+
+                        let mut single_entity = SingleMutEntity {
+                            entity,
+                            entities_and_components: &mut self.entities_and_components,
+                        };
+
+                        system.single_entity_step(&mut single_entity);
+                    }
+                }
+            }
+
+            #[cfg(not(feature = "single-threaded"))]
+            if !step_serially && !systems_with_single_entity_step.is_empty() {
                 let entities_and_components_ptr = &mut self.entities_and_components as *mut _;
                 let entities_and_components_ptr = EntitiesAndComponentPtr {
                     entities_and_components: entities_and_components_ptr,
@@ -1018,9 +2661,44 @@ impl World {
             }
         }
 
-        for system in &mut self.systems.values_mut() {
-            system.run(&mut self.entities_and_components);
+        // messages have been delivered to single_entity_step, anything sent from here on is for the next frame
+        self.entities_and_components.clear_mailboxes();
+        self.entities_and_components.flush();
+
+        // consecutive read only systems run in parallel, the rest run one at a time in order
+        let mut systems = self
+            .systems
+            .iter_mut()
+            .filter(|(_, system)| !paused || system.runs_while_paused())
+            .collect::<Vec<(DefaultKey, &mut Box<dyn SystemWrapper + Sync + Send>)>>();
+        for group in systems.chunk_by_mut(|(_, system), (_, next_system)| {
+            system.is_read_only() && next_system.is_read_only()
+        }) {
+            if group[0].1.is_read_only() {
+                let queues = run_with_own_commands(
+                    &mut self.entities_and_components,
+                    group,
+                    |(_, system), thread_safe_entities_and_components| {
+                        system.run_readonly(thread_safe_entities_and_components)
+                    },
+                );
+                for mut commands in queues {
+                    self.entities_and_components.commands.append(&mut commands);
+                }
+            } else {
+                for (system_id, system) in group {
+                    self.entities_and_components.system_last_run =
+                        self.system_last_runs.get(*system_id).copied().unwrap_or(0);
+                    system.run(&mut self.entities_and_components);
+                    self.system_last_runs
+                        .insert(*system_id, self.entities_and_components.current_tick());
+                }
+                self.entities_and_components.system_last_run = 0;
+            }
         }
+
+        self.entities_and_components.flush();
+        self.entities_and_components.late_update_resources(paused);
     }
 }
 
@@ -1036,6 +2714,12 @@ pub trait Component: 'static {}
 
 impl<T: 'static> Component for T {}
 
+/// Holds multiple components of the same type on a single entity, for example stacking buffs
+/// Use add_component_indexed and get_components_indexed to access it
+pub struct ComponentVec<T> {
+    components: Vec<T>,
+}
+
 /// Systems access and change components on objects
 /// Be careful to implement get_allow_entity_based_multithreading as true if you want to use the single_entity_step function
 /// If you don't it will still work but, it will be slower (in most cases)
@@ -1048,7 +2732,15 @@ pub trait System: 'static + Sized {
     fn implements_prestep(&self) -> bool {
         false
     }
+    /// Presteps run in tiers, lowest first, every prestep in a tier runs in parallel and a tier only starts once the one before it is done
+    /// A low tier prestep can compute something expensive once and share it through a resource (with interior mutability, like a Mutex)
+    /// so the presteps in the tiers after it can read it instead of computing it again
+    /// Defaults to 0
+    fn prestep_tier(&self) -> u8 {
+        0
+    }
     /// If you implement this function, it will be called for each entity in parallel, but make sure to implement get_allow_single_entity_step as true
+    /// If any component type has a custom storage set with set_storage, it is called on one entity after another instead
     fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {}
     /// Should just return true or false based on whether or not the system implements the single_entity_step function
     fn implements_single_entity_step(&self) -> bool {
@@ -1056,6 +2748,23 @@ pub trait System: 'static + Sized {
     }
     /// This function is called after the single_entity_step function is called for all entities
     fn run(&mut self, engine: &mut EntitiesAndComponents) {}
+    /// Should return true if the system only reads the world in the run phase
+    /// Read only systems have run_readonly called instead of run, and consecutive read only systems run in parallel
+    fn is_read_only(&self) -> bool {
+        false
+    }
+    /// This function is called instead of run if is_read_only returns true
+    /// Any results have to be stored with interior mutability, like a Mutex, since the system is only borrowed immutably
+    fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {}
+    /// Should return true if the system keeps running while the world is paused, see World::set_paused
+    fn runs_while_paused(&self) -> bool {
+        false
+    }
+    /// Declares the component types the system reads and writes, for World::access_report
+    /// Defaults to nothing
+    fn access(&self) -> SystemAccess {
+        SystemAccess::new()
+    }
 
     /// This function is used to downcast the system to an Any trait object
     /// Should be automatically implemented
@@ -1073,9 +2782,15 @@ pub trait System: 'static + Sized {
 trait SystemWrapper {
     fn prestep(&mut self, engine: &EntitiesAndComponentsThreadSafe);
     fn implements_prestep(&self) -> bool;
+    fn prestep_tier(&self) -> u8;
     fn single_entity_step(&self, single_entity: &mut SingleMutEntity);
     fn implements_single_entity_step(&self) -> bool;
     fn run(&mut self, engine: &mut EntitiesAndComponents);
+    fn is_read_only(&self) -> bool;
+    fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe);
+    fn runs_while_paused(&self) -> bool;
+    fn access(&self) -> SystemAccess;
+    fn type_name(&self) -> &'static str;
     fn as_any(&self) -> &dyn std::any::Any;
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
 }
@@ -1087,6 +2802,9 @@ impl<T: System> SystemWrapper for T {
     fn implements_prestep(&self) -> bool {
         System::implements_prestep(self)
     }
+    fn prestep_tier(&self) -> u8 {
+        System::prestep_tier(self)
+    }
     fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
         System::single_entity_step(self, single_entity);
     }
@@ -1096,6 +2814,21 @@ impl<T: System> SystemWrapper for T {
     fn run(&mut self, engine: &mut EntitiesAndComponents) {
         System::run(self, engine);
     }
+    fn is_read_only(&self) -> bool {
+        System::is_read_only(self)
+    }
+    fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+        System::run_readonly(self, engine);
+    }
+    fn runs_while_paused(&self) -> bool {
+        System::runs_while_paused(self)
+    }
+    fn access(&self) -> SystemAccess {
+        System::access(self)
+    }
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
+    }
     fn as_any(&self) -> &dyn std::any::Any {
         System::as_any(self)
     }
@@ -1144,155 +2877,1652 @@ mod tests {
         }
     }
 
-    struct ParallelMovementSystem {}
+    struct ParallelMovementSystem {}
+
+    impl System for ParallelMovementSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            let (position, velocity) = single_entity.get_components_mut::<(Position, Velocity)>();
+
+            position.x += velocity.x;
+            position.y += velocity.y;
+        }
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_components_mut() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+
+        engine.add_system(MovementSystem {});
+
+        for _ in 0..5 {
+            engine.run();
+        }
+    }
+
+    #[test]
+    fn test_try_get_components() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+
+        let (position, velocity) =
+            <(Position, Velocity)>::try_get_components(entities_and_components, entity);
+
+        assert_eq!(position.unwrap().x, 0.0);
+        assert_eq!(position.unwrap().y, 0.0);
+        assert_eq!(velocity.unwrap().x, 1.0);
+        assert_eq!(velocity.unwrap().y, 1.0);
+    }
+
+    #[test]
+    fn test_overriding_components() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity, Position { x: 6.0, y: 1.0 });
+
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 6.0);
+        assert_eq!(position.y, 1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is in the tuple more than once")]
+    fn test_spawning_duplicate_components_panics() {
+        let mut engine = World::new();
+        engine.entities_and_components.add_entity_with((
+            Position { x: 0.0, y: 0.0 },
+            Velocity { x: 1.0, y: 1.0 },
+            Position { x: 6.0, y: 1.0 },
+        ));
+    }
+
+    #[test]
+    fn test_multiple_entities() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+        let entity_2 = entities_and_components.add_entity();
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+
+        entities_and_components.add_component_to(entity_2, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity_2, Velocity { x: 1.0, y: 1.0 });
+
+        // this should compile but, currently you can't borrow from two different entities mutably at the same time
+        let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+
+        println!("Position: {}, {}", position.x, position.y);
+    }
+
+    #[test]
+    fn test_add_entity_with_components() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+
+        let (position, velocity) =
+            entities_and_components.get_components::<(Position, Velocity)>(entity);
+
+        assert_eq!(position.x, 0.0);
+        assert_eq!(position.y, 0.0);
+        assert_eq!(velocity.x, 1.0);
+        assert_eq!(velocity.y, 1.0);
+    }
+
+    #[test]
+    fn test_entity_removal() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+
+        let (position, velocity) =
+            entities_and_components.get_components::<(Position, Velocity)>(entity);
+
+        assert_eq!(position.x, 0.0);
+        assert_eq!(position.y, 0.0);
+        assert_eq!(velocity.x, 1.0);
+        assert_eq!(velocity.y, 1.0);
+
+        entities_and_components.remove_entity(entity);
+
+        assert_eq!(entities_and_components.get_entity_count(), 0);
+
+        let entity = entities_and_components.add_entity();
+
+        // make sure the new entity doesn't have the old entity's components
+        let (position, velocity) =
+            entities_and_components.try_get_components::<(Position, Velocity)>(entity);
+
+        assert_eq!(position, None);
+        assert_eq!(velocity, None);
+    }
+
+    #[test]
+    fn test_get_entities_with_component() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity();
+        let entity_2 = entities_and_components.add_entity();
+
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+
+        entities_and_components.add_component_to(entity_2, Position { x: 0.0, y: 0.0 });
+        entities_and_components.add_component_to(entity_2, Velocity { x: 1.0, y: 1.0 });
+
+        let entities = entities_and_components.get_entities_with_component::<Position>();
+
+        assert_eq!(entities.count(), 2);
+    }
+
+    system! {
+        fn macro_movement(position: &mut Position, velocity: &Velocity) {
+            position.x += velocity.x;
+            position.y += velocity.y;
+        }
+    }
+
+    #[test]
+    fn test_prestep_tiers() {
+        use std::sync::Mutex;
+
+        struct PositionSum(Mutex<Option<f32>>);
+        impl Resource for PositionSum {}
+
+        struct SumSeen(f32);
+        impl Resource for SumSeen {}
+
+        // tier 0, computes the sum once for every prestep after it
+        struct SumSystem {}
+
+        impl System for SumSystem {
+            fn prestep(&mut self, engine: &EntitiesAndComponentsThreadSafe) {
+                let sum = engine
+                    .get_entities_with_component::<Position>()
+                    .map(|entity| engine.get_components::<(Position,)>(*entity).0.x)
+                    .sum();
+                *engine
+                    .get_resource::<PositionSum>()
+                    .unwrap()
+                    .0
+                    .lock()
+                    .unwrap() = Some(sum);
+            }
+
+            fn implements_prestep(&self) -> bool {
+                true
+            }
+        }
+
+        // tier 1, reads the sum computed by the tier 0 prestep
+        struct SumReaderSystem {
+            sum: Option<f32>,
+        }
+
+        impl System for SumReaderSystem {
+            fn prestep(&mut self, engine: &EntitiesAndComponentsThreadSafe) {
+                self.sum = *engine
+                    .get_resource::<PositionSum>()
+                    .unwrap()
+                    .0
+                    .lock()
+                    .unwrap();
+            }
+
+            fn implements_prestep(&self) -> bool {
+                true
+            }
+
+            fn prestep_tier(&self) -> u8 {
+                1
+            }
+
+            fn run(&mut self, engine: &mut EntitiesAndComponents) {
+                engine.add_resource(SumSeen(self.sum.unwrap()));
+            }
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        entities_and_components.add_resource(PositionSum(Mutex::new(None)));
+        for i in 0..4 {
+            entities_and_components.add_entity_with((Position {
+                x: i as f32,
+                y: 0.0,
+            },));
+        }
+
+        // added first, so without tiers it could read the sum before it is computed
+        engine.add_system(SumReaderSystem { sum: None });
+        engine.add_system(SumSystem {});
+        engine.run();
+
+        let sum_seen = engine
+            .entities_and_components
+            .get_resource::<SumSeen>()
+            .unwrap();
+        assert_eq!(sum_seen.0, 6.0);
+    }
+
+    #[test]
+    fn test_read_only_systems() {
+        use std::sync::{Arc, Mutex};
+
+        struct SumSystem {
+            sum: Arc<Mutex<f32>>,
+        }
+
+        impl System for SumSystem {
+            fn is_read_only(&self) -> bool {
+                true
+            }
+
+            fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+                *self.sum.lock().unwrap() = engine
+                    .get_entities_with_component::<Position>()
+                    .map(|entity| engine.get_components::<(Position,)>(*entity).0.x)
+                    .sum();
+            }
+        }
+
+        struct CountSystem {
+            count: Arc<Mutex<usize>>,
+        }
+
+        impl System for CountSystem {
+            fn is_read_only(&self) -> bool {
+                true
+            }
+
+            fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+                *self.count.lock().unwrap() = engine.get_entity_count_with_component::<Position>();
+            }
+        }
+
+        struct MoveSystem {}
+
+        impl System for MoveSystem {
+            fn run(&mut self, engine: &mut EntitiesAndComponents) {
+                for entity in engine.get_entities() {
+                    let (position,) = engine.get_components_mut::<(Position,)>(entity);
+                    position.x += 1.0;
+                }
+            }
+        }
+
+        let mut engine = World::new();
+        for i in 0..4 {
+            engine.entities_and_components.add_entity_with((Position {
+                x: i as f32,
+                y: 0.0,
+            },));
+        }
+
+        let sum_before = Arc::new(Mutex::new(0.0));
+        let count = Arc::new(Mutex::new(0));
+        let sum_after = Arc::new(Mutex::new(0.0));
+        engine.add_system(SumSystem {
+            sum: sum_before.clone(),
+        });
+        engine.add_system(CountSystem {
+            count: count.clone(),
+        });
+        engine.add_system(MoveSystem {});
+        engine.add_system(SumSystem {
+            sum: sum_after.clone(),
+        });
+        engine.run();
+
+        assert_eq!(*sum_before.lock().unwrap(), 6.0);
+        assert_eq!(*count.lock().unwrap(), 4);
+        // the mutating system runs between the two groups of read only systems
+        assert_eq!(*sum_after.lock().unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_system_names() {
+        struct PhysicsSystem {}
+
+        impl System for PhysicsSystem {}
+
+        struct RenderSystem {}
+
+        impl System for RenderSystem {
+            fn is_read_only(&self) -> bool {
+                true
+            }
+        }
+
+        let mut engine = World::new();
+        let physics = engine.add_system_named("PhysicsStep", PhysicsSystem {});
+        let render = engine.add_system(RenderSystem {});
+
+        assert_eq!(engine.system_name(&physics), Some("PhysicsStep"));
+        assert!(engine
+            .system_name(&render)
+            .unwrap()
+            .ends_with("RenderSystem"));
+
+        let info = engine.system_info();
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].name, "PhysicsStep");
+        assert!(!info[0].is_read_only);
+        assert!(info[1].name.ends_with("RenderSystem"));
+        assert!(info[1].is_read_only);
+
+        engine.remove_system(physics);
+        assert_eq!(engine.system_info().len(), 1);
+    }
+
+    #[test]
+    fn test_get_component_or_default() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Tint(u8, u8, u8);
+
+        impl Default for Tint {
+            fn default() -> Self {
+                Tint(255, 255, 255)
+            }
+        }
+
+        struct TintSystem {
+            tints: std::sync::Arc<std::sync::Mutex<Vec<Tint>>>,
+        }
+
+        impl System for TintSystem {
+            fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+                let tint = single_entity.get_component_or_default::<Tint>();
+                self.tints.lock().unwrap().push(tint);
+            }
+
+            fn implements_single_entity_step(&self) -> bool {
+                true
+            }
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let red = entities_and_components.add_entity_with((Tint(255, 0, 0),));
+        let untinted = entities_and_components.add_entity();
+
+        assert_eq!(
+            entities_and_components.get_component_or_default::<Tint>(red),
+            Tint(255, 0, 0)
+        );
+        assert_eq!(
+            entities_and_components.get_component_or_default::<Tint>(untinted),
+            Tint(255, 255, 255)
+        );
+
+        entities_and_components.remove_entity(untinted);
+        // no panic even though the entity is gone
+        assert_eq!(
+            entities_and_components.get_component_or_default::<Tint>(untinted),
+            Tint::default()
+        );
+
+        entities_and_components.add_entity();
+        let tints = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        engine.add_system(TintSystem {
+            tints: tints.clone(),
+        });
+        engine.run();
+        let mut tints = tints.lock().unwrap().clone();
+        tints.sort_by_key(|tint| tint.1);
+        assert_eq!(tints, vec![Tint(255, 0, 0), Tint(255, 255, 255)]);
+    }
+
+    #[test]
+    fn test_pause() {
+        struct MovementSystem {}
+
+        impl System for MovementSystem {
+            fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+                let (position,) = single_entity.get_components_mut::<(Position,)>();
+                position.x += 1.0;
+            }
+
+            fn implements_single_entity_step(&self) -> bool {
+                true
+            }
+        }
+
+        struct MenuSystem {
+            frames: std::sync::Arc<std::sync::Mutex<u32>>,
+        }
+
+        impl System for MenuSystem {
+            fn run(&mut self, engine: &mut EntitiesAndComponents) {
+                *self.frames.lock().unwrap() += 1;
+                // the menu closes itself after three frames
+                if *self.frames.lock().unwrap() == 3 {
+                    engine.set_paused(false);
+                }
+            }
+
+            fn runs_while_paused(&self) -> bool {
+                true
+            }
+        }
+
+        struct Clock {
+            ticks: u32,
+        }
+
+        impl Resource for Clock {
+            fn update(&mut self) {
+                self.ticks += 1;
+            }
+        }
+
+        let mut engine = World::new();
+        let entity = engine
+            .entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 },));
+        engine
+            .entities_and_components
+            .add_resource(Clock { ticks: 0 });
+        let frames = std::sync::Arc::new(std::sync::Mutex::new(0));
+        engine.add_system(MovementSystem {});
+        engine.add_system(MenuSystem {
+            frames: frames.clone(),
+        });
+
+        engine.run();
+        engine.set_paused(true);
+        assert!(engine.is_paused());
+        engine.run();
+        engine.run();
+
+        // the simulation stopped while the menu kept running
+        let (position,) = engine
+            .entities_and_components
+            .get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 1.0);
+        let clock = engine.entities_and_components.get_resource::<Clock>();
+        assert_eq!(clock.unwrap().ticks, 1);
+        assert_eq!(*frames.lock().unwrap(), 3);
+
+        // the menu resumed the world in the last frame
+        assert!(!engine.is_paused());
+        engine.run();
+        let (position,) = engine
+            .entities_and_components
+            .get_components::<(Position,)>(entity);
+        assert_eq!(position.x, 2.0);
+    }
+
+    #[test]
+    fn test_frame_counter() {
+        struct FrameSystem {
+            frames: std::sync::Arc<std::sync::Mutex<Vec<u64>>>,
+        }
+
+        impl System for FrameSystem {
+            fn run(&mut self, engine: &mut EntitiesAndComponents) {
+                self.frames.lock().unwrap().push(engine.current_frame());
+            }
+        }
+
+        let mut engine = World::new();
+        assert_eq!(engine.frame(), 0);
+        engine.run();
+        assert_eq!(engine.frame(), 1);
+
+        let frames = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        engine.add_system(FrameSystem {
+            frames: frames.clone(),
+        });
+        for _ in 0..4 {
+            engine.run();
+        }
+        assert_eq!(engine.frame(), 5);
+        assert_eq!(*frames.lock().unwrap(), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_iter_entities() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = (0..10)
+            .map(|_| entities_and_components.add_entity())
+            .collect::<Vec<Entity>>();
+        entities_and_components.remove_entity(entities[3]);
+
+        assert_eq!(
+            entities_and_components
+                .iter_entities()
+                .collect::<Vec<Entity>>(),
+            entities_and_components.get_entities()
+        );
+        assert!(entities_and_components
+            .iter_entities()
+            .all(|entity| entity != entities[3]));
+    }
+
+    #[test]
+    fn test_run_results_match_with_and_without_threads() {
+        use std::sync::{Arc, Mutex};
+
+        // records the sum of every x before the entities move, in prestep and again in a read only run
+        struct SumSystem {
+            sums: Arc<Mutex<Vec<f32>>>,
+        }
+
+        impl System for SumSystem {
+            fn prestep(&mut self, engine: &EntitiesAndComponentsThreadSafe) {
+                let sum = engine
+                    .get_entities_with_component::<Position>()
+                    .map(|entity| engine.get_components::<(Position,)>(*entity).0.x)
+                    .sum::<f32>();
+                self.sums.lock().unwrap().push(sum);
+            }
+
+            fn implements_prestep(&self) -> bool {
+                true
+            }
+
+            fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+                let sum = engine
+                    .get_entities_with_component::<Position>()
+                    .map(|entity| engine.get_components::<(Position,)>(*entity).0.x)
+                    .sum::<f32>();
+                self.sums.lock().unwrap().push(sum);
+            }
+
+            fn is_read_only(&self) -> bool {
+                true
+            }
+        }
+
+        let mut engine = World::new();
+        let entities = (0..50)
+            .map(|i| {
+                engine.entities_and_components.add_entity_with((
+                    Position {
+                        x: i as f32,
+                        y: 0.0,
+                    },
+                    Velocity { x: 1.0, y: 2.0 },
+                ))
+            })
+            .collect::<Vec<Entity>>();
+        let sums = Arc::new(Mutex::new(Vec::new()));
+        engine.add_system(SumSystem { sums: sums.clone() });
+        engine.add_system(ParallelMovementSystem {});
+
+        for _ in 0..3 {
+            engine.run();
+        }
+
+        // every entity moved once per frame, whether single_entity_step ran in parallel or not
+        for (i, entity) in entities.into_iter().enumerate() {
+            let (position,) = engine
+                .entities_and_components
+                .get_components::<(Position,)>(entity);
+            assert_eq!(
+                *position,
+                Position {
+                    x: i as f32 + 3.0,
+                    y: 6.0
+                }
+            );
+        }
+        assert_eq!(
+            *sums.lock().unwrap(),
+            vec![1225.0, 1275.0, 1275.0, 1325.0, 1325.0, 1375.0]
+        );
+    }
+
+    // moves every position by a fixed velocity, with par_for_each_mut instead of single_entity_step
+    struct ParForEachMovementSystem {}
+
+    impl System for ParForEachMovementSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            engine.par_for_each_mut(|_, position: &mut Position| {
+                position.x += 1.0;
+                position.y += 2.0;
+            });
+        }
+    }
+
+    #[test]
+    fn test_par_for_each_mut() {
+        let mut engine = World::new();
+        let entities = (0..50)
+            .map(|i| {
+                engine.entities_and_components.add_entity_with((Position {
+                    x: i as f32,
+                    y: 0.0,
+                },))
+            })
+            .collect::<Vec<Entity>>();
+        engine.entities_and_components.disable_entity(entities[7]);
+        engine.add_system(ParForEachMovementSystem {});
+        let tick = engine.entities_and_components.current_tick();
+
+        for _ in 0..3 {
+            engine.run();
+        }
+
+        for (i, entity) in entities.iter().enumerate() {
+            let (position,) = engine
+                .entities_and_components
+                .get_components::<(Position,)>(*entity);
+            let expected = if i == 7 {
+                Position {
+                    x: i as f32,
+                    y: 0.0,
+                }
+            } else {
+                Position {
+                    x: i as f32 + 3.0,
+                    y: 6.0,
+                }
+            };
+            assert_eq!(*position, expected);
+        }
+        assert_eq!(
+            engine
+                .entities_and_components
+                .changed_since::<Position>(tick)
+                .count(),
+            49
+        );
+    }
+
+    #[test]
+    fn bench_par_for_each_mut() {
+        struct SingleComponentMovementSystem {}
+
+        impl System for SingleComponentMovementSystem {
+            fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+                let (position,) = single_entity.get_components_mut::<(Position,)>();
+                position.x += 1.0;
+                position.y += 2.0;
+            }
+
+            fn implements_single_entity_step(&self) -> bool {
+                true
+            }
+        }
+
+        let mut timings = Vec::new();
+        for par_for_each in [false, true] {
+            let mut engine = World::new();
+            for i in 0..100000 {
+                engine.entities_and_components.add_entity_with((Position {
+                    x: i as f32,
+                    y: 0.0,
+                },));
+            }
+            if par_for_each {
+                engine.add_system(ParForEachMovementSystem {});
+            } else {
+                engine.add_system(SingleComponentMovementSystem {});
+            }
+
+            let start = std::time::Instant::now();
+            for _ in 0..10 {
+                engine.run();
+            }
+            timings.push(start.elapsed());
+        }
+
+        println!("single_entity_step: {:?}", timings[0]);
+        println!("par_for_each_mut: {:?}", timings[1]);
+    }
+
+    #[test]
+    fn test_find_map_mut() {
+        struct Health(u32);
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = [50, 5, 80, 3, 100]
+            .map(|health| entities_and_components.add_entity_with((Health(health),)));
+
+        let mut visited = 0;
+        let healed = entities_and_components.find_map_mut(|entity, health: &mut Health| {
+            visited += 1;
+            if health.0 < 10 {
+                health.0 += 20;
+                Some(entity)
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(healed, Some(entities[1]));
+        // it stopped at the first low health entity
+        assert_eq!(visited, 2);
+        let (health,) = entities_and_components.get_components::<(Health,)>(entities[1]);
+        assert_eq!(health.0, 25);
+        let (health,) = entities_and_components.get_components::<(Health,)>(entities[3]);
+        assert_eq!(health.0, 3);
+    }
+
+    #[test]
+    fn test_for_each_pair() {
+        struct Position {
+            x: f32,
+            y: f32,
+        }
+
+        struct Collider {
+            radius: f32,
+            hits: u32,
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        // three overlapping circles in a row, and one far away
+        let entities = [(0.0, 0.0), (1.5, 0.0), (3.0, 0.0), (100.0, 100.0)].map(|(x, y)| {
+            entities_and_components.add_entity_with((
+                Position { x, y },
+                Collider {
+                    radius: 1.0,
+                    hits: 0,
+                },
+            ))
+        });
+        // no collider, so it is never checked
+        entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+
+        let mut pairs = 0;
+        let mut overlaps = 0;
+        entities_and_components.for_each_pair::<(Position, Collider)>(
+            |(_, (position_a, collider_a)), (_, (position_b, collider_b))| {
+                pairs += 1;
+                let distance = ((position_a.x - position_b.x).powi(2)
+                    + (position_a.y - position_b.y).powi(2))
+                .sqrt();
+                if distance < collider_a.radius + collider_b.radius {
+                    overlaps += 1;
+                    collider_a.hits += 1;
+                    collider_b.hits += 1;
+                }
+            },
+        );
+
+        assert_eq!(pairs, 6);
+        // 0 overlaps 1, 1 overlaps 2, 0 and 2 only touch
+        assert_eq!(overlaps, 2);
+        let hits = entities.map(|entity| {
+            let (collider,) = entities_and_components.get_components::<(Collider,)>(entity);
+            collider.hits
+        });
+        assert_eq!(hits, [1, 2, 1, 0]);
+
+        let [(a,), (b,)] =
+            entities_and_components.get_many_mut::<(Collider,), 2>([entities[0], entities[3]]);
+        std::mem::swap(a, b);
+        let (collider,) = entities_and_components.get_components::<(Collider,)>(entities[3]);
+        assert_eq!(collider.hits, 1);
+    }
+
+    #[test]
+    fn test_iter_entities_sorted_by_key() {
+        struct Priority(u32);
+
+        struct Target(Option<Entity>);
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let enemy = entities_and_components.add_entity();
+        let turrets = [2, 1, 3, 4].map(|priority| {
+            entities_and_components.add_entity_with((Priority(priority), Target(None)))
+        });
+
+        // the turret with the highest priority claims the enemy, the others find it taken
+        let order = entities_and_components.iter_entities_sorted_by_key(|entity| {
+            match entities_and_components.try_get_components::<(Priority,)>(entity) {
+                (Some(priority),) => std::cmp::Reverse(priority.0),
+                // entities without a priority come last
+                (None,) => std::cmp::Reverse(0),
+            }
+        });
+        assert_eq!(
+            order,
+            vec![turrets[3], turrets[2], turrets[0], turrets[1], enemy]
+        );
+
+        let mut claimed = false;
+        for entity in order {
+            if let (Some(target),) =
+                entities_and_components.try_get_components_mut::<(Target,)>(entity)
+            {
+                if !claimed {
+                    target.0 = Some(enemy);
+                    claimed = true;
+                }
+            }
+        }
+        let (target,) = entities_and_components.get_components::<(Target,)>(turrets[3]);
+        assert_eq!(target.0, Some(enemy));
+    }
+
+    #[test]
+    fn test_run_once() {
+        let mut engine = World::new();
+        engine.run_once(|entities_and_components| {
+            for i in 0..3 {
+                entities_and_components.add_entity_with((Position {
+                    x: i as f32,
+                    y: 0.0,
+                },));
+            }
+            entities_and_components
+                .commands()
+                .spawn((Velocity { x: 1.0, y: 0.0 },));
+        });
+
+        // it ran without a system and without running the world
+        assert_eq!(engine.frame(), 0);
+        assert!(engine.system_info().is_empty());
+        let entities_and_components = &engine.entities_and_components;
+        assert_eq!(entities_and_components.get_entity_count(), 4);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            3
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            1
+        );
+    }
+
+    #[test]
+    fn bench_component_buffer() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Body {
+            position: f32,
+            velocity: f32,
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = (0..100000)
+            .map(|i| {
+                entities_and_components.add_entity_with((Body {
+                    position: 0.0,
+                    velocity: i as f32,
+                },))
+            })
+            .collect::<Vec<Entity>>();
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            for entity in entities.iter() {
+                let (body,) = entities_and_components.get_components_mut::<(Body,)>(*entity);
+                body.position += body.velocity * 0.5;
+            }
+        }
+        let per_entity_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            entities_and_components.with_component_buffer_mut(|bodies: &mut [Body], _| {
+                for body in bodies {
+                    body.position += body.velocity * 0.5;
+                }
+            });
+        }
+        let buffer_time = start.elapsed();
+
+        // both integrated every body 10 times
+        for (i, entity) in entities.iter().enumerate() {
+            let (body,) = entities_and_components.get_components::<(Body,)>(*entity);
+            assert_eq!(body.position, i as f32 * 10.0);
+        }
+        println!("Per entity access: {:?}", per_entity_time);
+        println!("Bulk buffer: {:?}", buffer_time);
+    }
+
+    #[test]
+    fn test_group_by() {
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+        struct MeshId(u32);
+
+        struct Mesh {
+            id: MeshId,
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = [1, 2, 1, 3, 1]
+            .map(|id| entities_and_components.add_entity_with((Mesh { id: MeshId(id) },)));
+        entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+
+        let groups = entities_and_components.group_by(|mesh: &Mesh| mesh.id);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(
+            groups[&MeshId(1)],
+            vec![entities[0], entities[2], entities[4]]
+        );
+        assert_eq!(groups[&MeshId(2)], vec![entities[1]]);
+        assert_eq!(groups[&MeshId(3)], vec![entities[3]]);
+    }
+
+    #[test]
+    fn test_iter_related_pairs() {
+        #[derive(PartialEq, Eq, Hash)]
+        struct GroupId(u32);
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities =
+            [7, 7, 3, 7].map(|id| entities_and_components.add_entity_with((GroupId(id),)));
+
+        let mut pairs = entities_and_components
+            .iter_related_pairs(|group: &GroupId| group.0)
+            .collect::<Vec<(Entity, Entity)>>();
+        pairs.sort();
+        // the entity alone in group 3 is in no pair
+        assert_eq!(
+            pairs,
+            vec![
+                (entities[0], entities[1]),
+                (entities[0], entities[3]),
+                (entities[1], entities[3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_par_partition_matches_group_by() {
+        let mut engine = World::new();
+        let entities = (0..10000)
+            .map(|i| {
+                engine.entities_and_components.add_entity_with((Position {
+                    x: (i * 37 % 1000) as f32,
+                    y: (i * 91 % 1000) as f32,
+                },))
+            })
+            .collect::<Vec<Entity>>();
+        for entity in entities.iter().step_by(7) {
+            engine.entities_and_components.remove_entity(*entity);
+        }
+
+        let cell = |position: &Position| ((position.x / 100.0) as i32, (position.y / 100.0) as i32);
+        let serial = engine.entities_and_components.group_by(cell);
+        let thread_safe_entities_and_components =
+            EntitiesAndComponentsThreadSafe::new(&mut engine.entities_and_components);
+        let parallel = thread_safe_entities_and_components.par_partition(cell);
+
+        assert_eq!(parallel.len(), 100);
+        assert_eq!(parallel, serial);
+    }
+
+    #[test]
+    fn test_entity_chunks() {
+        use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+        let mut engine = World::new();
+        for i in 0..100 {
+            engine.entities_and_components.add_entity_with((Position {
+                x: i as f32,
+                y: 0.0,
+            },));
+        }
+
+        let thread_safe_entities_and_components =
+            EntitiesAndComponentsThreadSafe::new(&mut engine.entities_and_components);
+        let chunks = thread_safe_entities_and_components
+            .entity_chunks(32)
+            .collect::<Vec<&[Entity]>>();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| chunk.len())
+                .collect::<Vec<usize>>(),
+            vec![32, 32, 32, 4]
+        );
+
+        let sum = chunks
+            .par_iter()
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|entity| {
+                        thread_safe_entities_and_components
+                            .get_components::<(Position,)>(*entity)
+                            .0
+                            .x
+                    })
+                    .sum::<f32>()
+            })
+            .sum::<f32>();
+        assert_eq!(sum, 4950.0);
+    }
+
+    #[test]
+    fn test_system_macro() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 2.0 }));
+        // doesn't have a velocity so the system should skip it
+        let entity_2 = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+
+        engine.add_system(macro_movement);
+        let tick = engine.entities_and_components.current_tick();
+
+        for _ in 0..3 {
+            engine.run();
+        }
+
+        // only the &mut parameter counts as a change
+        assert_eq!(
+            engine
+                .entities_and_components
+                .changed_since::<Position>(tick)
+                .collect::<Vec<Entity>>(),
+            vec![entity]
+        );
+        assert_eq!(
+            engine
+                .entities_and_components
+                .changed_since::<Velocity>(tick)
+                .count(),
+            0
+        );
+
+        let (position,) = engine
+            .entities_and_components
+            .get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 3.0, y: 6.0 });
+        let (position,) = engine
+            .entities_and_components
+            .get_components::<(Position,)>(entity_2);
+        assert_eq!(*position, Position { x: 0.0, y: 0.0 });
+    }
+
+    #[test]
+    fn test_for_each_entity_mut() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 2.0 }));
+        let entity_2 = entities_and_components.add_entity_with((Position { x: 5.0, y: 5.0 },));
+
+        entities_and_components.for_each_entity_mut(|mut single_entity| {
+            if single_entity.has_component::<Velocity>() {
+                let (position, velocity) =
+                    single_entity.get_components_mut::<(Position, Velocity)>();
+                position.x += velocity.x;
+                position.y += velocity.y;
+            } else {
+                single_entity.add_component(Velocity { x: 0.0, y: 0.0 });
+            }
+        });
+
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 1.0, y: 2.0 });
+        let (position, velocity) =
+            entities_and_components.get_components::<(Position, Velocity)>(entity_2);
+        assert_eq!(*position, Position { x: 5.0, y: 5.0 });
+        assert_eq!(*velocity, Velocity { x: 0.0, y: 0.0 });
+    }
+
+    #[test]
+    fn test_insert_components_bulk() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = (0..100)
+            .map(|_| entities_and_components.add_entity())
+            .collect::<Vec<Entity>>();
+
+        let pairs = entities
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 0)
+            .map(|(i, entity)| {
+                (
+                    *entity,
+                    Position {
+                        x: i as f32,
+                        y: 0.0,
+                    },
+                )
+            })
+            .collect::<Vec<(Entity, Position)>>();
+        entities_and_components.insert_components_bulk(pairs);
+        entities_and_components.insert_components_bulk(Vec::<(Entity, Velocity)>::new());
+
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            50
+        );
+        assert!(!entities_and_components.any_with_component::<Velocity>());
+        for (i, entity) in entities.iter().enumerate() {
+            let (position,) = entities_and_components.try_get_components::<(Position,)>(*entity);
+            if i % 2 == 0 {
+                assert_eq!(position.unwrap().x, i as f32);
+            } else {
+                assert!(position.is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn bench_insert_components_bulk() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let entities = (0..100000)
+            .map(|_| entities_and_components.add_entity())
+            .collect::<Vec<Entity>>();
+
+        let start = std::time::Instant::now();
+        for (i, entity) in entities.iter().enumerate() {
+            entities_and_components.add_component_to(
+                *entity,
+                Position {
+                    x: i as f32,
+                    y: 0.0,
+                },
+            );
+        }
+        let individual_time = start.elapsed();
+
+        let pairs = entities
+            .iter()
+            .enumerate()
+            .map(|(i, entity)| {
+                (
+                    *entity,
+                    Velocity {
+                        x: i as f32,
+                        y: 0.0,
+                    },
+                )
+            })
+            .collect::<Vec<(Entity, Velocity)>>();
+        let start = std::time::Instant::now();
+        entities_and_components.insert_components_bulk(pairs);
+        let bulk_time = start.elapsed();
+
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            100000
+        );
+        println!("Individual inserts: {:?}", individual_time);
+        println!("Bulk insert: {:?}", bulk_time);
+    }
+
+    #[test]
+    fn test_update_and_maybe_insert() {
+        struct FastMoving;
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        let slow = entities_and_components.add_entity_with((Velocity { x: 1.0, y: 0.0 },));
+        let fast = entities_and_components.add_entity_with((Velocity { x: 4.0, y: 0.0 },));
+
+        for entity in [slow, fast] {
+            entities_and_components.update_and_maybe_insert(entity, |velocity: &mut Velocity| {
+                velocity.x *= 2.0;
+                (velocity.x > 5.0).then_some(FastMoving)
+            });
+        }
+
+        let (velocity, fast_moving) =
+            entities_and_components.try_get_components::<(Velocity, FastMoving)>(slow);
+        assert_eq!(*velocity.unwrap(), Velocity { x: 2.0, y: 0.0 });
+        assert!(fast_moving.is_none());
+        let (velocity, fast_moving) =
+            entities_and_components.try_get_components::<(Velocity, FastMoving)>(fast);
+        assert_eq!(*velocity.unwrap(), Velocity { x: 8.0, y: 0.0 });
+        assert!(fast_moving.is_some());
+    }
+
+    #[test]
+    fn test_replace_component() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+        let archetypes = entities_and_components
+            .archetypes()
+            .map(|(archetype_id, component_types, entity_count)| {
+                (archetype_id, component_types.to_vec(), entity_count)
+            })
+            .collect::<Vec<(ArchetypeId, Vec<TypeId>, usize)>>();
+        let tick = entities_and_components.current_tick();
+
+        let old = entities_and_components.replace_component(entity, Position { x: 5.0, y: 5.0 });
+        assert_eq!(old, Some(Position { x: 0.0, y: 0.0 }));
+
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
+        assert_eq!(*position, Position { x: 5.0, y: 5.0 });
+        assert_eq!(
+            entities_and_components
+                .archetypes()
+                .map(|(archetype_id, component_types, entity_count)| {
+                    (archetype_id, component_types.to_vec(), entity_count)
+                })
+                .collect::<Vec<(ArchetypeId, Vec<TypeId>, usize)>>(),
+            archetypes
+        );
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Position>(tick)
+                .collect::<Vec<Entity>>(),
+            vec![entity]
+        );
+        assert_eq!(
+            entities_and_components
+                .changed_since::<Velocity>(tick)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_deterministic_iteration_order() {
+        fn simulate() -> (Vec<Entity>, Vec<Entity>, Vec<ArchetypeId>) {
+            let mut entities_and_components = EntitiesAndComponents::new();
+            let mut entities = vec![];
+            for i in 0..50 {
+                let entity = entities_and_components.add_entity_with((Position {
+                    x: i as f32,
+                    y: 0.0,
+                },));
+                if i % 3 == 0 {
+                    entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+                }
+                entities.push(entity);
+            }
+            for entity in entities.iter().step_by(4) {
+                entities_and_components.remove_entity(*entity);
+            }
+            entities_and_components.add_entity_with((Velocity { x: 0.0, y: 0.0 },));
+
+            (
+                entities_and_components.get_entities(),
+                entities_and_components
+                    .entities_with_any::<(Position, Velocity)>()
+                    .collect(),
+                entities_and_components
+                    .archetypes()
+                    .map(|(archetype_id, _, _)| archetype_id)
+                    .collect(),
+            )
+        }
+
+        assert_eq!(simulate(), simulate());
+    }
+
+    #[test]
+    fn test_max_entities() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        entities_and_components.set_max_entities(Some(2));
+
+        assert!(entities_and_components.try_add_entity().is_ok());
+        assert!(entities_and_components
+            .try_add_entity_with((Position { x: 0.0, y: 0.0 },))
+            .is_ok());
+        assert_eq!(
+            entities_and_components.try_add_entity_with((Position { x: 1.0, y: 1.0 },)),
+            Err(EcsError::CapacityExceeded { max_entities: 2 })
+        );
+        assert_eq!(entities_and_components.get_entity_count(), 2);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            1
+        );
+
+        entities_and_components.set_max_entities(None);
+        assert!(entities_and_components.try_add_entity().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "maximum of 1 entities")]
+    fn test_max_entities_panics() {
+        let mut engine = World::new();
+        engine.entities_and_components.set_max_entities(Some(1));
+        engine.entities_and_components.add_entity();
+        engine.entities_and_components.add_entity();
+    }
 
-    impl System for ParallelMovementSystem {
-        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
-            let (position, velocity) = single_entity.get_components_mut::<(Position, Velocity)>();
+    #[test]
+    fn test_for_each_component_mut() {
+        struct Spawner {
+            remaining: u32,
+        }
 
-            position.x += velocity.x;
-            position.y += velocity.y;
+        struct Spawned {
+            spawner: Entity,
         }
-        fn implements_single_entity_step(&self) -> bool {
-            true
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let spawner = entities_and_components.add_entity_with((Spawner { remaining: 2 },));
+        let spawner_2 = entities_and_components.add_entity_with((Spawner { remaining: 3 },));
+
+        entities_and_components.for_each_component_mut::<Spawner>(
+            |spawner, spawner_component, entities_and_components| {
+                while spawner_component.remaining > 0 {
+                    spawner_component.remaining -= 1;
+                    entities_and_components.add_entity_with((Spawned { spawner },));
+                }
+                // the spawner is detached while it is being iterated
+                assert_eq!(
+                    entities_and_components.get_entity_count_with_component::<Spawner>(),
+                    1
+                );
+            },
+        );
+
+        assert_eq!(entities_and_components.get_entity_count(), 7);
+        for spawner in [spawner, spawner_2] {
+            let (spawner_component,) =
+                entities_and_components.get_components::<(Spawner,)>(spawner);
+            assert_eq!(spawner_component.remaining, 0);
         }
+        let spawned_by_second = entities_and_components
+            .query_where(|_, spawned: &Spawned| spawned.spawner == spawner_2)
+            .count();
+        assert_eq!(spawned_by_second, 3);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Spawner>(),
+            2
+        );
     }
 
     #[test]
-    fn test_components_mut() {
+    fn test_query_where() {
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
 
-        let entity = entities_and_components.add_entity();
+        let inside = entities_and_components.add_entity_with((Position { x: 1.0, y: 1.0 },));
+        entities_and_components.add_entity_with((Position { x: 50.0, y: 1.0 },));
+        let also_inside = entities_and_components.add_entity_with((Position { x: 9.0, y: 9.0 },));
+        entities_and_components.add_entity_with((Velocity { x: 1.0, y: 1.0 },));
 
-        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+        let mut matches = entities_and_components
+            .query_where::<Position>(|_, position| position.x < 10.0 && position.y < 10.0)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<Entity>>();
+        matches.sort();
 
-        engine.add_system(MovementSystem {});
+        let mut expected = vec![inside, also_inside];
+        expected.sort();
+        assert_eq!(matches, expected);
+    }
 
-        for _ in 0..5 {
-            engine.run();
+    #[test]
+    fn test_count_matching() {
+        struct Health(f32);
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        for health in [5.0, 50.0, 9.0, 100.0, 1.0] {
+            entities_and_components.add_entity_with((Health(health),));
         }
+        let disabled = entities_and_components.add_entity_with((Health(2.0),));
+        entities_and_components.disable_entity(disabled);
+        entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+
+        assert_eq!(
+            entities_and_components.count_matching(|health: &Health| health.0 < 10.0),
+            3
+        );
+        assert_eq!(
+            entities_and_components.count_matching(|health: &Health| health.0 > 1000.0),
+            0
+        );
     }
 
     #[test]
-    fn test_try_get_components() {
+    fn test_entities_with_component_rev() {
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
+        for x in 0..10 {
+            let entity = entities_and_components.add_entity_with((Position {
+                x: x as f32,
+                y: 0.0,
+            },));
+            if x % 4 == 0 {
+                entities_and_components.disable_entity(entity);
+            }
+            if x % 3 == 0 {
+                entities_and_components.add_component_to(entity, Velocity { x: 0.0, y: 0.0 });
+            }
+        }
 
-        let entity = entities_and_components.add_entity();
+        let forward = entities_and_components
+            .get_entities_with_component::<Position>()
+            .copied()
+            .collect::<Vec<Entity>>();
+        let mut reverse = entities_and_components
+            .entities_with_component_rev::<Position>()
+            .collect::<Vec<Entity>>();
+        assert_eq!(reverse.len(), 7);
+        reverse.reverse();
+        assert_eq!(reverse, forward);
+
+        // the last entity added comes first
+        let (position,) = entities_and_components.get_components::<(Position,)>(
+            entities_and_components
+                .entities_with_component_rev::<Position>()
+                .next()
+                .unwrap(),
+        );
+        assert_eq!(position.x, 9.0);
+        assert_eq!(
+            entities_and_components
+                .entities_with_component_rev::<Velocity>()
+                .count(),
+            3
+        );
+    }
 
-        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+    #[test]
+    fn test_any_with_component() {
+        struct Poisoned;
 
-        let (position, velocity) =
-            <(Position, Velocity)>::try_get_components(entities_and_components, entity);
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        assert!(!entities_and_components.any_with_component::<Poisoned>());
 
-        assert_eq!(position.unwrap().x, 0.0);
-        assert_eq!(position.unwrap().y, 0.0);
-        assert_eq!(velocity.unwrap().x, 1.0);
-        assert_eq!(velocity.unwrap().y, 1.0);
+        let entity = entities_and_components.add_entity_with((Poisoned,));
+        assert!(entities_and_components.any_with_component::<Poisoned>());
+
+        // disabled entities don't count
+        entities_and_components.disable_entity(entity);
+        assert!(!entities_and_components.any_with_component::<Poisoned>());
+        entities_and_components.enable_entity(entity);
+
+        entities_and_components.remove_component_from::<Poisoned>(entity);
+        assert!(!entities_and_components.any_with_component::<Poisoned>());
     }
 
     #[test]
-    fn test_overriding_components() {
+    fn test_min_max_by_component() {
+        struct Health(f32);
+
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
 
-        let entity = entities_and_components.add_entity();
+        assert!(entities_and_components
+            .min_by_component::<Health, f32>(|health| health.0)
+            .is_none());
 
-        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity, Position { x: 6.0, y: 1.0 });
+        entities_and_components.add_entity_with((Health(50.0),));
+        let weakest = entities_and_components.add_entity_with((Health(5.0),));
+        entities_and_components.add_entity_with((Health(f32::NAN),));
+        let strongest = entities_and_components.add_entity_with((Health(100.0),));
 
-        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
-        assert_eq!(position.x, 6.0);
-        assert_eq!(position.y, 1.0);
+        let (entity, health) = entities_and_components
+            .min_by_component::<Health, f32>(|health| health.0)
+            .unwrap();
+        assert_eq!(entity, weakest);
+        assert_eq!(health.0, 5.0);
+
+        let (entity, _) = entities_and_components
+            .max_by_component::<Health, f32>(|health| health.0)
+            .unwrap();
+        assert_eq!(entity, strongest);
     }
 
     #[test]
-    fn test_multiple_entities() {
+    fn test_indexed_components() {
+        #[derive(Debug, PartialEq)]
+        struct Buff {
+            strength: u32,
+        }
+
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
 
         let entity = entities_and_components.add_entity();
-        let entity_2 = entities_and_components.add_entity();
-
-        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
-
-        entities_and_components.add_component_to(entity_2, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity_2, Velocity { x: 1.0, y: 1.0 });
+        let other_entity = entities_and_components.add_entity();
 
-        // this should compile but, currently you can't borrow from two different entities mutably at the same time
-        let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+        for strength in 1..=3 {
+            let index = entities_and_components.add_component_indexed(entity, Buff { strength });
+            assert_eq!(index, strength as usize - 1);
+        }
 
-        println!("Position: {}, {}", position.x, position.y);
+        let total: u32 = entities_and_components
+            .get_components_indexed::<Buff>(entity)
+            .iter()
+            .map(|buff| buff.strength)
+            .sum();
+        assert_eq!(total, 6);
+
+        entities_and_components.get_components_indexed_mut::<Buff>(entity)[1].strength = 10;
+        assert_eq!(
+            entities_and_components.get_components_indexed::<Buff>(entity)[1],
+            Buff { strength: 10 }
+        );
+        assert!(entities_and_components
+            .get_components_indexed::<Buff>(other_entity)
+            .is_empty());
     }
 
     #[test]
-    fn test_add_entity_with_components() {
+    fn test_entities_with_any() {
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
 
-        let entity = entities_and_components
-            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+        let position_only = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        let velocity_only = entities_and_components.add_entity_with((Velocity { x: 0.0, y: 0.0 },));
+        let both = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 0.0, y: 0.0 }));
+        entities_and_components.add_entity();
 
-        let (position, velocity) =
-            entities_and_components.get_components::<(Position, Velocity)>(entity);
+        let mut entities = entities_and_components
+            .entities_with_any::<(Position, Velocity)>()
+            .collect::<Vec<Entity>>();
+        entities.sort();
 
-        assert_eq!(position.x, 0.0);
-        assert_eq!(position.y, 0.0);
-        assert_eq!(velocity.x, 1.0);
-        assert_eq!(velocity.y, 1.0);
+        let mut expected = vec![position_only, velocity_only, both];
+        expected.sort();
+        assert_eq!(entities, expected);
     }
 
     #[test]
-    fn test_entity_removal() {
+    #[should_panic(expected = "frozen")]
+    fn test_frozen_mutation_panics() {
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
 
-        let entity = entities_and_components
-            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
-
-        let (position, velocity) =
-            entities_and_components.get_components::<(Position, Velocity)>(entity);
+        let entity = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        entities_and_components.freeze();
 
+        // reading is still fine
+        let (position,) = entities_and_components.get_components::<(Position,)>(entity);
         assert_eq!(position.x, 0.0);
-        assert_eq!(position.y, 0.0);
-        assert_eq!(velocity.x, 1.0);
-        assert_eq!(velocity.y, 1.0);
-
-        entities_and_components.remove_entity(entity);
 
-        assert_eq!(entities_and_components.get_entity_count(), 0);
-
-        let entity = entities_and_components.add_entity();
+        entities_and_components.get_components_mut::<(Position,)>(entity);
+    }
 
-        // make sure the new entity doesn't have the old entity's components
-        let (position, velocity) =
-            entities_and_components.try_get_components::<(Position, Velocity)>(entity);
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "frozen")]
+    fn test_frozen_try_add_entity_with_panics() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        entities_and_components.freeze();
 
-        assert_eq!(position, None);
-        assert_eq!(velocity, None);
+        // like every mutation this panics in debug builds, and is an error in release builds
+        let _ = entities_and_components.try_add_entity_with((Position { x: 0.0, y: 0.0 },));
     }
 
     #[test]
-    fn test_get_entities_with_component() {
+    #[cfg(not(debug_assertions))]
+    fn test_frozen_mutation_is_an_error_in_release() {
         let mut engine = World::new();
         let entities_and_components = &mut engine.entities_and_components;
+        let errors = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = errors.clone();
+        entities_and_components
+            .set_error_handler(Box::new(move |error| reported.lock().unwrap().push(error)));
 
         let entity = entities_and_components.add_entity();
-        let entity_2 = entities_and_components.add_entity();
-
+        entities_and_components.freeze();
+        assert_eq!(
+            entities_and_components.try_add_entity(),
+            Err(EcsError::Frozen)
+        );
+        assert_eq!(
+            entities_and_components.try_add_entity_with((Position { x: 0.0, y: 0.0 },)),
+            Err(EcsError::Frozen)
+        );
         entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity, Velocity { x: 1.0, y: 1.0 });
+        entities_and_components.remove_entity(entity);
 
-        entities_and_components.add_component_to(entity_2, Position { x: 0.0, y: 0.0 });
-        entities_and_components.add_component_to(entity_2, Velocity { x: 1.0, y: 1.0 });
+        assert_eq!(*errors.lock().unwrap(), vec![EcsError::Frozen; 2]);
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+        let (position,) = entities_and_components.try_get_components::<(Position,)>(entity);
+        assert!(position.is_none());
+    }
 
-        let entities = entities_and_components.get_entities_with_component::<Position>();
+    #[test]
+    fn test_thaw() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
 
-        assert_eq!(entities.count(), 2);
+        entities_and_components.freeze();
+        assert!(entities_and_components.is_frozen());
+        entities_and_components.thaw();
+        assert!(!entities_and_components.is_frozen());
+
+        let entity = entities_and_components.add_entity();
+        entities_and_components.add_component_to(entity, Position { x: 0.0, y: 0.0 });
+        let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+        position.x = 1.0;
+        assert_eq!(entities_and_components.get_entity_count(), 1);
     }
 
     #[test]
@@ -1328,6 +4558,64 @@ mod tests {
             entities_and_components.try_get_components::<(Position, Velocity)>(entity_1);
     }
 
+    #[test]
+    fn test_snapshot_resource() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Score {
+            points: u32,
+            combo: u32,
+        }
+
+        impl Resource for Score {}
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        assert_eq!(entities_and_components.snapshot_resource::<Score>(), None);
+        entities_and_components.add_resource(Score {
+            points: 10,
+            combo: 2,
+        });
+
+        let snapshot = entities_and_components
+            .snapshot_resource::<Score>()
+            .unwrap();
+        let score = entities_and_components.get_resource_mut::<Score>().unwrap();
+        score.points = 500;
+        score.combo = 0;
+
+        entities_and_components.restore_resource(snapshot);
+        assert_eq!(
+            entities_and_components.get_resource::<Score>(),
+            Some(&Score {
+                points: 10,
+                combo: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_spawn_from_resource() {
+        struct Difficulty {
+            health_multiplier: f32,
+        }
+
+        impl Resource for Difficulty {}
+
+        struct Health(f32);
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+        entities_and_components.add_resource(Difficulty {
+            health_multiplier: 1.5,
+        });
+
+        let entity = entities_and_components.spawn_from_resource(|difficulty: &Difficulty| {
+            (Health(100.0 * difficulty.health_multiplier),)
+        });
+        let (health,) = entities_and_components.get_components::<(Health,)>(entity);
+        assert_eq!(health.0, 150.0);
+    }
+
     #[test]
     fn test_resources() {
         struct TestResource {
@@ -1644,6 +4932,223 @@ mod tests {
         assert_eq!(parent, None);
     }
 
+    #[test]
+    fn test_remove_entity_with_grandparent() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let grandparent = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        let parent = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        let child = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+        let other = entities_and_components.add_entity_with((Velocity { x: 1.0, y: 1.0 },));
+        entities_and_components.set_parent(parent, grandparent);
+        entities_and_components.set_parent(child, parent);
+
+        entities_and_components.remove_entity(parent);
+
+        assert!(entities_and_components.does_entity_exist(grandparent));
+        assert!(!entities_and_components.does_entity_exist(parent));
+        assert!(!entities_and_components.does_entity_exist(child));
+        assert!(entities_and_components.get_children(grandparent).is_empty());
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Position>()
+                .collect::<Vec<_>>(),
+            vec![&grandparent]
+        );
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Velocity>()
+                .collect::<Vec<_>>(),
+            vec![&other]
+        );
+    }
+
+    #[test]
+    fn test_spawn_chain() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components
+            .spawn()
+            .insert(Position { x: 1.0, y: 2.0 })
+            .insert(Velocity { x: 3.0, y: 4.0 })
+            .id();
+
+        let (position, velocity) =
+            entities_and_components.get_components::<(Position, Velocity)>(entity);
+        assert_eq!(*position, Position { x: 1.0, y: 2.0 });
+        assert_eq!((velocity.x, velocity.y), (3.0, 4.0));
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+    }
+
+    #[test]
+    fn test_despawn_all_with() {
+        struct Projectile;
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let player = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        let projectiles = (0..5)
+            .map(|_| {
+                entities_and_components.add_entity_with((Projectile, Position { x: 0.0, y: 0.0 }))
+            })
+            .collect::<Vec<Entity>>();
+        // a projectile that is the child of another projectile
+        entities_and_components.set_parent(projectiles[1], projectiles[0]);
+        // the trail of a projectile goes with it
+        let trail = entities_and_components.add_entity();
+        entities_and_components.set_parent(trail, projectiles[2]);
+        entities_and_components.disable_entity(projectiles[3]);
+        let wall = entities_and_components.add_entity_with((Velocity { x: 0.0, y: 0.0 },));
+
+        entities_and_components.despawn_all_with::<Projectile>();
+
+        let mut remaining = entities_and_components.get_entities_include_disabled();
+        remaining.sort();
+        let mut expected = vec![player, wall];
+        expected.sort();
+        assert_eq!(remaining, expected);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Projectile>(),
+            0
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            1
+        );
+
+        // the tag can be used again afterwards
+        let projectile = entities_and_components.add_entity_with((Projectile,));
+        assert_eq!(
+            entities_and_components.get_entity_with_component::<Projectile>(0),
+            Some(projectile)
+        );
+    }
+
+    #[test]
+    fn test_remove_component_from_returns_removed() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entity = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        assert!(entities_and_components.remove_component_from::<Position>(entity));
+        // it is gone now, so there is nothing left to remove
+        assert!(!entities_and_components.remove_component_from::<Position>(entity));
+        assert!(!entities_and_components.remove_component_from::<Velocity>(entity));
+    }
+
+    #[test]
+    fn test_remove_entity_updates_index() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let parent = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+        let children = (0..3)
+            .map(|_| {
+                let child = entities_and_components
+                    .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }));
+                entities_and_components.set_parent(child, parent);
+                child
+            })
+            .collect::<Vec<Entity>>();
+        let other = entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+
+        entities_and_components.remove_entity(children[1]);
+        assert_eq!(
+            entities_and_components.get_children(parent),
+            vec![children[0], children[2]]
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            2
+        );
+
+        entities_and_components.remove_entity(parent);
+        assert_eq!(entities_and_components.get_entities(), vec![other]);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            1
+        );
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Velocity>(),
+            0
+        );
+        assert_eq!(
+            entities_and_components.get_entities_with_parent().count(),
+            0
+        );
+        assert_eq!(
+            entities_and_components.get_entities_with_children().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn bench_mass_despawn() {
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let parents = (0..1000)
+            .map(|_| {
+                let parent =
+                    entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 },));
+                for _ in 0..100 {
+                    let child = entities_and_components.add_entity_with((
+                        Position { x: 0.0, y: 0.0 },
+                        Velocity { x: 1.0, y: 1.0 },
+                    ));
+                    entities_and_components.set_parent(child, parent);
+                }
+                parent
+            })
+            .collect::<Vec<Entity>>();
+
+        let start = std::time::Instant::now();
+        for parent in parents {
+            entities_and_components.remove_entity(parent);
+        }
+        let mass_despawn_time = start.elapsed();
+
+        assert_eq!(entities_and_components.get_entity_count(), 0);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            0
+        );
+        println!("Mass despawn of 101000 entities: {:?}", mass_despawn_time);
+    }
+
+    #[test]
+    fn bench_despawn() {
+        fn spawn(entities_and_components: &mut EntitiesAndComponents) -> Vec<Entity> {
+            (0..100000)
+                .map(|_| {
+                    entities_and_components
+                        .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 1.0 }))
+                })
+                .collect()
+        }
+
+        let mut engine = World::new();
+        let entities_and_components = &mut engine.entities_and_components;
+
+        let entities = spawn(entities_and_components);
+        let start = std::time::Instant::now();
+        for entity in entities {
+            entities_and_components.remove_entity(entity);
+        }
+        let remove_entity_time = start.elapsed();
+        assert_eq!(entities_and_components.get_entity_count(), 0);
+        assert_eq!(
+            entities_and_components.get_entity_count_with_component::<Position>(),
+            0
+        );
+
+        println!("remove_entity: {:?}", remove_entity_time);
+    }
+
     #[test]
     fn bench_every_function() {
         let mut engine = World::new();
@@ -1774,6 +5279,61 @@ mod tests {
         write_bar(&mut file, remove_entity_time.as_micros() as usize);
     }
 
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_spawn_emits_trace_event() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for CapturedOutput {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let output = CapturedOutput::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let entity = tracing::subscriber::with_default(subscriber, || {
+            let mut world = World::new();
+            world.entities_and_components.add_entity()
+        });
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("spawn"));
+        assert!(output.contains(&format!("entity={:?}", entity.entity_id)));
+    }
+
+    #[test]
+    fn test_derive_resource() {
+        #[derive(Resource)]
+        struct Score(u32);
+
+        let mut world = World::new();
+        world.entities_and_components.add_resource(Score(3));
+        world
+            .entities_and_components
+            .get_resource_mut::<Score>()
+            .unwrap()
+            .0 += 1;
+
+        let score = world
+            .entities_and_components
+            .get_resource::<Score>()
+            .unwrap();
+        assert_eq!(score.0, 4);
+    }
+
     fn write_bar(file: &mut File, length: usize) {
         const ADJUSTMENT: usize = 100000;
         let length = length / ADJUSTMENT;
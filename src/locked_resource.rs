@@ -0,0 +1,113 @@
+use crate::*;
+use std::sync::Mutex;
+
+/// A resource behind a Mutex, added with add_locked_resource
+/// Resources are read only while systems run in parallel, but a locked resource can still be changed by locking it,
+/// for example to collect events from single_entity_step
+/// The lock should only be held briefly, every other thread that wants it has to wait
+pub struct LockedResource<T> {
+    mutex: Mutex<T>,
+}
+
+impl<T: 'static> Resource for LockedResource<T> {}
+
+impl EntitiesAndComponents {
+    /// Adds a resource that can be changed through a lock, even where resources are only readable
+    /// It is accessed with get_locked_resource instead of get_resource
+    pub fn add_locked_resource<T: Send + 'static>(&mut self, resource: T) {
+        self.add_resource(LockedResource {
+            mutex: Mutex::new(resource),
+        });
+    }
+
+    /// Gets the lock of a resource added with add_locked_resource
+    pub fn get_locked_resource<T: Send + 'static>(&self) -> Option<&Mutex<T>> {
+        self.get_resource::<LockedResource<T>>()
+            .map(|resource| &resource.mutex)
+    }
+}
+
+impl<'a> EntitiesAndComponentsThreadSafe<'a> {
+    /// Gets the lock of a resource added with add_locked_resource
+    pub fn get_locked_resource<T: Send + 'static>(&self) -> Option<&Mutex<T>> {
+        self.entities_and_components.get_locked_resource::<T>()
+    }
+}
+
+impl<'a> SingleMutEntity<'a> {
+    /// Gets the lock of a resource added with add_locked_resource
+    /// panics if the resource does not exist
+    pub fn get_locked_resource<T: Send + 'static>(&self) -> &Mutex<T> {
+        self.entities_and_components
+            .get_locked_resource::<T>()
+            .unwrap_or_else(|| {
+                panic!(
+                    "Resource of type {type:?} does not exist, was the type edited?",
+                    type = std::any::type_name::<T>()
+                );
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Health(u32);
+
+    struct Died(Entity);
+
+    // every entity loses 10 health a frame and reports it to the event list once it dies
+    struct DamageSystem {}
+
+    impl System for DamageSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            let (health,) = single_entity.get_components_mut::<(Health,)>();
+            if health.0 == 0 {
+                return;
+            }
+            health.0 -= 10;
+            if health.0 == 0 {
+                let entity = single_entity.id();
+                single_entity
+                    .get_locked_resource::<Vec<Died>>()
+                    .lock()
+                    .unwrap()
+                    .push(Died(entity));
+            }
+        }
+
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_locked_resource_in_single_entity_step() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.add_locked_resource(Vec::<Died>::new());
+        for i in 0..100 {
+            entities_and_components.add_entity_with((Health(10 * (i % 3 + 1)),));
+        }
+        world.add_system(DamageSystem {});
+
+        world.run();
+        world.run();
+
+        let events = world
+            .entities_and_components
+            .get_locked_resource::<Vec<Died>>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        // the entities with 10 and 20 health have died
+        assert_eq!(events.len(), (0..100).filter(|i| i % 3 != 2).count());
+        for Died(entity) in events.iter() {
+            let (health,) = world
+                .entities_and_components
+                .get_components::<(Health,)>(*entity);
+            assert_eq!(health.0, 0);
+        }
+    }
+}
@@ -13,8 +13,29 @@ pub trait ComponentsRef<'a> {
     ) -> Self::Result;
 }
 
+/// This trait is used to get the types of the components in a tuple
+/// it is automatically implemented for tuples of components
+pub trait ComponentTypes {
+    /// Returns the TypeIds of the components in the order they appear in the tuple
+    fn type_ids() -> Vec<TypeId>;
+
+    /// Returns the TypeIds the components are stored under in the order they appear in the tuple
+    /// components are boxed before they are stored, so this is the TypeId of Box<T>
+    fn storage_type_ids() -> Vec<TypeId>;
+}
+
 macro_rules! impl_components {
     ($($generic_name: ident),*) => {
+        impl<$($generic_name: 'static),*> ComponentTypes for ($($generic_name,)*) {
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<$generic_name>(),)*]
+            }
+
+            fn storage_type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<Box<$generic_name>>(),)*]
+            }
+        }
+
         impl<'b, $($generic_name: 'static),*> ComponentsRef<'b> for ($($generic_name,)*) {
             type Result = ($(&'b $generic_name,)*);
 
@@ -30,11 +51,13 @@ macro_rules! impl_components {
                 }
 
                 let components = components.unwrap();
+                $(
+                    entities_and_components.record_read::<$generic_name>();
+                )*
 
                 (
                     $(
-                        components
-                            .get::<Box<$generic_name>>()
+                        get_stored::<$generic_name>(&entities_and_components.storages, components, entity)
                             .unwrap_or_else(||{
                                 let type_name = std::any::type_name::<$generic_name>();
                                 panic!(
@@ -77,12 +100,13 @@ macro_rules! impl_try_components {
                 }
 
                 let components = components.unwrap();
-
+                $(
+                    entities_and_components.record_read::<$generic_name>();
+                )*
 
                 (
                     $(
-                        components
-                            .get::<Box<$generic_name>>().map(|boxed_t1|{ &**boxed_t1}),
+                        get_stored::<$generic_name>(&entities_and_components.storages, components, entity),
                     )*
                 )
             }
@@ -123,6 +147,12 @@ macro_rules! impl_components_mut {
                     }
                 }
 
+                // mutable access counts as a change, components that aren't on the entity are ignored
+                $(
+                    entities_and_components.record_write::<$generic_name>();
+                    entities_and_components.mark_changed(entity, std::any::TypeId::of::<Box<$generic_name>>());
+                )*
+
                 let components = entities_and_components
                 .components
                 .get_mut(entity.entity_id);
@@ -138,8 +168,11 @@ macro_rules! impl_components_mut {
                 (
                     $(
                         {
-                            let pointer: *mut $generic_name = &mut **components
-                                .get_mut::<Box<$generic_name>>()
+                            let pointer: *mut $generic_name = get_stored_mut::<$generic_name>(
+                                &mut entities_and_components.storages,
+                                components,
+                                entity,
+                            )
                                 .unwrap_or_else(||{
                                     let type_name = std::any::type_name::<$generic_name>();
                                     panic!(
@@ -193,6 +226,12 @@ macro_rules! impl_try_components_mut {
                     }
                 }
 
+                // mutable access counts as a change, components that aren't on the entity are ignored
+                $(
+                    entities_and_components.record_write::<$generic_name>();
+                    entities_and_components.mark_changed(entity, std::any::TypeId::of::<Box<$generic_name>>());
+                )*
+
                 let components = entities_and_components
                     .components
                     .get_mut(entity.entity_id);
@@ -208,11 +247,14 @@ macro_rules! impl_try_components_mut {
                 (
                     $(
                         {
-                            let original_reference = components
-                                .get_mut::<Box<$generic_name>>();
+                            let original_reference = get_stored_mut::<$generic_name>(
+                                &mut entities_and_components.storages,
+                                components,
+                                entity,
+                            );
                             match original_reference {
                                 Some(reference) => {
-                                    let pointer: *mut $generic_name = &mut **reference;
+                                    let pointer: *mut $generic_name = reference;
                                     // SAFETY: We just checked that the component exists
                                     // and that the component is not borrowed mutably more than once
                                     // and lifetimes are checked at compile time to make sure that the component still exists
@@ -252,6 +294,24 @@ macro_rules! impl_owned_components {
                 entities_and_components: &mut EntitiesAndComponents,
                 components: Self::Input,
             ) -> Entity {
+                // a later component of the same type would silently overwrite the earlier one
+                let all_types = [
+                    $(
+                        (std::any::TypeId::of::<$generic_name>(), std::any::type_name::<$generic_name>()),
+                    )*
+                ];
+
+                for i in 0..all_types.len() {
+                    for j in i+1..all_types.len() {
+                        if all_types[i].0 == all_types[j].0 {
+                            panic!(
+                                "Component {} is in the tuple more than once, an entity can only have one of each component",
+                                all_types[i].1
+                            );
+                        }
+                    }
+                }
+
                 let entity = entities_and_components.add_entity();
 
                 $(
@@ -264,6 +324,130 @@ macro_rules! impl_owned_components {
     };
 }
 
+/// This trait is used by the system! macro to get the component type from a parameter type
+/// it is implemented for &T and &mut T
+pub trait SystemParameter: Sized {
+    /// The component the parameter refers to
+    type Component: Component + Send + Sync;
+
+    /// true for &mut T, only mutable parameters count as a change
+    const MUTABLE: bool;
+
+    /// Gets the parameter from the entity, or None if the entity does not have the component
+    /// &T is fetched immutably, so it doesn't show up in changed_since
+    /// # Safety
+    /// The parameter is not tied to the borrow of single_entity, so a component fetched mutably
+    /// must not be fetched again for the same call, and the parameters must not outlive the entity's components
+    unsafe fn fetch(single_entity: &mut SingleMutEntity) -> Option<Self>;
+}
+
+impl<T: Component + Send + Sync> SystemParameter for &T {
+    type Component = T;
+
+    const MUTABLE: bool = false;
+
+    unsafe fn fetch(single_entity: &mut SingleMutEntity) -> Option<Self> {
+        let (component,) = single_entity.try_get_components::<(T,)>();
+        let component: *const T = component?;
+        Some(unsafe { &*component })
+    }
+}
+
+impl<T: Component + Send + Sync> SystemParameter for &mut T {
+    type Component = T;
+
+    const MUTABLE: bool = true;
+
+    unsafe fn fetch(single_entity: &mut SingleMutEntity) -> Option<Self> {
+        let (component,) = single_entity.try_get_components_mut::<(T,)>();
+        let component: *mut T = component?;
+        Some(unsafe { &mut *component })
+    }
+}
+
+/// Turns a function into a system that runs it in single_entity_step for every entity that has all of its parameters
+/// The parameters must be references to components, &T for reading and &mut T for writing
+/// The system is a unit struct with the same name as the function, so it can be added with world.add_system(movement)
+/// ```
+/// use ABC_ECS::*;
+///
+/// struct Position { x: f32 }
+/// struct Velocity { x: f32 }
+///
+/// system! {
+///     fn movement(position: &mut Position, velocity: &Velocity) {
+///         position.x += velocity.x;
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.add_system(movement);
+/// ```
+#[macro_export]
+macro_rules! system {
+    ($vis: vis fn $name: ident($($parameter: ident: $parameter_type: ty),+ $(,)?) $body: block) => {
+        #[allow(non_camel_case_types)]
+        $vis struct $name;
+
+        impl $name {
+            fn call($($parameter: $parameter_type),+) $body
+        }
+
+        impl $crate::System for $name {
+            fn single_entity_step(&self, single_entity: &mut $crate::SingleMutEntity) {
+                // make sure that the same component is not borrowed mutably more than once
+                let all_types = [
+                    $(
+                        (
+                            ::std::any::TypeId::of::<<$parameter_type as $crate::SystemParameter>::Component>(),
+                            <$parameter_type as $crate::SystemParameter>::MUTABLE,
+                        ),
+                    )+
+                ];
+                for i in 0..all_types.len() {
+                    for j in i + 1..all_types.len() {
+                        assert!(
+                            all_types[i].0 != all_types[j].0 || !(all_types[i].1 || all_types[j].1),
+                            "You cannot borrow the same component mutably more than once!"
+                        );
+                    }
+                }
+
+                // nothing is fetched mutably unless the entity has every component
+                let ($($parameter,)+) = single_entity.try_get_components::<(
+                    $(<$parameter_type as $crate::SystemParameter>::Component,)+
+                )>();
+                if $($parameter.is_some())&&+ {
+                    // SAFETY: a component fetched mutably is not fetched again,
+                    // and the parameters are dropped before single_entity can be used again
+                    if let ($(Some($parameter),)+) = unsafe {(
+                        $(<$parameter_type as $crate::SystemParameter>::fetch(single_entity),)+
+                    )} {
+                        Self::call($($parameter),+);
+                    }
+                }
+            }
+
+            fn implements_single_entity_step(&self) -> bool {
+                true
+            }
+        }
+    };
+}
+
+/// Derives Resource, so as_any and as_any_mut always return self and the resource can always be downcast
+/// ```
+/// use ABC_ECS::*;
+///
+/// #[derive(Resource)]
+/// struct Score(u32);
+///
+/// let mut world = World::new();
+/// world.entities_and_components.add_resource(Score(0));
+/// assert_eq!(world.entities_and_components.get_resource::<Score>().unwrap().0, 0);
+/// ```
+pub use ABC_ECS_derive::Resource;
+
 // it would be nice to have a macro that generates this code
 impl_components!(T1);
 impl_components!(T1, T2);
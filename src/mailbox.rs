@@ -0,0 +1,167 @@
+use crate::*;
+
+// the mailboxes of one message type, with the type erased so every type can be kept in one map
+pub(crate) trait ErasedMailboxes {
+    fn clear(&mut self);
+
+    // drops the messages of an entity that was removed
+    fn forget(&mut self, entity_id: DefaultKey);
+
+    // moves every mailbox to the entity it was moved to by compact
+    fn remap(&mut self, entity_ids: &EntityIds);
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// the messages of one type sent to every entity, kept next to the entities instead of as components
+// so sending a message is not a structural change
+struct Mailboxes<M> {
+    messages: SecondaryMap<DefaultKey, Vec<M>>,
+}
+
+impl<M: Component> ErasedMailboxes for Mailboxes<M> {
+    fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    fn forget(&mut self, entity_id: DefaultKey) {
+        self.messages.remove(entity_id);
+    }
+
+    fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.messages, entity_ids);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Sends a message to an entity, it can be read with inbox during the next single_entity_step phase
+    /// All messages are cleared once that phase is over, so they are only delivered once
+    /// Messages are kept outside of the entity's components, so sending one doesn't change its archetype
+    /// panics if the entity does not exist
+    pub fn send_to<M: Component>(&mut self, entity: Entity, message: M) {
+        if !self.does_entity_exist(entity) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        if self.frozen_mutation() {
+            return;
+        }
+
+        let mailboxes = self
+            .mailboxes
+            .entry(TypeId::of::<M>())
+            .or_insert_with(|| {
+                Box::new(Mailboxes::<M> {
+                    messages: SecondaryMap::new(),
+                })
+            })
+            .as_any_mut()
+            .downcast_mut::<Mailboxes<M>>()
+            .unwrap();
+        if let Some(messages) = mailboxes.messages.entry(entity.entity_id) {
+            messages.or_default().push(message);
+        }
+    }
+
+    /// returns an iterator over the messages of a certain type that have been sent to an entity, in the order they were sent
+    /// panics if the entity does not exist
+    pub fn inbox<M: Component>(&self, entity: Entity) -> impl Iterator<Item = &M> {
+        if !self.does_entity_exist(entity) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        self.mailboxes
+            .get(&TypeId::of::<M>())
+            .and_then(|mailboxes| mailboxes.as_any().downcast_ref::<Mailboxes<M>>())
+            .and_then(|mailboxes| mailboxes.messages.get(entity.entity_id))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Removes all messages from every mailbox, World::run does this after the single_entity_step phase
+    pub fn clear_mailboxes(&mut self) {
+        for mailboxes in self.mailboxes.values_mut() {
+            mailboxes.clear();
+        }
+    }
+}
+
+impl<'a> SingleMutEntity<'a> {
+    /// returns an iterator over the messages of a certain type that have been sent to this entity with send_to
+    pub fn inbox<M: Component + Send + Sync>(&self) -> impl Iterator<Item = &M> {
+        self.entities_and_components.inbox::<M>(self.entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Health(u32);
+
+    struct Damage(u32);
+
+    struct DamageSystem {}
+
+    impl System for DamageSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            let damage = single_entity
+                .inbox::<Damage>()
+                .map(|damage| damage.0)
+                .sum::<u32>();
+            if let Some(health) = single_entity.try_get_component_mut::<Health>() {
+                health.0 = health.0.saturating_sub(damage);
+            }
+        }
+
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_send_damage_messages() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let target = entities_and_components.add_entity_with((Health(100),));
+        let bystander = entities_and_components.add_entity_with((Health(100),));
+
+        let generation = entities_and_components.archetype_generation();
+        entities_and_components.send_to(target, Damage(10));
+        entities_and_components.send_to(target, Damage(15));
+        // messages are not components
+        assert_eq!(entities_and_components.archetype_generation(), generation);
+        assert_eq!(
+            entities_and_components
+                .inbox::<Damage>(target)
+                .map(|damage| damage.0)
+                .collect::<Vec<u32>>(),
+            vec![10, 15]
+        );
+
+        world.add_system(DamageSystem {});
+        world.run();
+
+        let entities_and_components = &mut world.entities_and_components;
+        let (health,) = entities_and_components.get_components::<(Health,)>(target);
+        assert_eq!(health.0, 75);
+        let (health,) = entities_and_components.get_components::<(Health,)>(bystander);
+        assert_eq!(health.0, 100);
+
+        // the messages were cleared after they were delivered
+        assert_eq!(entities_and_components.inbox::<Damage>(target).count(), 0);
+        world.run();
+        let (health,) = world
+            .entities_and_components
+            .get_components::<(Health,)>(target);
+        assert_eq!(health.0, 75);
+    }
+}
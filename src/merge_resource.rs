@@ -0,0 +1,69 @@
+use crate::*;
+
+/// A value that another value of the same type can be merged into, see EntitiesAndComponents::merge_resource
+pub trait Merge {
+    /// Merges other into self, what that means is up to the type, for example only the fields other has set
+    fn merge(&mut self, other: Self);
+}
+
+impl EntitiesAndComponents {
+    /// Merges a resource into the existing resource of the same type, or adds it if there is none
+    /// This is useful for loading settings over their defaults
+    pub fn merge_resource<T: Resource + Merge>(&mut self, other: T) {
+        match self.get_resource_mut::<T>() {
+            Some(resource) => resource.merge(other),
+            None => self.add_resource(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Settings {
+        volume: Option<f32>,
+        fullscreen: Option<bool>,
+        name: Option<String>,
+    }
+
+    impl Resource for Settings {}
+
+    // the fields set in other replace the ones in self
+    impl Merge for Settings {
+        fn merge(&mut self, other: Self) {
+            self.volume = other.volume.or(self.volume);
+            self.fullscreen = other.fullscreen.or(self.fullscreen);
+            self.name = other.name.or(self.name.take());
+        }
+    }
+
+    #[test]
+    fn test_merge_resource() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let defaults = Settings {
+            volume: Some(1.0),
+            fullscreen: Some(false),
+            name: None,
+        };
+        // there is nothing to merge into yet, so the defaults are added
+        entities_and_components.merge_resource(defaults);
+
+        entities_and_components.merge_resource(Settings {
+            volume: None,
+            fullscreen: Some(true),
+            name: Some("player".to_string()),
+        });
+
+        assert_eq!(
+            entities_and_components.get_resource::<Settings>(),
+            Some(&Settings {
+                volume: Some(1.0),
+                fullscreen: Some(true),
+                name: Some("player".to_string()),
+            })
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::*;
+use std::collections::HashMap;
+
+impl EntitiesAndComponents {
+    /// Calls f for every entity with the component T, except disabled ones, with its component mutably
+    /// and the components of its neighbors, for example the cells of a grid in a cellular automaton
+    /// The neighbors are read from a copy of every T taken before the first call,
+    /// so every entity sees its neighbors as they were before any of them were changed
+    /// Neighbors without T are skipped, and an entity that is not in neighbors gets no neighbors
+    pub fn for_each_with_neighbors<T: Component + Clone>(
+        &mut self,
+        neighbors: &HashMap<Entity, Vec<Entity>>,
+        mut f: impl FnMut(&mut T, &[&T]),
+    ) {
+        let snapshot = self
+            .get_entities_with_component_include_disabled::<T>()
+            .filter_map(|entity| {
+                let component =
+                    get_stored::<T>(&self.storages, &self.components[entity.entity_id], *entity)?;
+                Some((*entity, component.clone()))
+            })
+            .collect::<FxHashMap<Entity, T>>();
+        let entities = self
+            .get_entities_with_component::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+
+        let mut neighbor_components = Vec::new();
+        for entity in entities {
+            neighbor_components.clear();
+            neighbor_components.extend(
+                neighbors
+                    .get(&entity)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|neighbor| snapshot.get(neighbor)),
+            );
+            let (component,) = self.get_components_mut::<(T,)>(entity);
+            f(component, &neighbor_components);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Cell {
+        alive: bool,
+    }
+
+    #[test]
+    fn test_for_each_with_neighbors_game_of_life() {
+        const SIZE: i32 = 5;
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        // a vertical blinker in the middle of the grid
+        let alive = |x: i32, y: i32| x == 2 && (1..=3).contains(&y);
+        let mut grid = HashMap::new();
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let entity =
+                    entities_and_components.add_entity_with((Cell { alive: alive(x, y) },));
+                grid.insert((x, y), entity);
+            }
+        }
+        let mut neighbors = HashMap::new();
+        for ((x, y), entity) in &grid {
+            let around = (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+                .filter(|position| position != &(*x, *y))
+                .filter_map(|position| grid.get(&position).copied())
+                .collect::<Vec<Entity>>();
+            neighbors.insert(*entity, around);
+        }
+
+        let step = |entities_and_components: &mut EntitiesAndComponents| {
+            entities_and_components.for_each_with_neighbors(
+                &neighbors,
+                |cell: &mut Cell, around| {
+                    let alive_around = around.iter().filter(|cell| cell.alive).count();
+                    cell.alive = matches!((cell.alive, alive_around), (true, 2) | (_, 3));
+                },
+            );
+        };
+        let is_alive = |entities_and_components: &EntitiesAndComponents, x: i32, y: i32| {
+            let (cell,) = entities_and_components.get_components::<(Cell,)>(grid[&(x, y)]);
+            cell.alive
+        };
+
+        // the blinker turns horizontal, and back again
+        step(entities_and_components);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let expected = y == 2 && (1..=3).contains(&x);
+                assert_eq!(
+                    is_alive(entities_and_components, x, y),
+                    expected,
+                    "cell {x}, {y}"
+                );
+            }
+        }
+        step(entities_and_components);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                assert_eq!(
+                    is_alive(entities_and_components, x, y),
+                    alive(x, y),
+                    "cell {x}, {y}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::*;
+
+// the stable ids of the component types registered with register_net_id, both ways
+pub(crate) struct NetIds {
+    types: FxHashMap<u16, TypeId>,
+    ids: FxHashMap<TypeId, u16>,
+}
+
+impl NetIds {
+    pub(crate) fn new() -> Self {
+        NetIds {
+            types: FxHashMap::default(),
+            ids: FxHashMap::default(),
+        }
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gives a component type a stable id, to be sent over the network instead of its TypeId or name
+    /// TypeIds change between builds, so both sides have to register the same ids for the same types
+    /// panics if the id is already used by another type, or the type already has another id
+    pub fn register_net_id<T: Component>(&mut self, id: u16) {
+        let type_id = TypeId::of::<T>();
+        if let Some(registered) = self.net_ids.types.get(&id) {
+            assert!(
+                *registered == type_id,
+                "Net id {id} is already used by another component type than {type:?}",
+                type = std::any::type_name::<T>()
+            );
+        }
+        if let Some(registered) = self.net_ids.ids.get(&type_id) {
+            assert!(
+                *registered == id,
+                "Component type {type:?} already has the net id {registered}",
+                type = std::any::type_name::<T>()
+            );
+        }
+        self.net_ids.types.insert(id, type_id);
+        self.net_ids.ids.insert(type_id, id);
+    }
+
+    /// Gets the TypeId of the component type registered with a net id
+    pub fn type_for_net_id(&self, id: u16) -> Option<TypeId> {
+        self.net_ids.types.get(&id).copied()
+    }
+
+    /// Gets the net id a component type was registered with
+    pub fn net_id_for<T: Component>(&self) -> Option<u16> {
+        self.net_ids.ids.get(&TypeId::of::<T>()).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+    struct Velocity;
+    struct Health;
+
+    #[test]
+    fn test_net_ids() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_net_id::<Position>(1);
+        entities_and_components.register_net_id::<Velocity>(2);
+        // registering the same id again does nothing
+        entities_and_components.register_net_id::<Position>(1);
+
+        assert_eq!(entities_and_components.net_id_for::<Position>(), Some(1));
+        assert_eq!(entities_and_components.net_id_for::<Velocity>(), Some(2));
+        assert_eq!(entities_and_components.net_id_for::<Health>(), None);
+
+        for id in [1, 2] {
+            let type_id = entities_and_components.type_for_net_id(id).unwrap();
+            let round_trip = if type_id == TypeId::of::<Position>() {
+                entities_and_components.net_id_for::<Position>()
+            } else {
+                entities_and_components.net_id_for::<Velocity>()
+            };
+            assert_eq!(round_trip, Some(id));
+        }
+        assert_eq!(entities_and_components.type_for_net_id(3), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "already used")]
+    fn test_net_id_used_twice_panics() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.register_net_id::<Position>(1);
+        entities_and_components.register_net_id::<Velocity>(1);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::*;
+
+// the entities matched by a query cached with cache_query, as of the archetype generation it was built at
+pub(crate) struct CachedQuery {
+    // the TypeIds the components are stored under, Box<T>
+    type_ids: Vec<TypeId>,
+    entities: Vec<Entity>,
+    generation: u64,
+}
+
+impl EntitiesAndComponents {
+    /// Gets the archetype generation, which goes up every time an entity is spawned or removed,
+    /// or gains or loses a component
+    /// Anything computed from which entities have which components stays valid while it is the same
+    pub fn archetype_generation(&self) -> u64 {
+        self.archetype_generation
+    }
+
+    /// Caches the entities that have every component in the tuple under a name, except disabled ones,
+    /// so systems can share one query instead of each running it every frame, see get_cached
+    /// The cache is only rebuilt when the archetype generation has changed since it was last built
+    /// Caching another query under the same name replaces it
+    pub fn cache_query<T: ComponentTypes>(&mut self, name: &str) {
+        let mut cached_query = CachedQuery {
+            type_ids: T::storage_type_ids(),
+            entities: Vec::new(),
+            generation: 0,
+        };
+        self.rebuild_cached_query(&mut cached_query);
+        self.cached_queries.insert(name.to_string(), cached_query);
+    }
+
+    /// Gets the entities matched by the query cached under the name, rebuilding them first if the world's structure changed
+    /// panics if no query has been cached under the name
+    pub fn get_cached(&mut self, name: &str) -> &[Entity] {
+        let Some(cached_query) = self.cached_queries.get(name) else {
+            panic!("No query has been cached under the name {name:?}, use cache_query first");
+        };
+        if cached_query.generation != self.archetype_generation {
+            let (name, mut cached_query) = self.cached_queries.remove_entry(name).unwrap();
+            self.rebuild_cached_query(&mut cached_query);
+            self.cached_queries.insert(name, cached_query);
+        }
+        &self.cached_queries[name].entities
+    }
+
+    /// Rebuilds every cached query whose entities are out of date
+    /// World::run calls this before every phase where systems run in parallel,
+    /// since they can only read the caches, not rebuild them
+    pub fn refresh_cached_queries(&mut self) {
+        if self.cached_queries.is_empty() {
+            return;
+        }
+        let mut cached_queries = std::mem::take(&mut self.cached_queries);
+        for cached_query in cached_queries.values_mut() {
+            if cached_query.generation != self.archetype_generation {
+                self.rebuild_cached_query(cached_query);
+            }
+        }
+        self.cached_queries = cached_queries;
+    }
+
+    fn rebuild_cached_query(&self, cached_query: &mut CachedQuery) {
+        cached_query.generation = self.archetype_generation;
+        cached_query.entities.clear();
+        let Some(first) = cached_query
+            .type_ids
+            .first()
+            .and_then(|type_id| self.entities_with_components.get(type_id))
+        else {
+            return;
+        };
+        let disabled = self.disabled_entities();
+        let has_all = self.has_all_matcher(&cached_query.type_ids[1..]);
+        cached_query.entities.extend(
+            first
+                .values()
+                .filter(|entity| !is_in(disabled, entity) && has_all(entity)),
+        );
+    }
+}
+
+impl<'a> EntitiesAndComponentsThreadSafe<'a> {
+    /// Gets the entities matched by the query cached under the name with cache_query
+    /// The caches are rebuilt before every parallel phase of World::run, and the structure can't change during one,
+    /// but outside of World::run refresh_cached_queries has to be called first
+    /// panics if no query has been cached under the name
+    pub fn get_cached(&self, name: &str) -> &[Entity] {
+        &self
+            .entities_and_components
+            .cached_queries
+            .get(name)
+            .unwrap_or_else(|| {
+                panic!("No query has been cached under the name {name:?}, use cache_query first");
+            })
+            .entities
+    }
+}
+
+impl World {
+    /// Caches the entities that have every component in the tuple under a name, see EntitiesAndComponents::cache_query
+    pub fn cache_query<T: ComponentTypes>(&mut self, name: &str) {
+        self.entities_and_components.cache_query::<T>(name);
+    }
+
+    /// Gets the entities matched by the query cached under the name, see EntitiesAndComponents::get_cached
+    pub fn get_cached(&mut self, name: &str) -> &[Entity] {
+        self.entities_and_components.get_cached(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+    struct Velocity;
+
+    #[test]
+    fn test_cached_query_rebuilds_after_structural_change() {
+        let mut world = World::new();
+        let moving = world
+            .entities_and_components
+            .add_entity_with((Position, Velocity));
+        world.entities_and_components.add_entity_with((Position,));
+        world.cache_query::<(Position, Velocity)>("movers");
+        assert_eq!(world.get_cached("movers"), &[moving]);
+
+        let generation = world.entities_and_components.archetype_generation();
+        let built = world.entities_and_components.cached_queries["movers"].generation;
+        // accessing components is not a structural change, so the cache is not rebuilt
+        world.run();
+        world
+            .entities_and_components
+            .get_components_mut::<(Position,)>(moving);
+        assert_eq!(world.get_cached("movers"), &[moving]);
+        assert_eq!(
+            world.entities_and_components.archetype_generation(),
+            generation
+        );
+        assert_eq!(
+            world.entities_and_components.cached_queries["movers"].generation,
+            built
+        );
+
+        let other = world
+            .entities_and_components
+            .add_entity_with((Position, Velocity));
+        assert_ne!(
+            world.entities_and_components.archetype_generation(),
+            generation
+        );
+        assert_eq!(world.get_cached("movers"), &[moving, other]);
+
+        world
+            .entities_and_components
+            .remove_component_from::<Velocity>(moving);
+        world.entities_and_components.disable_entity(other);
+        assert!(world.get_cached("movers").is_empty());
+    }
+
+    struct CountMovers {
+        count: std::sync::atomic::AtomicUsize,
+    }
+
+    impl System for CountMovers {
+        fn is_read_only(&self) -> bool {
+            true
+        }
+
+        fn run_readonly(&self, engine: &EntitiesAndComponentsThreadSafe) {
+            self.count.store(
+                engine.get_cached("movers").len(),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+        }
+    }
+
+    #[test]
+    fn test_cached_query_in_parallel_system() {
+        let mut world = World::new();
+        world.cache_query::<(Position, Velocity)>("movers");
+        let counter = world.add_system(CountMovers {
+            count: std::sync::atomic::AtomicUsize::new(0),
+        });
+        for _ in 0..3 {
+            world
+                .entities_and_components
+                .add_entity_with((Position, Velocity));
+        }
+
+        world.run();
+        let counter = world.systems[counter.system_id]
+            .as_any()
+            .downcast_ref::<CountMovers>()
+            .unwrap();
+        assert_eq!(counter.count.load(std::sync::atomic::Ordering::Relaxed), 3);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::*;
+use std::marker::PhantomData;
+
+/// A filter for query_filtered that only matches entities whose T changed since the running system last ran
+/// A component counts as changed when it was added, overwritten or accessed mutably, like with changed_since
+pub struct Changed<T> {
+    component: PhantomData<fn() -> T>,
+}
+
+/// Filters the entities of query_filtered, it is implemented for Changed<T> and for tuples of filters,
+/// which only match an entity if every filter in the tuple does
+pub trait QueryFilter {
+    /// Checks if the entity passes the filter, since is the tick the running system last ran at
+    fn matches(entities_and_components: &EntitiesAndComponents, entity: Entity, since: u64)
+        -> bool;
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    fn matches(
+        entities_and_components: &EntitiesAndComponents,
+        entity: Entity,
+        since: u64,
+    ) -> bool {
+        entities_and_components
+            .change_tracker
+            .get(entity.entity_id, TypeId::of::<Box<T>>())
+            .is_some_and(|ticks| ticks.changed > since)
+    }
+}
+
+macro_rules! impl_query_filter {
+    ($($generic_name: ident),*) => {
+        impl<$($generic_name: QueryFilter),*> QueryFilter for ($($generic_name,)*) {
+            fn matches(entities_and_components: &EntitiesAndComponents, entity: Entity, since: u64) -> bool {
+                $($generic_name::matches(entities_and_components, entity, since))&&*
+            }
+        }
+    };
+}
+
+impl_query_filter!(F1);
+impl_query_filter!(F1, F2);
+impl_query_filter!(F1, F2, F3);
+impl_query_filter!(F1, F2, F3, F4);
+impl_query_filter!(F1, F2, F3, F4, F5);
+impl_query_filter!(F1, F2, F3, F4, F5, F6);
+impl_query_filter!(F1, F2, F3, F4, F5, F6, F7);
+impl_query_filter!(F1, F2, F3, F4, F5, F6, F7, F8);
+
+impl EntitiesAndComponents {
+    /// returns an iterator over the entities with every component in Q that pass the filter F, except disabled ones,
+    /// for example query_filtered::<(Position,), (Changed<Position>,)>() gives the entities that moved
+    /// Inside System::run the filter is checked against the tick the system last ran at, so a system only sees
+    /// the changes made since then, not its own changes; a system that has not run yet sees everything
+    /// Outside of a system every component counts as changed since it was added
+    pub fn query_filtered<Q: ComponentTypes, F: QueryFilter>(
+        &self,
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let type_ids = Q::storage_type_ids();
+        let disabled = self.disabled_entities();
+        let since = self.system_last_run;
+        type_ids
+            .first()
+            .and_then(|type_id| self.entities_with_components.get(type_id))
+            .into_iter()
+            .flat_map(|entities| entities.values())
+            .filter(move |entity| {
+                !is_in(disabled, entity)
+                    && type_ids[1..]
+                        .iter()
+                        .all(|type_id| is_in(self.entities_with_components.get(type_id), entity))
+                    && F::matches(self, **entity, since)
+            })
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Velocity;
+
+    struct Moved(Vec<Entity>);
+
+    impl Resource for Moved {}
+
+    // records the entities that moved since it last ran
+    struct ReactSystem {}
+
+    impl System for ReactSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            let mut moved = engine
+                .query_filtered::<(Position,), (Changed<Position>,)>()
+                .collect::<Vec<Entity>>();
+            moved.sort();
+            // changes made by the system itself are not seen the next time it runs
+            for entity in &moved {
+                let (position,) = engine.get_components_mut::<(Position,)>(*entity);
+                position.x += 0.0;
+            }
+            engine.get_resource_mut::<Moved>().unwrap().0 = moved;
+        }
+    }
+
+    fn moved(world: &World) -> &[Entity] {
+        &world
+            .entities_and_components
+            .get_resource::<Moved>()
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_changed_filter() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.add_resource(Moved(Vec::new()));
+        let entities = (0..4)
+            .map(|i| entities_and_components.add_entity_with((Position { x: i as f32 }, Velocity)))
+            .collect::<Vec<Entity>>();
+        let without_position = entities_and_components.add_entity_with((Velocity,));
+        world.add_system(ReactSystem {});
+
+        // everything was added since the system last ran
+        world.run();
+        assert_eq!(moved(&world), &entities[..]);
+
+        let entities_and_components = &mut world.entities_and_components;
+        for entity in [entities[0], entities[2]] {
+            let (position,) = entities_and_components.get_components_mut::<(Position,)>(entity);
+            position.x += 1.0;
+        }
+        entities_and_components.get_components_mut::<(Velocity,)>(entities[1]);
+        entities_and_components.get_components_mut::<(Velocity,)>(without_position);
+        world.run();
+        assert_eq!(moved(&world), &[entities[0], entities[2]]);
+
+        world.run();
+        assert!(moved(&world).is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::*;
+
+impl EntitiesAndComponents {
+    /// Adds a reference to an entity that is shared by several owners, like an audio emitter used by several systems
+    /// The entity is only removed once release_entity has been called as many times as retain_entity
+    /// panics if the entity does not exist
+    pub fn retain_entity(&mut self, entity: Entity) {
+        if self.frozen_mutation() {
+            return;
+        }
+        if !self.does_entity_exist(entity) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        *self
+            .ref_counts
+            .entry(entity.entity_id)
+            .unwrap()
+            .or_insert(0) += 1;
+    }
+
+    /// Removes a reference to an entity added with retain_entity, and removes the entity when the last reference is released
+    /// Returns true if the entity was removed
+    /// Releasing an entity that is not retained does nothing, remove_entity still removes retained entities
+    pub fn release_entity(&mut self, entity: Entity) -> bool {
+        if self.frozen_mutation() {
+            return false;
+        }
+        let Some(count) = self.ref_counts.get_mut(entity.entity_id) else {
+            return false;
+        };
+
+        *count -= 1;
+        if *count == 0 {
+            self.remove_entity(entity);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Gets how many times an entity has been retained and not released
+    pub fn get_ref_count(&self, entity: Entity) -> u32 {
+        self.ref_counts.get(entity.entity_id).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_release_entity() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let emitter = entities_and_components.add_entity();
+        entities_and_components.retain_entity(emitter);
+        entities_and_components.retain_entity(emitter);
+        assert_eq!(entities_and_components.get_ref_count(emitter), 2);
+
+        assert!(!entities_and_components.release_entity(emitter));
+        assert!(entities_and_components.does_entity_exist(emitter));
+
+        assert!(entities_and_components.release_entity(emitter));
+        assert!(!entities_and_components.does_entity_exist(emitter));
+        assert_eq!(entities_and_components.get_ref_count(emitter), 0);
+
+        // entities that were never retained are left alone
+        let other = entities_and_components.add_entity();
+        assert!(!entities_and_components.release_entity(other));
+        assert!(entities_and_components.does_entity_exist(other));
+    }
+}
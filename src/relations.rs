@@ -0,0 +1,283 @@
+use crate::*;
+use std::marker::PhantomData;
+
+// unlinks an entity that is leaving the world from everything it is related to,
+// taking the relationship components out of its components
+pub(crate) type RelationCleanup = fn(&mut EntitiesAndComponents, Entity, &mut Map<dyn Any>);
+
+// the entities an entity is related to with R, stored as a component on the entity
+struct RelationsFrom<R> {
+    targets: Vec<Entity>,
+    relation: PhantomData<fn() -> R>,
+}
+
+// the entities that are related to an entity with R, the reverse index of RelationsFrom<R>
+struct RelationsTo<R> {
+    sources: Vec<Entity>,
+    relation: PhantomData<fn() -> R>,
+}
+
+impl EntitiesAndComponents {
+    /// Relates from to to with the relationship R, which is usually a zero sized marker type
+    /// The relationship can be looked up from both sides with relations_from and relations_to,
+    /// and it is removed automatically when either entity is removed
+    /// Relating the same entities twice does nothing
+    /// panics if either entity does not exist
+    pub fn relate<R: Component>(&mut self, from: Entity, to: Entity) {
+        if self.relations_from::<R>(from).contains(&to) {
+            return;
+        }
+        // checks that to exists before anything is changed
+        self.relations_to::<R>(to);
+        // before the components are added, so they never get a mask bit
+        self.linked_components
+            .insert(TypeId::of::<Box<RelationsFrom<R>>>());
+        self.linked_components
+            .insert(TypeId::of::<Box<RelationsTo<R>>>());
+
+        match self.try_get_components_mut::<(RelationsFrom<R>,)>(from) {
+            (Some(relations),) => relations.targets.push(to),
+            (None,) => self.add_component_to(
+                from,
+                RelationsFrom::<R> {
+                    targets: vec![to],
+                    relation: PhantomData,
+                },
+            ),
+        }
+        match self.try_get_components_mut::<(RelationsTo<R>,)>(to) {
+            (Some(relations),) => relations.sources.push(from),
+            (None,) => self.add_component_to(
+                to,
+                RelationsTo::<R> {
+                    sources: vec![from],
+                    relation: PhantomData,
+                },
+            ),
+        }
+        self.relation_cleanups
+            .entry(TypeId::of::<R>())
+            .or_insert(forget_relations_of::<R>);
+        self.entity_remaps
+            .entry(TypeId::of::<R>())
+            .or_insert(remap_relations_of::<R>);
+        self.cloners
+            .entry(TypeId::of::<Box<RelationsFrom<R>>>())
+            .or_insert(clone_relations_from::<R>);
+        self.cloners
+            .entry(TypeId::of::<Box<RelationsTo<R>>>())
+            .or_insert(clone_relations_to::<R>);
+    }
+
+    /// Removes the relationship R from from to to, if there is one
+    /// panics if either entity does not exist
+    pub fn unrelate<R: Component>(&mut self, from: Entity, to: Entity) {
+        if let (Some(relations),) = self.try_get_components_mut::<(RelationsFrom<R>,)>(from) {
+            relations.targets.retain(|target| *target != to);
+        }
+        if let (Some(relations),) = self.try_get_components_mut::<(RelationsTo<R>,)>(to) {
+            relations.sources.retain(|source| *source != from);
+        }
+    }
+
+    /// Gets the entities that entity is related to with R, in the order they were related
+    /// panics if the entity does not exist
+    pub fn relations_from<R: Component>(&self, entity: Entity) -> &[Entity] {
+        match self.try_get_components::<(RelationsFrom<R>,)>(entity) {
+            (Some(relations),) => &relations.targets,
+            (None,) => &[],
+        }
+    }
+
+    /// Gets the entities that are related to entity with R, in the order they were related
+    /// panics if the entity does not exist
+    pub fn relations_to<R: Component>(&self, entity: Entity) -> &[Entity] {
+        match self.try_get_components::<(RelationsTo<R>,)>(entity) {
+            (Some(relations),) => &relations.sources,
+            (None,) => &[],
+        }
+    }
+
+    // called with the components of an entity that is being removed or moved to another world
+    pub(crate) fn forget_relations(&mut self, entity: Entity, components: &mut Map<dyn Any>) {
+        if self.relation_cleanups.is_empty() {
+            return;
+        }
+
+        let cleanups = self
+            .relation_cleanups
+            .values()
+            .copied()
+            .collect::<Vec<RelationCleanup>>();
+        for cleanup in cleanups {
+            cleanup(self, entity, components);
+        }
+    }
+
+    fn forget_relation_index<T: Component>(&mut self, entity: Entity) {
+        if let Some(entities) = self
+            .entities_with_components
+            .get_mut(&TypeId::of::<Box<T>>())
+        {
+            entities.remove(entity.entity_id);
+        }
+    }
+}
+
+// clone_subset only keeps the relationships between the cloned entities
+fn clone_relations_from<R: Component>(
+    source: &EntitiesAndComponents,
+    entity: Entity,
+    dest: &mut EntitiesAndComponents,
+    new_entity: Entity,
+    entities: &HashMap<Entity, Entity>,
+) {
+    let targets = source
+        .relations_from::<R>(entity)
+        .iter()
+        .filter_map(|target| entities.get(target).copied())
+        .collect::<Vec<Entity>>();
+    if !targets.is_empty() {
+        dest.add_component_to(
+            new_entity,
+            RelationsFrom::<R> {
+                targets,
+                relation: PhantomData,
+            },
+        );
+    }
+}
+
+fn clone_relations_to<R: Component>(
+    source: &EntitiesAndComponents,
+    entity: Entity,
+    dest: &mut EntitiesAndComponents,
+    new_entity: Entity,
+    entities: &HashMap<Entity, Entity>,
+) {
+    let sources = source
+        .relations_to::<R>(entity)
+        .iter()
+        .filter_map(|source| entities.get(source).copied())
+        .collect::<Vec<Entity>>();
+    if !sources.is_empty() {
+        dest.add_component_to(
+            new_entity,
+            RelationsTo::<R> {
+                sources,
+                relation: PhantomData,
+            },
+        );
+    }
+}
+
+fn forget_relations_of<R: Component>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+    components: &mut Map<dyn Any>,
+) {
+    if let Some(relations) = components.remove::<Box<RelationsFrom<R>>>() {
+        entities_and_components.forget_relation_index::<RelationsFrom<R>>(entity);
+        for target in relations.targets {
+            if let Some(target_relations) = entities_and_components
+                .components
+                .get_mut(target.entity_id)
+                .and_then(|components| components.get_mut::<Box<RelationsTo<R>>>())
+            {
+                target_relations.sources.retain(|source| *source != entity);
+            }
+        }
+    }
+    if let Some(relations) = components.remove::<Box<RelationsTo<R>>>() {
+        entities_and_components.forget_relation_index::<RelationsTo<R>>(entity);
+        for source in relations.sources {
+            if let Some(source_relations) = entities_and_components
+                .components
+                .get_mut(source.entity_id)
+                .and_then(|components| components.get_mut::<Box<RelationsFrom<R>>>())
+            {
+                source_relations.targets.retain(|target| *target != entity);
+            }
+        }
+    }
+}
+
+fn remap_relations_of<R: Component>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity_ids: &EntityIds,
+) {
+    for components in entities_and_components.components.values_mut() {
+        if let Some(relations) = components.get_mut::<Box<RelationsFrom<R>>>() {
+            for target in relations.targets.iter_mut() {
+                *target = remap_entity(*target, entity_ids);
+            }
+        }
+        if let Some(relations) = components.get_mut::<Box<RelationsTo<R>>>() {
+            for source in relations.sources.iter_mut() {
+                *source = remap_entity(*source, entity_ids);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DockedTo;
+
+    struct Targeting;
+
+    #[test]
+    fn test_relations() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let station = entities_and_components.add_entity();
+        let ship_a = entities_and_components.add_entity();
+        let ship_b = entities_and_components.add_entity();
+
+        entities_and_components.relate::<DockedTo>(ship_a, station);
+        entities_and_components.relate::<DockedTo>(ship_b, station);
+        entities_and_components.relate::<DockedTo>(ship_b, station);
+        entities_and_components.relate::<Targeting>(ship_a, ship_b);
+
+        assert_eq!(
+            entities_and_components.relations_to::<DockedTo>(station),
+            &[ship_a, ship_b]
+        );
+        assert_eq!(
+            entities_and_components.relations_from::<DockedTo>(ship_a),
+            &[station]
+        );
+        assert_eq!(
+            entities_and_components.relations_from::<Targeting>(ship_a),
+            &[ship_b]
+        );
+        // relationships of different types are separate
+        assert!(entities_and_components
+            .relations_to::<DockedTo>(ship_b)
+            .is_empty());
+
+        entities_and_components.unrelate::<DockedTo>(ship_a, station);
+        assert_eq!(
+            entities_and_components.relations_to::<DockedTo>(station),
+            &[ship_b]
+        );
+
+        // ship_b is the source of one relationship and the target of another, both are cleaned up
+        entities_and_components.remove_entity(ship_b);
+        assert!(entities_and_components
+            .relations_to::<DockedTo>(station)
+            .is_empty());
+        assert!(entities_and_components
+            .relations_from::<Targeting>(ship_a)
+            .is_empty());
+
+        // removing the source cleans up the target's side
+        entities_and_components.relate::<DockedTo>(ship_a, station);
+        entities_and_components.remove_entity(ship_a);
+        assert!(entities_and_components
+            .relations_to::<DockedTo>(station)
+            .is_empty());
+    }
+}
@@ -0,0 +1,446 @@
+use crate::*;
+use std::collections::BTreeMap;
+
+/// Components that are sent over the network in a WorldPatch, encoded to bytes and back
+/// Register them on both sides with register_replicated
+pub trait Replicated: Component + Sized {
+    /// Encodes the component to bytes
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes a component from the bytes encode made
+    /// Returns None if the bytes are not a component, patches can come from anywhere so this shouldn't panic
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+// how to encode, decode, insert and remove one replicated component type without knowing the type
+#[derive(Clone, Copy)]
+pub(crate) struct Replicator {
+    encode: fn(&EntitiesAndComponents, Entity) -> Option<Vec<u8>>,
+    decode: fn(&[u8]) -> Option<Box<dyn Any>>,
+    insert: BoxedInserter,
+    remove: fn(&mut EntitiesAndComponents, Entity),
+}
+
+fn encode_component<T: Replicated>(
+    entities_and_components: &EntitiesAndComponents,
+    entity: Entity,
+) -> Option<Vec<u8>> {
+    get_stored::<T>(
+        &entities_and_components.storages,
+        &entities_and_components.components[entity.entity_id],
+        entity,
+    )
+    .map(T::encode)
+}
+
+fn decode_component<T: Replicated>(bytes: &[u8]) -> Option<Box<dyn Any>> {
+    T::decode(bytes).map(|component| Box::new(component) as Box<dyn Any>)
+}
+
+fn remove_component<T: Replicated>(
+    entities_and_components: &mut EntitiesAndComponents,
+    entity: Entity,
+) {
+    entities_and_components.remove_component_from::<T>(entity);
+}
+
+/// The replicated components of every entity with a stable id, encoded, taken with EntitiesAndComponents::snapshot
+/// A snapshot is the baseline a WorldPatch is computed against, the default snapshot is an empty world
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorldSnapshot {
+    // by stable id, then by net id
+    entities: BTreeMap<u64, BTreeMap<u16, Vec<u8>>>,
+}
+
+/// The changes that turn a world that matches a snapshot into another world, see EntitiesAndComponents::compute_patch
+/// It can be sent over the network with to_bytes and from_bytes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorldPatch {
+    despawned: Vec<u64>,
+    spawned: Vec<u64>,
+    // stable id and net id of every component that was removed
+    removed: Vec<(u64, u16)>,
+    // stable id, net id and the encoded component of every component that was added or changed
+    changed: Vec<(u64, u16, Vec<u8>)>,
+}
+
+impl WorldPatch {
+    /// Checks if the patch has no changes
+    pub fn is_empty(&self) -> bool {
+        self.despawned.is_empty()
+            && self.spawned.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// Encodes the patch to bytes, to be decoded with from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for stable_ids in [&self.despawned, &self.spawned] {
+            bytes.extend((stable_ids.len() as u32).to_le_bytes());
+            for stable_id in stable_ids {
+                bytes.extend(stable_id.to_le_bytes());
+            }
+        }
+        bytes.extend((self.removed.len() as u32).to_le_bytes());
+        for (stable_id, net_id) in &self.removed {
+            bytes.extend(stable_id.to_le_bytes());
+            bytes.extend(net_id.to_le_bytes());
+        }
+        bytes.extend((self.changed.len() as u32).to_le_bytes());
+        for (stable_id, net_id, component) in &self.changed {
+            bytes.extend(stable_id.to_le_bytes());
+            bytes.extend(net_id.to_le_bytes());
+            bytes.extend((component.len() as u32).to_le_bytes());
+            bytes.extend(component);
+        }
+        bytes
+    }
+
+    /// Decodes a patch encoded with to_bytes
+    /// Returns EcsError::InvalidPatch if the bytes are not a whole patch
+    pub fn from_bytes(bytes: &[u8]) -> Result<WorldPatch, EcsError> {
+        let mut reader = PatchReader { bytes };
+        let patch = reader.read_patch().ok_or(EcsError::InvalidPatch)?;
+        if !reader.bytes.is_empty() {
+            return Err(EcsError::InvalidPatch);
+        }
+        Ok(patch)
+    }
+}
+
+// reads a patch from the front of the bytes, None if they run out
+struct PatchReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PatchReader<'a> {
+    fn read<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (read, rest) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = rest;
+        Some(*read)
+    }
+
+    fn read_slice(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (read, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(read)
+    }
+
+    fn read_len(&mut self) -> Option<usize> {
+        self.read().map(|len| u32::from_le_bytes(len) as usize)
+    }
+
+    fn read_u64(&mut self) -> Option<u64> {
+        self.read().map(u64::from_le_bytes)
+    }
+
+    fn read_u16(&mut self) -> Option<u16> {
+        self.read().map(u16::from_le_bytes)
+    }
+
+    fn read_patch(&mut self) -> Option<WorldPatch> {
+        let mut patch = WorldPatch::default();
+        for stable_ids in [&mut patch.despawned, &mut patch.spawned] {
+            for _ in 0..self.read_len()? {
+                stable_ids.push(self.read_u64()?);
+            }
+        }
+        for _ in 0..self.read_len()? {
+            patch.removed.push((self.read_u64()?, self.read_u16()?));
+        }
+        for _ in 0..self.read_len()? {
+            let stable_id = self.read_u64()?;
+            let net_id = self.read_u16()?;
+            let len = self.read_len()?;
+            patch
+                .changed
+                .push((stable_id, net_id, self.read_slice(len)?.to_vec()));
+        }
+        Some(patch)
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Registers a component type to be included in snapshots and patches under a net id, see register_net_id
+    /// Both sides have to register the same types with the same ids
+    /// panics if the id is already used by another type, or the type already has another id
+    pub fn register_replicated<T: Replicated>(&mut self, net_id: u16) {
+        self.register_net_id::<T>(net_id);
+        self.replicators.insert(
+            net_id,
+            Replicator {
+                encode: encode_component::<T>,
+                decode: decode_component::<T>,
+                insert: add_boxed_component_of::<T>,
+                remove: remove_component::<T>,
+            },
+        );
+    }
+
+    /// Takes a snapshot of the replicated components of every entity with a stable id, including disabled ones
+    /// Entities without a stable id and components that are not registered with register_replicated are left out
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let mut snapshot = WorldSnapshot::default();
+        for (stable_id, entity) in self.stable_ids.iter() {
+            let components = self
+                .replicators
+                .iter()
+                .filter_map(|(net_id, replicator)| {
+                    (replicator.encode)(self, entity).map(|component| (*net_id, component))
+                })
+                .collect();
+            snapshot.entities.insert(stable_id, components);
+        }
+        snapshot
+    }
+
+    /// Computes the patch that turns a world matching the baseline into this world
+    /// Components are compared by their encoded bytes, so unchanged components are not included
+    pub fn compute_patch(&self, baseline: &WorldSnapshot) -> WorldPatch {
+        let current = self.snapshot();
+        let mut patch = WorldPatch::default();
+        for stable_id in baseline.entities.keys() {
+            if !current.entities.contains_key(stable_id) {
+                patch.despawned.push(*stable_id);
+            }
+        }
+        let no_components = BTreeMap::new();
+        for (stable_id, components) in &current.entities {
+            let baseline_components = match baseline.entities.get(stable_id) {
+                Some(baseline_components) => baseline_components,
+                None => {
+                    patch.spawned.push(*stable_id);
+                    &no_components
+                }
+            };
+            for net_id in baseline_components.keys() {
+                if !components.contains_key(net_id) {
+                    patch.removed.push((*stable_id, *net_id));
+                }
+            }
+            for (net_id, component) in components {
+                if baseline_components.get(net_id) != Some(component) {
+                    patch.changed.push((*stable_id, *net_id, component.clone()));
+                }
+            }
+        }
+        patch
+    }
+
+    /// Applies a patch from compute_patch, entities are found by their stable ids and spawned if they don't exist
+    /// Removing an entity also removes its children, like remove_entity
+    /// Returns EcsError::UnknownNetId if the patch has a component whose net id was not registered with register_replicated,
+    /// and EcsError::InvalidComponent if a component can't be decoded, in which case nothing is changed
+    pub fn apply_patch(&mut self, patch: &WorldPatch) -> Result<(), EcsError> {
+        // everything is checked and decoded first, so a bad patch doesn't leave the world half patched
+        for (_, net_id) in &patch.removed {
+            self.replicator(*net_id)?;
+        }
+        let changed = patch
+            .changed
+            .iter()
+            .map(|(stable_id, net_id, component)| {
+                let replicator = self.replicator(*net_id)?;
+                let component = (replicator.decode)(component)
+                    .ok_or(EcsError::InvalidComponent { net_id: *net_id })?;
+                Ok((*stable_id, component, replicator.insert))
+            })
+            .collect::<Result<Vec<(u64, Box<dyn Any>, BoxedInserter)>, EcsError>>()?;
+
+        for stable_id in &patch.despawned {
+            // it may have been removed already as the child of another despawned entity
+            if let Some(entity) = self.get_entity_by_stable_id(*stable_id) {
+                self.remove_entity(entity);
+            }
+        }
+        for stable_id in &patch.spawned {
+            self.get_or_spawn_stable(*stable_id);
+        }
+        for (stable_id, net_id) in &patch.removed {
+            let replicator = self.replicator(*net_id)?;
+            if let Some(entity) = self.get_entity_by_stable_id(*stable_id) {
+                (replicator.remove)(self, entity);
+            }
+        }
+        for (stable_id, component, insert) in changed {
+            let entity = self.get_or_spawn_stable(stable_id);
+            insert(self, entity, component);
+        }
+        Ok(())
+    }
+
+    fn replicator(&self, net_id: u16) -> Result<Replicator, EcsError> {
+        self.replicators
+            .get(&net_id)
+            .copied()
+            .ok_or(EcsError::UnknownNetId { net_id })
+    }
+}
+
+impl World {
+    /// Takes a snapshot of the replicated components, see EntitiesAndComponents::snapshot
+    pub fn snapshot(&self) -> WorldSnapshot {
+        self.entities_and_components.snapshot()
+    }
+
+    /// Computes the patch that turns a world matching the baseline into this world,
+    /// see EntitiesAndComponents::compute_patch
+    pub fn compute_patch(&self, baseline: &WorldSnapshot) -> WorldPatch {
+        self.entities_and_components.compute_patch(baseline)
+    }
+
+    /// Applies a patch from compute_patch, see EntitiesAndComponents::apply_patch
+    pub fn apply_patch(&mut self, patch: &WorldPatch) -> Result<(), EcsError> {
+        self.entities_and_components.apply_patch(patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    impl Replicated for Position {
+        fn encode(&self) -> Vec<u8> {
+            let mut bytes = self.x.to_le_bytes().to_vec();
+            bytes.extend(self.y.to_le_bytes());
+            bytes
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            let (x, y) = bytes.split_first_chunk::<4>()?;
+            Some(Position {
+                x: f32::from_le_bytes(*x),
+                y: f32::from_le_bytes(y.try_into().ok()?),
+            })
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    impl Replicated for Health {
+        fn encode(&self) -> Vec<u8> {
+            self.0.to_le_bytes().to_vec()
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            Some(Health(u32::from_le_bytes(bytes.try_into().ok()?)))
+        }
+    }
+
+    fn replicated_world() -> World {
+        let mut world = World::new();
+        world
+            .entities_and_components
+            .register_replicated::<Position>(1);
+        world
+            .entities_and_components
+            .register_replicated::<Health>(2);
+        world
+    }
+
+    #[test]
+    fn test_patch_round_trip() {
+        let mut server = replicated_world();
+        let entities_and_components = &mut server.entities_and_components;
+        let player =
+            entities_and_components.add_entity_with((Position { x: 0.0, y: 0.0 }, Health(100)));
+        entities_and_components.set_stable_id(player, 1);
+        let crate_entity = entities_and_components.add_entity_with((Position { x: 5.0, y: 5.0 },));
+        entities_and_components.set_stable_id(crate_entity, 2);
+        let enemy = entities_and_components.add_entity_with((Health(30),));
+        entities_and_components.set_stable_id(enemy, 3);
+        // entities without a stable id are not replicated
+        entities_and_components.add_entity_with((Health(1),));
+
+        // the client starts out as a copy of the baseline
+        let baseline = server.snapshot();
+        let mut client = replicated_world();
+        client
+            .apply_patch(&server.compute_patch(&WorldSnapshot::default()))
+            .unwrap();
+        assert_eq!(client.snapshot(), baseline);
+        assert_eq!(client.entities_and_components.get_entity_count(), 3);
+
+        let entities_and_components = &mut server.entities_and_components;
+        let (position,) = entities_and_components.get_components_mut::<(Position,)>(player);
+        position.x = 2.0;
+        entities_and_components.remove_component_from::<Health>(player);
+        entities_and_components.remove_entity(crate_entity);
+        entities_and_components.add_component_to(enemy, Position { x: 9.0, y: 0.0 });
+        let pickup = entities_and_components.add_entity_with((Health(25),));
+        entities_and_components.set_stable_id(pickup, 4);
+
+        let patch = server.compute_patch(&baseline);
+        // the enemy's health did not change, so it is not sent again
+        assert!(!patch.changed.contains(&(3, 2, Health(30).encode())));
+        let patch = WorldPatch::from_bytes(&patch.to_bytes()).unwrap();
+        client.apply_patch(&patch).unwrap();
+
+        assert_eq!(client.snapshot(), server.snapshot());
+        let client_entities_and_components = &client.entities_and_components;
+        assert_eq!(client_entities_and_components.get_entity_count(), 3);
+        assert_eq!(
+            client_entities_and_components.get_entity_by_stable_id(2),
+            None
+        );
+        let client_player = client_entities_and_components
+            .get_entity_by_stable_id(1)
+            .unwrap();
+        let (position, health) =
+            client_entities_and_components.try_get_components::<(Position, Health)>(client_player);
+        assert_eq!(*position.unwrap(), Position { x: 2.0, y: 0.0 });
+        assert!(health.is_none());
+
+        // nothing has changed since the current state
+        assert!(server.compute_patch(&server.snapshot()).is_empty());
+        assert_eq!(
+            WorldPatch::from_bytes(&patch.to_bytes()[1..]),
+            Err(EcsError::InvalidPatch)
+        );
+    }
+
+    #[test]
+    fn test_bad_patch_changes_nothing() {
+        let mut client = replicated_world();
+        let entities_and_components = &mut client.entities_and_components;
+        let player = entities_and_components.add_entity_with((Health(100),));
+        entities_and_components.set_stable_id(player, 1);
+        let before = entities_and_components.snapshot();
+
+        let unknown = WorldPatch {
+            despawned: vec![1],
+            changed: vec![(2, 9, Vec::new())],
+            ..Default::default()
+        };
+        assert_eq!(
+            entities_and_components.apply_patch(&unknown),
+            Err(EcsError::UnknownNetId { net_id: 9 })
+        );
+        // the health is one byte short
+        let invalid = WorldPatch {
+            removed: vec![(1, 2)],
+            changed: vec![
+                (1, 1, Position { x: 1.0, y: 2.0 }.encode()),
+                (2, 2, vec![0; 3]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            entities_and_components.apply_patch(&invalid),
+            Err(EcsError::InvalidComponent { net_id: 2 })
+        );
+        assert_eq!(entities_and_components.snapshot(), before);
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+    }
+}
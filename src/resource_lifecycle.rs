@@ -0,0 +1,244 @@
+use crate::*;
+
+impl EntitiesAndComponents {
+    /// Calls early_update and then update on every resource, in order of priority
+    pub(crate) fn early_update_resources(&mut self, paused: bool) {
+        for type_id in self.resource_ids_by_priority(paused) {
+            if let Some(resource) = self.resources.get_mut(&type_id) {
+                resource.early_update();
+                resource.update();
+            }
+        }
+    }
+
+    /// Calls update_with_world on every resource, in order of priority
+    /// Each resource is taken out of the world while it is updated, so it can have the rest of the world mutably
+    pub(crate) fn update_resources_with_world(&mut self, paused: bool) {
+        for type_id in self.resource_ids_by_priority(paused) {
+            // it may have been removed by a resource updated before it
+            let Some(mut resource) = self.resources.remove(&type_id) else {
+                continue;
+            };
+            resource.update_with_world(self);
+            // if the resource added a new one of its own type while it was taken out, the new one is kept
+            self.resources.entry(type_id).or_insert(resource);
+        }
+    }
+
+    /// Calls late_update on every resource, in order of priority
+    pub(crate) fn late_update_resources(&mut self, paused: bool) {
+        for type_id in self.resource_ids_by_priority(paused) {
+            if let Some(resource) = self.resources.get_mut(&type_id) {
+                resource.late_update();
+            }
+        }
+    }
+
+    /// Runs every resource through the same phases as World::run, early_update and update, then update_with_world,
+    /// then late_update, without running any systems
+    /// This keeps resources like timers going while the systems are skipped, so the paused state is ignored
+    /// Commands queued by update_with_world are flushed before late_update, like in World::run
+    pub fn update_resources(&mut self) {
+        self.early_update_resources(false);
+        self.update_resources_with_world(false);
+        self.flush();
+        self.late_update_resources(false);
+    }
+
+    /// Calls f with every resource in order of priority, so a custom scheduler can update them in its own way
+    /// The resources can be downcast to their types with as_any_mut
+    pub fn for_each_resource_mut(&mut self, mut f: impl FnMut(&mut dyn ResourceWrapper)) {
+        for type_id in self.resource_ids_by_priority(false) {
+            if let Some(resource) = self.resources.get_mut(&type_id) {
+                f(&mut **resource);
+            }
+        }
+    }
+
+    // while paused only the resources that run while paused are updated
+    fn resource_ids_by_priority(&self, paused: bool) -> Vec<TypeId> {
+        let mut resources = self
+            .resources
+            .iter()
+            .filter(|(_, resource)| !paused || resource.runs_while_paused())
+            .map(|(type_id, resource)| (resource.priority(), *type_id))
+            .collect::<Vec<(i32, TypeId)>>();
+        resources.sort_by_key(|(priority, _)| *priority);
+        resources.into_iter().map(|(_, type_id)| type_id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<&'static str>>>;
+
+    struct Score;
+
+    struct LoggingResource {
+        log: Log,
+    }
+
+    impl Resource for LoggingResource {
+        fn early_update(&mut self) {
+            self.log.lock().unwrap().push("early_update");
+        }
+
+        fn update(&mut self) {
+            self.log.lock().unwrap().push("update");
+        }
+
+        fn update_with_world(&mut self, world: &mut EntitiesAndComponents) {
+            self.log.lock().unwrap().push("update_with_world");
+            world.add_entity_with((Score,));
+        }
+
+        fn late_update(&mut self) {
+            self.log.lock().unwrap().push("late_update");
+        }
+    }
+
+    // updated before LoggingResource in every phase
+    struct FirstResource {
+        log: Log,
+    }
+
+    impl Resource for FirstResource {
+        fn early_update(&mut self) {
+            self.log.lock().unwrap().push("first early_update");
+        }
+
+        fn update_with_world(&mut self, _world: &mut EntitiesAndComponents) {
+            self.log.lock().unwrap().push("first update_with_world");
+        }
+
+        fn late_update(&mut self) {
+            self.log.lock().unwrap().push("first late_update");
+        }
+
+        fn priority(&self) -> i32 {
+            -1
+        }
+    }
+
+    struct LoggingSystem {
+        log: Log,
+    }
+
+    impl System for LoggingSystem {
+        fn run(&mut self, engine: &mut EntitiesAndComponents) {
+            // the entity spawned by update_with_world is already there
+            assert_eq!(engine.get_entity_count_with_component::<Score>(), 1);
+            self.log.lock().unwrap().push("system");
+        }
+    }
+
+    struct Counter {
+        count: u32,
+    }
+
+    impl Resource for Counter {
+        fn update(&mut self) {
+            self.count += 1;
+        }
+    }
+
+    struct Timer {
+        seconds: f32,
+    }
+
+    impl Resource for Timer {
+        fn update(&mut self) {
+            self.seconds += 0.5;
+        }
+    }
+
+    #[test]
+    fn test_for_each_resource_mut() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        entities_and_components.add_resource(Counter { count: 0 });
+        entities_and_components.add_resource(Timer { seconds: 0.0 });
+
+        // a custom loop that updates every resource twice and skips the world
+        for _ in 0..2 {
+            entities_and_components.for_each_resource_mut(|resource| resource.update());
+        }
+
+        let counter = entities_and_components.get_resource::<Counter>();
+        assert_eq!(counter.unwrap().count, 2);
+        let timer = entities_and_components.get_resource::<Timer>();
+        assert_eq!(timer.unwrap().seconds, 1.0);
+
+        let mut visited = 0;
+        entities_and_components.for_each_resource_mut(|resource| {
+            if let Some(counter) = resource.as_any_mut().downcast_mut::<Counter>() {
+                counter.count = 10;
+            }
+            visited += 1;
+        });
+        assert_eq!(visited, 2);
+        let counter = entities_and_components.get_resource::<Counter>();
+        assert_eq!(counter.unwrap().count, 10);
+    }
+
+    #[test]
+    fn test_update_resources() {
+        let log = Log::default();
+        let mut world = World::new();
+        world
+            .entities_and_components
+            .add_resource(Timer { seconds: 0.0 });
+        world
+            .entities_and_components
+            .add_resource(LoggingResource { log: log.clone() });
+        world.add_system(LoggingSystem { log: log.clone() });
+        world.set_paused(true);
+
+        world.entities_and_components.update_resources();
+        world.entities_and_components.update_resources();
+
+        let timer = world.entities_and_components.get_resource::<Timer>();
+        assert_eq!(timer.unwrap().seconds, 1.0);
+        // the system never ran and the world didn't advance a frame
+        assert!(!log.lock().unwrap().contains(&"system"));
+        assert_eq!(log.lock().unwrap().len(), 8);
+        assert_eq!(world.frame(), 0);
+    }
+
+    #[test]
+    fn test_resource_lifecycle_order() {
+        let log = Log::default();
+        let mut world = World::new();
+        world
+            .entities_and_components
+            .add_resource(LoggingResource { log: log.clone() });
+        world
+            .entities_and_components
+            .add_resource(FirstResource { log: log.clone() });
+        world.add_system(LoggingSystem { log: log.clone() });
+
+        world.run();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "first early_update",
+                "early_update",
+                "update",
+                "first update_with_world",
+                "update_with_world",
+                "system",
+                "first late_update",
+                "late_update",
+            ]
+        );
+        // the resource was put back after its update with the world
+        assert!(world
+            .entities_and_components
+            .get_resource::<LoggingResource>()
+            .is_some());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::*;
+use std::fmt::Write;
+
+// quotes a name so it can be used as a label in DOT
+fn dot_string(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// adds an edge from every system in a group to every system in the group after it
+fn write_group_edges(dot: &mut String, phase: &str, groups: &[Vec<usize>]) {
+    for pair in groups.windows(2) {
+        for from in &pair[0] {
+            for to in &pair[1] {
+                writeln!(dot, "    {phase}_{from} -> {phase}_{to};").unwrap();
+            }
+        }
+    }
+}
+
+impl World {
+    /// Describes the systems and the order they run in as a Graphviz DOT graph, to be rendered with a tool like dot
+    /// Every phase of run is a cluster, and an edge means a system has to finish before the other one starts
+    /// Systems without an edge between them in a phase can run in parallel
+    pub fn export_schedule_dot(&self) -> String {
+        let systems = self.system_info();
+        let mut dot = String::from("digraph schedule {\n    rankdir=LR;\n");
+
+        // presteps run in tiers, every prestep in a tier has to finish before the next tier starts
+        let mut presteps = (0..systems.len())
+            .filter(|i| systems[*i].implements_prestep)
+            .collect::<Vec<usize>>();
+        presteps.sort_by_key(|i| systems[*i].prestep_tier);
+        let prestep_tiers = presteps
+            .chunk_by(|i, j| systems[*i].prestep_tier == systems[*j].prestep_tier)
+            .map(|tier| tier.to_vec())
+            .collect::<Vec<Vec<usize>>>();
+        dot.push_str("    subgraph cluster_prestep {\n        label=\"prestep\";\n");
+        for i in &presteps {
+            let label = format!("{} (tier {})", systems[*i].name, systems[*i].prestep_tier);
+            writeln!(dot, "        prestep_{i} [label={}];", dot_string(&label)).unwrap();
+        }
+        dot.push_str("    }\n");
+        write_group_edges(&mut dot, "prestep", &prestep_tiers);
+
+        // single_entity_step runs the systems one after another on every entity
+        let single_entity_steps = (0..systems.len())
+            .filter(|i| systems[*i].implements_single_entity_step)
+            .map(|i| vec![i])
+            .collect::<Vec<Vec<usize>>>();
+        dot.push_str(
+            "    subgraph cluster_single_entity_step {\n        label=\"single_entity_step\";\n",
+        );
+        for i in single_entity_steps.iter().flatten() {
+            writeln!(
+                dot,
+                "        single_entity_step_{i} [label={}];",
+                dot_string(&systems[*i].name)
+            )
+            .unwrap();
+        }
+        dot.push_str("    }\n");
+        write_group_edges(&mut dot, "single_entity_step", &single_entity_steps);
+
+        // consecutive read only systems run in parallel, the rest run one at a time in order
+        let indices = (0..systems.len()).collect::<Vec<usize>>();
+        let run_groups = indices
+            .chunk_by(|i, j| systems[*i].is_read_only && systems[*j].is_read_only)
+            .map(|group| group.to_vec())
+            .collect::<Vec<Vec<usize>>>();
+        dot.push_str("    subgraph cluster_run {\n        label=\"run\";\n");
+        for (i, system) in systems.iter().enumerate() {
+            let label = if system.is_read_only {
+                format!("{} (read only)", system.name)
+            } else {
+                system.name.clone()
+            };
+            writeln!(dot, "        run_{i} [label={}];", dot_string(&label)).unwrap();
+        }
+        dot.push_str("    }\n");
+        write_group_edges(&mut dot, "run", &run_groups);
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BuildGrid {}
+
+    impl System for BuildGrid {
+        fn implements_prestep(&self) -> bool {
+            true
+        }
+    }
+
+    struct Steer {}
+
+    impl System for Steer {
+        fn implements_prestep(&self) -> bool {
+            true
+        }
+
+        fn prestep_tier(&self) -> u8 {
+            1
+        }
+    }
+
+    struct Draw {}
+
+    impl System for Draw {
+        fn is_read_only(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_export_schedule_dot() {
+        let mut world = World::new();
+        world.add_system_named("steer", Steer {});
+        world.add_system_named("build \"grid\"", BuildGrid {});
+        world.add_system_named("draw", Draw {});
+
+        let dot = world.export_schedule_dot();
+        assert!(dot.starts_with("digraph schedule {"));
+        assert!(dot.contains("prestep_0 [label=\"steer (tier 1)\"];"));
+        assert!(dot.contains("prestep_1 [label=\"build \\\"grid\\\" (tier 0)\"];"));
+        assert!(dot.contains("run_2 [label=\"draw (read only)\"];"));
+        // steer is in a later tier, so it runs after build grid even though it was added first
+        assert!(dot.contains("prestep_1 -> prestep_0;"));
+        assert!(!dot.contains("prestep_0 -> prestep_1;"));
+        // the run phase goes in the order the systems were added
+        assert!(dot.contains("run_0 -> run_1;"));
+        assert!(dot.contains("run_1 -> run_2;"));
+        assert!(!dot.contains("single_entity_step_"));
+    }
+}
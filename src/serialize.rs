@@ -0,0 +1,121 @@
+use crate::*;
+
+/// Receives the entities of a world and their components from serialize_filtered, and writes them in its own format
+/// The components are passed to ComponentVisitor::visit, where they can be downcast to the types the format knows
+pub trait EntitySerializer: ComponentVisitor {
+    /// Called before the components of every entity are visited
+    fn begin_entity(&mut self, entity: Entity);
+}
+
+impl EntitiesAndComponents {
+    /// Passes every entity, including disabled ones, to the serializer with only the components whose type passes the filter,
+    /// for example to replicate only the components registered with register_net_id and keep local ones out
+    /// The filter is called with TypeId::of::<T>(), and the components of each entity come in the order of type_ids_on_entity
+    pub fn serialize_filtered<S: EntitySerializer>(
+        &self,
+        serializer: &mut S,
+        filter: impl Fn(TypeId) -> bool,
+    ) {
+        struct Filtered<'a, S, F> {
+            serializer: &'a mut S,
+            filter: F,
+        }
+
+        impl<S: EntitySerializer, F: Fn(TypeId) -> bool> ComponentVisitor for Filtered<'_, S, F> {
+            fn visit(&mut self, type_id: TypeId, name: &'static str, component: &dyn Any) {
+                if (self.filter)(type_id) {
+                    self.serializer.visit(type_id, name, component);
+                }
+            }
+        }
+
+        let mut filtered = Filtered { serializer, filter };
+        for entity in self.iter_entities_include_disabled() {
+            filtered.serializer.begin_entity(entity);
+            self.visit_components(entity, &mut filtered);
+        }
+    }
+}
+
+impl World {
+    /// Passes every entity to the serializer with only the components that pass the filter,
+    /// see EntitiesAndComponents::serialize_filtered
+    pub fn serialize_filtered<S: EntitySerializer>(
+        &self,
+        serializer: &mut S,
+        filter: impl Fn(TypeId) -> bool,
+    ) {
+        self.entities_and_components
+            .serialize_filtered(serializer, filter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position {
+        x: f32,
+    }
+
+    struct Health(u32);
+
+    // only lives on this machine, so it should never be sent
+    struct LocalCamera;
+
+    // writes one line per entity, with the components it knows how to write
+    #[derive(Default)]
+    struct TextSerializer {
+        output: String,
+    }
+
+    impl ComponentVisitor for TextSerializer {
+        fn visit(&mut self, _type_id: TypeId, _name: &'static str, component: &dyn Any) {
+            if let Some(position) = component.downcast_ref::<Position>() {
+                self.output += &format!(" Position({})", position.x);
+            } else if let Some(health) = component.downcast_ref::<Health>() {
+                self.output += &format!(" Health({})", health.0);
+            } else if component.is::<LocalCamera>() {
+                self.output += " LocalCamera";
+            }
+        }
+    }
+
+    impl EntitySerializer for TextSerializer {
+        fn begin_entity(&mut self, _entity: Entity) {
+            if !self.output.is_empty() {
+                self.output += "\n";
+            }
+            self.output += "entity:";
+        }
+    }
+
+    #[test]
+    fn test_serialize_filtered() {
+        let mut world = World::new();
+        world.entities_and_components.add_entity_with((
+            Position { x: 1.0 },
+            Health(10),
+            LocalCamera,
+        ));
+        world
+            .entities_and_components
+            .add_entity_with((Position { x: 2.0 },));
+
+        let mut everything = TextSerializer::default();
+        world.serialize_filtered(&mut everything, |_| true);
+        assert_eq!(
+            everything.output,
+            "entity: Position(1) Health(10) LocalCamera\nentity: Position(2)"
+        );
+
+        let mut replicated = TextSerializer::default();
+        world.serialize_filtered(&mut replicated, |type_id| {
+            type_id != TypeId::of::<LocalCamera>()
+        });
+        assert_eq!(
+            replicated.output,
+            "entity: Position(1) Health(10)\nentity: Position(2)"
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use crate::*;
+
+/// Maps stable ids to entities and back
+/// Entity handles are only meaningful inside one world, stable ids are chosen by the user
+/// so they can refer to the same entity across worlds, for example on a server and its clients
+pub(crate) struct StableIds {
+    entities: FxHashMap<u64, Entity>,
+    // where stable_ids[entity_id]
+    stable_ids: SecondaryMap<DefaultKey, u64>,
+}
+
+impl StableIds {
+    pub(crate) fn new() -> Self {
+        StableIds {
+            entities: FxHashMap::default(),
+            stable_ids: SecondaryMap::new(),
+        }
+    }
+
+    pub(crate) fn entity_removed(&mut self, entity_id: DefaultKey) {
+        if let Some(stable_id) = self.stable_ids.remove(entity_id) {
+            self.entities.remove(&stable_id);
+        }
+    }
+
+    // every stable id and the entity with it, in no particular order
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u64, Entity)> + '_ {
+        self.entities
+            .iter()
+            .map(|(stable_id, entity)| (*stable_id, *entity))
+    }
+
+    pub(crate) fn remap(&mut self, entity_ids: &EntityIds) {
+        remap_keys(&mut self.stable_ids, entity_ids);
+        for entity in self.entities.values_mut() {
+            *entity = remap_entity(*entity, entity_ids);
+        }
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Gives an entity a stable id, replacing any stable id it had before
+    /// panics if the entity does not exist or another entity already has the stable id
+    pub fn set_stable_id(&mut self, entity: Entity, stable_id: u64) {
+        if self.frozen_mutation() {
+            return;
+        }
+        if !self.does_entity_exist(entity) {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        }
+        if let Some(other) = self.stable_ids.entities.get(&stable_id) {
+            if *other != entity {
+                panic!("Stable ID {stable_id} is already used by {other:?}");
+            }
+            return;
+        }
+
+        self.stable_ids.entity_removed(entity.entity_id);
+        self.stable_ids.entities.insert(stable_id, entity);
+        self.stable_ids
+            .stable_ids
+            .insert(entity.entity_id, stable_id);
+    }
+
+    /// Gets the stable id of an entity, if it has one
+    pub fn get_stable_id(&self, entity: Entity) -> Option<u64> {
+        self.stable_ids.stable_ids.get(entity.entity_id).copied()
+    }
+
+    /// Gets the entity with a stable id, if there is one
+    pub fn get_entity_by_stable_id(&self, stable_id: u64) -> Option<Entity> {
+        self.stable_ids.entities.get(&stable_id).copied()
+    }
+
+    /// Gets the entity with a stable id, or spawns a new entity with that stable id if there is none
+    /// This is useful for applying state received over the network for entities that may not exist locally yet
+    pub fn get_or_spawn_stable(&mut self, stable_id: u64) -> Entity {
+        match self.get_entity_by_stable_id(stable_id) {
+            Some(entity) => entity,
+            None => {
+                let entity = self.add_entity();
+                self.set_stable_id(entity, stable_id);
+                entity
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_spawn_stable() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let entity = entities_and_components.get_or_spawn_stable(42);
+        assert_eq!(entities_and_components.get_or_spawn_stable(42), entity);
+        assert_eq!(entities_and_components.get_entity_count(), 1);
+        assert_eq!(entities_and_components.get_stable_id(entity), Some(42));
+
+        let other = entities_and_components.get_or_spawn_stable(7);
+        assert_ne!(other, entity);
+        assert_eq!(entities_and_components.get_entity_count(), 2);
+
+        entities_and_components.remove_entity(entity);
+        assert_eq!(entities_and_components.get_entity_by_stable_id(42), None);
+    }
+}
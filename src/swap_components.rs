@@ -0,0 +1,241 @@
+use crate::*;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+// swaps the entries of the types back between two maps that have just been swapped, so they stay where they were
+pub(crate) fn swap_entries_back<V, S: BuildHasher>(
+    a: &mut HashMap<TypeId, V, S>,
+    b: &mut HashMap<TypeId, V, S>,
+    type_ids: &FxHashSet<TypeId>,
+) {
+    for type_id in type_ids {
+        let entry_a = a.remove(type_id);
+        let entry_b = b.remove(type_id);
+        if let Some(entry_b) = entry_b {
+            a.insert(*type_id, entry_b);
+        }
+        if let Some(entry_a) = entry_a {
+            b.insert(*type_id, entry_a);
+        }
+    }
+}
+
+impl EntitiesAndComponents {
+    /// Exchanges every component of two entities, for example to swap the bodies of two characters,
+    /// the handles stay the same but each entity now has the other's components
+    /// Parents, children and relationships point at handles, so they stay with the handles
+    /// and each entity keeps its place in the hierarchy
+    /// Every swapped component counts as changed, the countdowns of temporary tags move with the tags,
+    /// and the swap is recorded as one change for undo_last
+    /// If either entity does not exist, the error handler is called, or it panics if there is no handler
+    pub fn swap_all_components(&mut self, a: Entity, b: Entity) {
+        if self.frozen_mutation() {
+            return;
+        }
+        for entity in [a, b] {
+            if !self.does_entity_exist(entity) {
+                return self.report_error(EcsError::EntityNotFound { entity });
+            }
+        }
+        if a == b {
+            return;
+        }
+
+        let old_types = self.archetype_types(a).zip(self.archetype_types(b));
+        for entity in [a, b] {
+            self.count_disabled_entity(entity, false);
+        }
+        let mut components_a = std::mem::replace(&mut self.components[a.entity_id], Map::new());
+        let mut components_b = std::mem::replace(&mut self.components[b.entity_id], Map::new());
+        std::mem::swap(&mut components_a, &mut components_b);
+        // SAFETY: every entry is moved under the TypeId it was stored under
+        unsafe {
+            swap_entries_back(
+                components_a.as_raw_mut(),
+                components_b.as_raw_mut(),
+                &self.linked_components,
+            );
+        }
+        self.components[a.entity_id] = components_a;
+        self.components[b.entity_id] = components_b;
+        for storage in self.storages.values_mut() {
+            storage.swap(a, b);
+        }
+        for history in self.histories.values_mut() {
+            history.swap(a.entity_id, b.entity_id);
+        }
+        for temporary_tags in self.temporary_tags.values_mut() {
+            let frames_a = temporary_tags.frames_left.remove(a.entity_id);
+            let frames_b = temporary_tags.frames_left.remove(b.entity_id);
+            if let Some(frames_b) = frames_b {
+                temporary_tags.frames_left.insert(a.entity_id, frames_b);
+            }
+            if let Some(frames_a) = frames_a {
+                temporary_tags.frames_left.insert(b.entity_id, frames_a);
+            }
+        }
+
+        let mut type_ids = self.components[a.entity_id]
+            .as_raw()
+            .keys()
+            .chain(self.components[b.entity_id].as_raw().keys())
+            .filter(|type_id| !self.linked_components.contains(*type_id))
+            .copied()
+            .collect::<FxHashSet<TypeId>>();
+        for storage in self.storages.values() {
+            type_ids.insert(storage.index_type_id());
+        }
+        for type_id in &type_ids {
+            let Some(entities) = self.entities_with_components.get_mut(type_id) else {
+                continue;
+            };
+            let had_a = entities.remove(a.entity_id).is_some();
+            let had_b = entities.remove(b.entity_id).is_some();
+            if had_b {
+                entities.insert(a.entity_id, a);
+            }
+            if had_a {
+                entities.insert(b.entity_id, b);
+            }
+        }
+
+        for entity in [a, b] {
+            self.count_disabled_entity(entity, true);
+        }
+        self.change_tracker
+            .swap_entities(a.entity_id, b.entity_id, &self.linked_components);
+        self.component_masks
+            .swap_entities(a.entity_id, b.entity_id, &self.linked_components);
+        if !self.entity_refs.is_empty() {
+            self.swap_entity_ref_holders(a, b);
+        }
+        self.archetype_generation += 1;
+        self.refresh_archetype(a);
+        self.refresh_archetype(b);
+        // the entities trade archetypes, so no archetype is left empty
+        if let Some((old_types_a, old_types_b)) = old_types {
+            self.archetype_replaced(a, old_types_a);
+            self.archetype_replaced(b, old_types_b);
+        }
+        self.journal_swap(a, b);
+        for entity in [a, b] {
+            for type_id in self.change_tracker.component_types(entity.entity_id) {
+                if type_ids.contains(&type_id) {
+                    self.mark_changed(entity, type_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    struct Ghost;
+
+    struct Possesses;
+
+    #[test]
+    fn test_swap_all_components() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let root = entities_and_components.add_entity();
+        let hero = entities_and_components.add_entity_with((Health(100), Name("hero")));
+        let ghost = entities_and_components.add_entity_with((Name("ghost"), Ghost));
+        entities_and_components.set_parent(hero, root);
+        entities_and_components.relate::<Possesses>(ghost, hero);
+        let tick = entities_and_components.current_tick();
+
+        entities_and_components.swap_all_components(hero, ghost);
+
+        let (health, name, is_ghost) =
+            entities_and_components.try_get_components::<(Health, Name, Ghost)>(hero);
+        assert!(health.is_none());
+        assert_eq!(*name.unwrap(), Name("ghost"));
+        assert!(is_ghost.is_some());
+        let (health, name, is_ghost) =
+            entities_and_components.try_get_components::<(Health, Name, Ghost)>(ghost);
+        assert_eq!(*health.unwrap(), Health(100));
+        assert_eq!(*name.unwrap(), Name("hero"));
+        assert!(is_ghost.is_none());
+
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Ghost>()
+                .copied()
+                .collect::<Vec<Entity>>(),
+            vec![hero]
+        );
+        assert_eq!(
+            entities_and_components
+                .get_entities_with_component::<Health>()
+                .copied()
+                .collect::<Vec<Entity>>(),
+            vec![ghost]
+        );
+        let mut changed = entities_and_components
+            .changed_since::<Name>(tick)
+            .collect::<Vec<Entity>>();
+        changed.sort();
+        assert_eq!(changed, vec![hero, ghost]);
+
+        // the hierarchy and relationships stay with the handles
+        assert_eq!(entities_and_components.get_parent(hero), Some(root));
+        assert_eq!(entities_and_components.get_parent(ghost), None);
+        assert_eq!(
+            entities_and_components.relations_from::<Possesses>(ghost),
+            &[hero]
+        );
+        assert_eq!(
+            entities_and_components.relations_to::<Possesses>(hero),
+            &[ghost]
+        );
+    }
+
+    #[derive(Default)]
+    struct Stunned;
+
+    #[test]
+    fn test_swap_is_recorded() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+        let hero = entities_and_components.add_entity_with((Health(100),));
+        let ghost = entities_and_components.add_entity_with((Ghost,));
+        entities_and_components.add_temporary_tag::<Stunned>(hero, 5);
+        entities_and_components.track_archetype_transitions();
+        entities_and_components.set_journal_depth(10);
+
+        entities_and_components.swap_all_components(hero, ghost);
+
+        // the countdown moves with the tag
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(hero),
+            None
+        );
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(ghost),
+            Some(5)
+        );
+        let transitions = entities_and_components.drain_archetype_transitions();
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0].0, hero);
+        assert_eq!(transitions[0].1, transitions[1].2);
+        assert_eq!(transitions[0].2, vec![TypeId::of::<Ghost>()]);
+
+        assert!(entities_and_components.undo_last());
+        let (health,) = entities_and_components.try_get_components::<(Health,)>(hero);
+        assert_eq!(*health.unwrap(), Health(100));
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(hero),
+            Some(5)
+        );
+        assert!(!entities_and_components.undo_last());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::*;
+
+/// The component types a system reads and writes, returned by System::access
+/// Built with SystemAccess::new().reads::<Position>().writes::<Velocity>()
+/// The declarations are not checked or used for scheduling, they are only reported by World::access_report
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SystemAccess {
+    // (TypeId::of::<T>(), type name)
+    reads: Vec<(TypeId, &'static str)>,
+    writes: Vec<(TypeId, &'static str)>,
+}
+
+impl SystemAccess {
+    /// Creates an access declaration without any component types
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that the system reads T
+    pub fn reads<T: Component>(mut self) -> Self {
+        self.reads
+            .push((TypeId::of::<T>(), std::any::type_name::<T>()));
+        self
+    }
+
+    /// Declares that the system writes T
+    pub fn writes<T: Component>(mut self) -> Self {
+        self.writes
+            .push((TypeId::of::<T>(), std::any::type_name::<T>()));
+        self
+    }
+
+    /// Checks if the two systems can't safely run at the same time, because one writes something the other reads or writes
+    pub fn conflicts_with(&self, other: &SystemAccess) -> bool {
+        let writes_any = |writes: &[(TypeId, &'static str)],
+                          accesses: &[(TypeId, &'static str)]| {
+            writes
+                .iter()
+                .any(|(type_id, _)| accesses.iter().any(|(other, _)| other == type_id))
+        };
+        writes_any(&self.writes, &other.writes)
+            || writes_any(&self.writes, &other.reads)
+            || writes_any(&other.writes, &self.reads)
+    }
+}
+
+impl World {
+    /// Lists the component types every system declared with System::access, in the order the systems were added,
+    /// as (system name, names of the types it reads, names of the types it writes)
+    /// Two systems that appear with the same type, where at least one writes it, can't run at the same time
+    pub fn access_report(&self) -> Vec<(String, Vec<&'static str>, Vec<&'static str>)> {
+        self.systems
+            .iter()
+            .map(|(system_id, system)| {
+                let access = system.access();
+                let names = |accesses: Vec<(TypeId, &'static str)>| {
+                    accesses
+                        .into_iter()
+                        .map(|(_, name)| name)
+                        .collect::<Vec<&'static str>>()
+                };
+                (
+                    self.system_name(&SystemHandle { system_id })
+                        .unwrap()
+                        .to_string(),
+                    names(access.reads),
+                    names(access.writes),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Position;
+    struct Velocity;
+    struct Health;
+
+    struct MoveSystem {}
+
+    impl System for MoveSystem {
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().reads::<Velocity>().writes::<Position>()
+        }
+    }
+
+    struct KnockbackSystem {}
+
+    impl System for KnockbackSystem {
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().reads::<Health>().writes::<Position>()
+        }
+    }
+
+    struct RegenSystem {}
+
+    impl System for RegenSystem {
+        fn access(&self) -> SystemAccess {
+            SystemAccess::new().writes::<Health>()
+        }
+    }
+
+    #[test]
+    fn test_access_report() {
+        let mut world = World::new();
+        world.add_system_named("move", MoveSystem {});
+        world.add_system_named("knockback", KnockbackSystem {});
+
+        let report = world.access_report();
+        assert_eq!(
+            report,
+            vec![
+                (
+                    "move".to_string(),
+                    vec![std::any::type_name::<Velocity>()],
+                    vec![std::any::type_name::<Position>()]
+                ),
+                (
+                    "knockback".to_string(),
+                    vec![std::any::type_name::<Health>()],
+                    vec![std::any::type_name::<Position>()]
+                ),
+            ]
+        );
+        // both write Position
+        let move_access = System::access(&MoveSystem {});
+        let knockback_access = System::access(&KnockbackSystem {});
+        let regen_access = System::access(&RegenSystem {});
+        assert!(move_access.conflicts_with(&knockback_access));
+        // one writes what the other reads
+        assert!(regen_access.conflicts_with(&knockback_access));
+        assert!(!regen_access.conflicts_with(&move_access));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::*;
+use std::marker::PhantomData;
+
+/// Components that can be advanced by a rate of change every frame, like a position by a velocity
+/// Implement this for your own components to use them with IntegrateSystem
+pub trait Integrable: Component + Send + Sync {
+    /// The component that holds the rate of change, for example Velocity for Position
+    type Rate: Component + Send + Sync;
+
+    /// Advances the component by the rate, called once per frame
+    fn integrate(&mut self, rate: &Self::Rate);
+}
+
+/// A reusable system that integrates C by its rate on every entity that has both
+/// It can be added once for every Integrable component, for example
+/// IntegrateSystem::<Position>::new() and IntegrateSystem::<Rotation>::new()
+pub struct IntegrateSystem<C: Integrable> {
+    component: PhantomData<C>,
+}
+
+impl<C: Integrable> IntegrateSystem<C> {
+    /// Creates a new IntegrateSystem for the component C
+    pub fn new() -> Self {
+        IntegrateSystem {
+            component: PhantomData,
+        }
+    }
+}
+
+impl<C: Integrable> Default for IntegrateSystem<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Integrable> System for IntegrateSystem<C> {
+    fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+        if let (Some(component), Some(rate)) =
+            single_entity.try_get_components_mut::<(C, C::Rate)>()
+        {
+            component.integrate(rate);
+        }
+    }
+
+    fn implements_single_entity_step(&self) -> bool {
+        true
+    }
+}
+
+/// Components with their own per frame logic, like a cooldown counting down
+/// Register them with World::enable_component_ticking to have tick called on every instance every frame
+pub trait TickingComponent: Component + Send + Sync {
+    /// Advances the component by one frame
+    fn tick(&mut self);
+}
+
+/// The system added by World::enable_component_ticking, it ticks every C in the world
+pub struct TickSystem<C: TickingComponent> {
+    component: PhantomData<C>,
+}
+
+impl<C: TickingComponent> System for TickSystem<C> {
+    fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+        if let (Some(component),) = single_entity.try_get_components_mut::<(C,)>() {
+            component.tick();
+        }
+    }
+
+    fn implements_single_entity_step(&self) -> bool {
+        true
+    }
+}
+
+impl World {
+    /// Adds a system that calls tick on every C every frame
+    /// Each call adds another system, so a type should only be registered once
+    pub fn enable_component_ticking<C: TickingComponent>(&mut self) -> SystemHandle {
+        self.add_system(TickSystem::<C> {
+            component: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    struct Velocity {
+        x: f32,
+        y: f32,
+    }
+
+    impl Integrable for Position {
+        type Rate = Velocity;
+
+        fn integrate(&mut self, rate: &Velocity) {
+            self.x += rate.x;
+            self.y += rate.y;
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Rotation(f32);
+
+    struct AngularVelocity(f32);
+
+    impl Integrable for Rotation {
+        type Rate = AngularVelocity;
+
+        fn integrate(&mut self, rate: &AngularVelocity) {
+            self.0 += rate.0;
+        }
+    }
+
+    #[test]
+    fn test_integrate_system_for_two_components() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let mover = entities_and_components
+            .add_entity_with((Position { x: 0.0, y: 0.0 }, Velocity { x: 1.0, y: 2.0 }));
+        let spinner =
+            entities_and_components.add_entity_with((Rotation(0.0), AngularVelocity(0.5)));
+        let still = entities_and_components.add_entity_with((Position { x: 3.0, y: 3.0 },));
+
+        world.add_system(IntegrateSystem::<Position>::new());
+        world.add_system(IntegrateSystem::<Rotation>::new());
+
+        for _ in 0..4 {
+            world.run();
+        }
+
+        let entities_and_components = &world.entities_and_components;
+        let (position,) = entities_and_components.get_components::<(Position,)>(mover);
+        assert_eq!(*position, Position { x: 4.0, y: 8.0 });
+        let (rotation,) = entities_and_components.get_components::<(Rotation,)>(spinner);
+        assert_eq!(*rotation, Rotation(2.0));
+        let (position,) = entities_and_components.get_components::<(Position,)>(still);
+        assert_eq!(*position, Position { x: 3.0, y: 3.0 });
+    }
+
+    struct Cooldown {
+        frames_left: u32,
+    }
+
+    impl TickingComponent for Cooldown {
+        fn tick(&mut self) {
+            self.frames_left = self.frames_left.saturating_sub(1);
+        }
+    }
+
+    #[test]
+    fn test_component_ticking() {
+        let mut world = World::new();
+        let short = world
+            .entities_and_components
+            .add_entity_with((Cooldown { frames_left: 2 },));
+        let long = world
+            .entities_and_components
+            .add_entity_with((Cooldown { frames_left: 5 },));
+        world.enable_component_ticking::<Cooldown>();
+
+        for frame in 1..=3 {
+            world.run();
+            let (cooldown,) = world
+                .entities_and_components
+                .get_components::<(Cooldown,)>(long);
+            assert_eq!(cooldown.frames_left, 5 - frame);
+        }
+        let (cooldown,) = world
+            .entities_and_components
+            .get_components::<(Cooldown,)>(short);
+        assert_eq!(cooldown.frames_left, 0);
+    }
+}
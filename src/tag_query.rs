@@ -0,0 +1,136 @@
+use crate::*;
+
+/// A boolean expression over component types, used with EntitiesAndComponents::select
+/// Built with TagQuery::has, and, and or, for example
+/// TagQuery::has::<Friendly>().and(TagQuery::has::<Airborne>()).or(TagQuery::has::<Neutral>())
+pub struct TagQuery {
+    node: TagQueryNode,
+}
+
+enum TagQueryNode {
+    // the TypeId the component is stored under, Box<T>
+    Has(TypeId),
+    And(Box<TagQuery>, Box<TagQuery>),
+    Or(Box<TagQuery>, Box<TagQuery>),
+}
+
+impl TagQuery {
+    /// Matches entities with the component T
+    pub fn has<T: Component>() -> Self {
+        TagQuery {
+            node: TagQueryNode::Has(TypeId::of::<Box<T>>()),
+        }
+    }
+
+    /// Matches entities matched by both this query and other
+    pub fn and(self, other: TagQuery) -> Self {
+        TagQuery {
+            node: TagQueryNode::And(Box::new(self), Box::new(other)),
+        }
+    }
+
+    /// Matches entities matched by this query, other, or both
+    pub fn or(self, other: TagQuery) -> Self {
+        TagQuery {
+            node: TagQueryNode::Or(Box::new(self), Box::new(other)),
+        }
+    }
+
+    // evaluated by intersecting and unioning the sets of entities with each component
+    fn evaluate(
+        &self,
+        entities_and_components: &EntitiesAndComponents,
+    ) -> SecondaryMap<DefaultKey, Entity> {
+        match &self.node {
+            TagQueryNode::Has(type_id) => entities_and_components
+                .entities_with_components
+                .get(type_id)
+                .cloned()
+                .unwrap_or_default(),
+            TagQueryNode::And(left, right) => {
+                let left = left.evaluate(entities_and_components);
+                let right = right.evaluate(entities_and_components);
+                let (smaller, larger) = if left.len() <= right.len() {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                smaller
+                    .into_iter()
+                    .filter(|(entity_id, _)| larger.contains_key(*entity_id))
+                    .collect()
+            }
+            TagQueryNode::Or(left, right) => {
+                let mut left = left.evaluate(entities_and_components);
+                left.extend(right.evaluate(entities_and_components));
+                left
+            }
+        }
+    }
+}
+
+impl EntitiesAndComponents {
+    /// returns an iterator over all entities matched by the query, except disabled ones
+    pub fn select(&self, query: TagQuery) -> impl Iterator<Item = Entity> + '_ {
+        let disabled = self.disabled_entities();
+        self.select_include_disabled(query)
+            .filter(move |entity| !is_in(disabled, entity))
+    }
+
+    /// returns an iterator over all entities matched by the query, including disabled ones
+    pub fn select_include_disabled(&self, query: TagQuery) -> impl Iterator<Item = Entity> + '_ {
+        query.evaluate(self).into_iter().map(|(_, entity)| entity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    struct C;
+
+    #[test]
+    fn test_select_and_or() {
+        let mut world = World::new();
+        let entities_and_components = &mut world.entities_and_components;
+
+        let a_and_b = entities_and_components.add_entity_with((A, B));
+        let only_a = entities_and_components.add_entity_with((A,));
+        let only_b = entities_and_components.add_entity_with((B,));
+        let only_c = entities_and_components.add_entity_with((C,));
+        let a_and_c = entities_and_components.add_entity_with((A, C));
+        let all = entities_and_components.add_entity_with((A, B, C));
+        entities_and_components.add_entity();
+
+        let query = TagQuery::has::<A>()
+            .and(TagQuery::has::<B>())
+            .or(TagQuery::has::<C>());
+        let mut selected = entities_and_components
+            .select(query)
+            .collect::<Vec<Entity>>();
+        selected.sort();
+        let mut expected = vec![a_and_b, only_c, a_and_c, all];
+        expected.sort();
+        assert_eq!(selected, expected);
+        assert!(!selected.contains(&only_a));
+        assert!(!selected.contains(&only_b));
+
+        entities_and_components.disable_entity(all);
+        assert_eq!(
+            entities_and_components
+                .select(TagQuery::has::<A>().and(TagQuery::has::<C>()))
+                .collect::<Vec<Entity>>(),
+            vec![a_and_c]
+        );
+        assert_eq!(
+            entities_and_components
+                .select_include_disabled(TagQuery::has::<A>().and(TagQuery::has::<C>()))
+                .count(),
+            2
+        );
+    }
+}
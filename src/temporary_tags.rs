@@ -0,0 +1,186 @@
+use crate::*;
+
+// the frames left on every entity's temporary tag of one type
+pub(crate) struct TemporaryTags {
+    pub(crate) frames_left: SecondaryMap<DefaultKey, u32>,
+    // removes the tag from an entity once its frames are up
+    remove: fn(&mut EntitiesAndComponents, Entity),
+}
+
+fn remove_tag<T: Component>(entities_and_components: &mut EntitiesAndComponents, entity: Entity) {
+    entities_and_components.remove_component_from::<T>(entity);
+}
+
+impl EntitiesAndComponents {
+    /// Adds a tag to an entity that is removed again after a number of frames, for example Stunned for 120 frames
+    /// The frames are only counted down while World::enable_temporary_tags has been called
+    /// Adding the tag again restarts its countdown, a tag added for 0 frames is removed on the next frame
+    /// panics if the entity does not exist
+    pub fn add_temporary_tag<T: Component + Default>(&mut self, entity: Entity, frames: u32) {
+        self.add_component_to(entity, T::default());
+        self.temporary_tags
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| TemporaryTags {
+                frames_left: SecondaryMap::new(),
+                remove: remove_tag::<T>,
+            })
+            .frames_left
+            .insert(entity.entity_id, frames);
+    }
+
+    /// Gets how many frames are left before an entity's temporary tag is removed,
+    /// None if the entity has no temporary tag of this type
+    pub fn temporary_tag_frames_left<T: Component>(&self, entity: Entity) -> Option<u32> {
+        self.temporary_tags
+            .get(&TypeId::of::<T>())?
+            .frames_left
+            .get(entity.entity_id)
+            .copied()
+    }
+
+    // counts every temporary tag down by a frame and removes the ones that are up
+    fn tick_temporary_tags(&mut self) {
+        let mut expired = Vec::new();
+        for temporary_tags in self.temporary_tags.values_mut() {
+            temporary_tags.frames_left.retain(|entity_id, frames_left| {
+                if *frames_left <= 1 {
+                    expired.push((temporary_tags.remove, Entity { entity_id }));
+                    return false;
+                }
+                *frames_left -= 1;
+                true
+            });
+        }
+        for (remove, entity) in expired {
+            // the entity may have been removed since the tag was added
+            if self.does_entity_exist(entity) {
+                remove(self, entity);
+            }
+        }
+    }
+
+    // stops the countdown of a tag that was removed, so adding it again normally doesn't remove it
+    pub(crate) fn forget_temporary_tag(&mut self, entity_id: DefaultKey, type_id: TypeId) {
+        if let Some(temporary_tags) = self.temporary_tags.get_mut(&type_id) {
+            temporary_tags.frames_left.remove(entity_id);
+        }
+    }
+
+    pub(crate) fn remap_temporary_tags(&mut self, entity_ids: &EntityIds) {
+        for temporary_tags in self.temporary_tags.values_mut() {
+            remap_keys(&mut temporary_tags.frames_left, entity_ids);
+        }
+    }
+}
+
+/// The system added by World::enable_temporary_tags, it counts down every temporary tag once a frame
+pub struct TemporaryTagSystem {}
+
+impl System for TemporaryTagSystem {
+    fn run(&mut self, engine: &mut EntitiesAndComponents) {
+        engine.tick_temporary_tags();
+    }
+}
+
+impl World {
+    /// Adds the system that counts down the tags added with add_temporary_tag and removes them when they are up
+    /// Enabling it again returns the handle of the system that was already added, so the tags aren't counted down twice
+    pub fn enable_temporary_tags(&mut self) -> SystemHandle {
+        for (system_id, system) in self.systems.iter() {
+            if system.as_any().is::<TemporaryTagSystem>() {
+                return SystemHandle { system_id };
+            }
+        }
+        self.add_system(TemporaryTagSystem {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Stunned;
+
+    #[derive(Default)]
+    struct Invulnerable;
+
+    #[test]
+    fn test_temporary_tag() {
+        let mut world = World::new();
+        world.enable_temporary_tags();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity();
+        let removed = entities_and_components.add_entity();
+        entities_and_components.add_temporary_tag::<Stunned>(entity, 3);
+        entities_and_components.add_temporary_tag::<Invulnerable>(entity, 1);
+        entities_and_components.add_temporary_tag::<Stunned>(removed, 1);
+        entities_and_components.remove_entity(removed);
+
+        for frame in 0..3 {
+            let (stunned,) = world
+                .entities_and_components
+                .try_get_components::<(Stunned,)>(entity);
+            assert!(stunned.is_some(), "the tag was gone in frame {frame}");
+            assert_eq!(
+                world
+                    .entities_and_components
+                    .temporary_tag_frames_left::<Stunned>(entity),
+                Some(3 - frame)
+            );
+            world.run();
+            let (invulnerable,) = world
+                .entities_and_components
+                .try_get_components::<(Invulnerable,)>(entity);
+            assert!(invulnerable.is_none());
+        }
+
+        let (stunned,) = world
+            .entities_and_components
+            .try_get_components::<(Stunned,)>(entity);
+        assert!(stunned.is_none());
+        assert_eq!(
+            world
+                .entities_and_components
+                .temporary_tag_frames_left::<Stunned>(entity),
+            None
+        );
+    }
+
+    #[test]
+    fn test_removed_temporary_tag() {
+        let mut world = World::new();
+        world.enable_temporary_tags();
+        world.enable_temporary_tags();
+        let entities_and_components = &mut world.entities_and_components;
+        let entity = entities_and_components.add_entity();
+        let removed = entities_and_components.add_entity();
+        entities_and_components.add_temporary_tag::<Stunned>(entity, 2);
+        entities_and_components.add_temporary_tag::<Stunned>(removed, 1);
+        entities_and_components.remove_entity(removed);
+        // enabling twice still counts down once a frame
+        world.run();
+        let entities_and_components = &mut world.entities_and_components;
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(entity),
+            Some(1)
+        );
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(removed),
+            None
+        );
+
+        // a tag that was removed and added again normally stays
+        entities_and_components.remove_component_from::<Stunned>(entity);
+        assert_eq!(
+            entities_and_components.temporary_tag_frames_left::<Stunned>(entity),
+            None
+        );
+        entities_and_components.add_component_to(entity, Stunned);
+        world.run();
+        let (stunned,) = world
+            .entities_and_components
+            .try_get_components::<(Stunned,)>(entity);
+        assert!(stunned.is_some());
+    }
+}
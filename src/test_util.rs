@@ -0,0 +1,146 @@
+use crate::*;
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+
+/// A World with conveniences for writing tests, only available with the test-util feature
+/// It derefs to World, so systems can be added and entities_and_components accessed as usual
+/// Like every world, a test sees the same iteration order every run
+pub struct TestWorld {
+    world: World,
+}
+
+impl TestWorld {
+    /// Creates a new empty TestWorld
+    pub fn new() -> Self {
+        TestWorld {
+            world: World::new(),
+        }
+    }
+
+    /// Spawns count entities, each with the components returned by f for its index
+    /// Returns the entities in the order they were spawned
+    pub fn spawn_n<T: OwnedComponents<Input = T>>(
+        &mut self,
+        count: usize,
+        mut f: impl FnMut(usize) -> T,
+    ) -> Vec<Entity> {
+        (0..count)
+            .map(|i| self.world.entities_and_components.add_entity_with(f(i)))
+            .collect()
+    }
+
+    /// Asserts that the entity has the component T and that it is equal to expected
+    pub fn assert_component_eq<T: Component + PartialEq + Debug>(
+        &self,
+        entity: Entity,
+        expected: T,
+    ) {
+        match self
+            .world
+            .entities_and_components
+            .try_get_component::<T>(entity)
+        {
+            Some(component) => assert_eq!(**component, expected),
+            None => panic!(
+                "Entity {entity:?} does not have a {}",
+                std::any::type_name::<T>()
+            ),
+        }
+    }
+
+    /// Runs the world n times
+    pub fn run_frames(&mut self, n: usize) {
+        for _ in 0..n {
+            self.world.run();
+        }
+    }
+
+    /// Gets a copy of the component T from every entity that has it, except disabled ones,
+    /// in the order of get_entities_with_component
+    pub fn component_values<T: Component + Clone>(&self) -> Vec<T> {
+        let entities_and_components = &self.world.entities_and_components;
+        entities_and_components
+            .get_entities_with_component::<T>()
+            .filter_map(|entity| entities_and_components.try_get_component::<T>(*entity))
+            .map(|component| (**component).clone())
+            .collect()
+    }
+}
+
+impl Default for TestWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for TestWorld {
+    type Target = World;
+
+    fn deref(&self) -> &World {
+        &self.world
+    }
+}
+
+impl DerefMut for TestWorld {
+    fn deref_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Velocity {
+        x: f32,
+    }
+
+    struct MovementSystem {}
+
+    impl System for MovementSystem {
+        fn single_entity_step(&self, single_entity: &mut SingleMutEntity) {
+            let (position, velocity) = single_entity.get_components_mut::<(Position, Velocity)>();
+            position.x += velocity.x;
+        }
+
+        fn implements_single_entity_step(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_test_world() {
+        let mut world = TestWorld::new();
+        let entities = world.spawn_n(3, |i| (Position { x: 0.0 }, Velocity { x: i as f32 }));
+        assert_eq!(entities.len(), 3);
+
+        world.add_system(MovementSystem {});
+        world.run_frames(2);
+
+        world.assert_component_eq(entities[0], Position { x: 0.0 });
+        world.assert_component_eq(entities[2], Position { x: 4.0 });
+        assert_eq!(
+            world.component_values::<Position>(),
+            vec![
+                Position { x: 0.0 },
+                Position { x: 2.0 },
+                Position { x: 4.0 }
+            ]
+        );
+        assert_eq!(world.frame(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_component_eq_fails() {
+        let mut world = TestWorld::new();
+        let entities = world.spawn_n(1, |_| (Position { x: 1.0 },));
+        world.assert_component_eq(entities[0], Position { x: 2.0 });
+    }
+}
@@ -0,0 +1,154 @@
+use crate::*;
+
+impl EntitiesAndComponents {
+    /// Moves every entity with the component T, along with all of its components, into dest
+    /// The entities are removed from self and new entities are spawned in dest, nothing is cloned
+    /// Returns pairs of (the entity in self, the entity in dest)
+    /// Entities can't keep their parents and children in another world, so they are detached from them first
+    /// panics if dest reaches its maximum number of entities
+    pub fn transfer_with<T: Component>(
+        &mut self,
+        dest: &mut EntitiesAndComponents,
+    ) -> Vec<(Entity, Entity)> {
+        if self.frozen_mutation() {
+            return Vec::new();
+        }
+        let entities = self
+            .get_entities_with_component_include_disabled::<T>()
+            .copied()
+            .collect::<Vec<Entity>>();
+
+        entities
+            .into_iter()
+            .map(|entity| (entity, self.transfer_entity(entity, dest)))
+            .collect()
+    }
+
+    fn transfer_entity(&mut self, entity: Entity, dest: &mut EntitiesAndComponents) -> Entity {
+        // spawn first, so nothing is lost if dest is full
+        let new_entity = dest.add_entity();
+
+        self.remove_parent(entity);
+        self.remove_all_children(entity);
+        trace_structural_change!(entity = ?entity.entity_id, "despawn");
+        self.count_disabled_entity(entity, false);
+        let mut components = self.components.remove(entity.entity_id).unwrap_or_else(|| {
+            panic!("Entity ID {entity:?} does not exist, was the Entity ID edited?");
+        });
+        // stable ids and ref counts belong to the world they were set in
+        self.forget_entity(entity.entity_id);
+        // the children stay behind, so the entity leaves the index of Children too
+        if components.remove::<Box<Children>>().is_some() {
+            if let Some(entities) = self
+                .entities_with_components
+                .get_mut(&TypeId::of::<Box<Children>>())
+            {
+                entities.remove(entity.entity_id);
+            }
+        }
+        // relationships can't point into another world either
+        self.forget_relations(entity, &mut components);
+
+        for type_id in components.as_raw().keys() {
+            if let Some(entities) = self.entities_with_components.get_mut(type_id) {
+                entities.remove(entity.entity_id);
+            }
+
+            dest.entities_with_components
+                .entry(*type_id)
+                .or_default()
+                .insert(new_entity.entity_id, new_entity);
+            if let Some(component_type_id) = self.component_type_ids.get(type_id) {
+                dest.component_type_ids.insert(*type_id, *component_type_id);
+            }
+            if let Some(component_type_name) = self.component_type_names.get(type_id) {
+                dest.component_type_names
+                    .insert(*type_id, *component_type_name);
+            }
+            if let Some(accessor) = self.component_accessors.get(type_id) {
+                dest.component_accessors.insert(*type_id, *accessor);
+            }
+            if let Some(restorer) = self.component_restorers.get(type_id) {
+                dest.component_restorers.insert(*type_id, *restorer);
+            }
+            dest.change_tracker
+                .component_added(new_entity.entity_id, *type_id);
+            dest.component_masks.component_added(
+                new_entity.entity_id,
+                *type_id,
+                &dest.linked_components,
+            );
+        }
+        self.archetype_left(&components);
+        dest.components[new_entity.entity_id] = components;
+        dest.count_disabled_entity(new_entity, true);
+        dest.archetype_entered(new_entity);
+        if !dest.pending_inits.is_empty() {
+            for type_id in dest.components[new_entity.entity_id]
+                .as_raw()
+                .keys()
+                .copied()
+                .collect::<Vec<TypeId>>()
+            {
+                dest.queue_initializers(new_entity, type_id);
+            }
+        }
+
+        new_entity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct InZoneB;
+
+    // not Clone, so it has to be moved
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn test_transfer_with() {
+        let mut zone_a = World::new();
+        let mut zone_b = World::new();
+        let source = &mut zone_a.entities_and_components;
+        let dest = &mut zone_b.entities_and_components;
+        dest.track_archetype_transitions();
+
+        let leaving = (0..3)
+            .map(|i| source.add_entity_with((InZoneB, Health(i))))
+            .collect::<Vec<Entity>>();
+        let staying = source.add_entity_with((Health(10),));
+        // a child of a leaving entity stays behind
+        let child = source.add_entity_with((Health(11),));
+        source.set_parent(child, leaving[0]);
+
+        let transferred = source.transfer_with::<InZoneB>(dest);
+        assert_eq!(transferred.len(), 3);
+
+        assert_eq!(source.get_entity_count(), 2);
+        assert_eq!(source.get_entity_count_with_component::<InZoneB>(), 0);
+        assert_eq!(source.get_entity_count_with_component::<Health>(), 2);
+        assert!(source.does_entity_exist(staying));
+        assert_eq!(source.get_parent(child), None);
+        assert_eq!(source.get_entity_count_with_component::<Children>(), 0);
+        for entity in &leaving {
+            assert!(!source.does_entity_exist(*entity));
+        }
+
+        assert_eq!(dest.get_entity_count(), 3);
+        // every moved entity gets its components like a spawned one
+        let transitions = dest.drain_archetype_transitions();
+        assert_eq!(transitions.len(), 6);
+        assert!(transitions
+            .iter()
+            .all(|(_, before, after)| after.len() == before.len() + 1));
+        assert_eq!(dest.get_entity_count_with_component::<InZoneB>(), 3);
+        for (i, (old_entity, new_entity)) in transferred.into_iter().enumerate() {
+            assert_eq!(old_entity, leaving[i]);
+            let (health,) = dest.get_components::<(Health,)>(new_entity);
+            assert_eq!(*health, Health(i as u32));
+        }
+    }
+}